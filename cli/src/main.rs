@@ -0,0 +1,203 @@
+//! A command line interface to the world of hw-architect, which runs without a window or a gpu.
+//! It checks, measures, exports and edits save games, such that designs can be handled by scripts
+//! on machines that have no display. Everything it needs is built into the binary, so it can be
+//! run from any directory.
+mod script;
+
+use world::{SaveError, SaveFormat, World};
+use world_api::RoadQuery;
+use world_io::{GeoJsonOptions, GeoOrigin, OpenDriveOptions, SumoOptions};
+
+use anyhow::{bail, Context};
+use clap::{Parser, Subcommand, ValueEnum};
+
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+
+#[derive(Parser)]
+#[command(name = "hw-architect-cli", version, about)]
+struct Cli {
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Checks that the road graph of a save game is valid, and prints every violation if not.
+    Validate {
+        /// The save game to check.
+        save: PathBuf,
+    },
+    /// Prints statistics of the road network of a save game.
+    Stats {
+        /// The save game to measure.
+        save: PathBuf,
+    },
+    /// Writes the road network of a save game in the format of another tool.
+    Export {
+        /// The save game to export.
+        save: PathBuf,
+        /// The format to write.
+        #[arg(short, long, value_enum)]
+        format: ExportFormat,
+        /// The file to write. For SUMO this is the prefix of the node, edge and connection files.
+        output: PathBuf,
+        /// The longitude and latitude of the origin of the world, as `<lon>,<lat>`. GeoJSON and
+        /// OpenDRIVE files are georeferenced around it.
+        #[arg(long, value_parser = parse_origin)]
+        origin: Option<GeoOrigin>,
+        /// Writes the center line of every lane instead of the spine of every segment to GeoJSON.
+        #[arg(long)]
+        lanes: bool,
+        /// The speed limit in meters per second of every road written to SUMO.
+        #[arg(long)]
+        speed: Option<f32>,
+    },
+    /// Applies the edits of a script to a save game. Nothing is written if any edit fails.
+    #[command(after_long_help = script::HELP)]
+    Edit {
+        /// The script of edits to apply.
+        script: PathBuf,
+        /// The save game to edit. An empty world is edited if it is left out.
+        #[arg(short, long)]
+        input: Option<PathBuf>,
+        /// Where to write the edited world. Defaults to the input save game.
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+        /// Writes the save game as RON, which is readable but large, instead of binary.
+        #[arg(long)]
+        ron: bool,
+    },
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum ExportFormat {
+    Geojson,
+    Opendrive,
+    Sumo,
+    Gltf,
+    Obj,
+}
+
+fn main() -> ExitCode {
+    let cli = Cli::parse();
+    match run(cli.command) {
+        Ok(true) => ExitCode::SUCCESS,
+        Ok(false) => ExitCode::FAILURE,
+        Err(err) => {
+            eprintln!("error: {:#}", err);
+            ExitCode::FAILURE
+        }
+    }
+}
+
+/// Runs the command, and returns whether the save game passed the checks of the command.
+fn run(command: Command) -> anyhow::Result<bool> {
+    match command {
+        Command::Validate { save } => validate(&save),
+        Command::Stats { save } => {
+            let world = load(&save)?;
+            println!("{}", world.compute_stats());
+            Ok(true)
+        }
+        Command::Export {
+            save,
+            format,
+            output,
+            origin,
+            lanes,
+            speed,
+        } => {
+            let world = load(&save)?;
+            match format {
+                ExportFormat::Geojson => {
+                    world_io::export_geojson(&world, &output, &GeoJsonOptions { origin, lanes })?
+                }
+                ExportFormat::Opendrive => {
+                    world_io::export_opendrive(&world, &output, &OpenDriveOptions { origin })?
+                }
+                ExportFormat::Sumo => {
+                    let default = SumoOptions::default();
+                    let options = SumoOptions {
+                        speed: speed.unwrap_or(default.speed),
+                    };
+                    world_io::export_sumo(&world, &output, &options)?
+                }
+                ExportFormat::Gltf => tool::export_gltf(&world, &output)?,
+                ExportFormat::Obj => tool::export_obj(&world, &output)?,
+            }
+            Ok(true)
+        }
+        Command::Edit {
+            script,
+            input,
+            output,
+            ron,
+        } => {
+            let Some(output) = output.or(input.clone()) else {
+                bail!("An output path is required when no input save game is given");
+            };
+            let mut world = match input {
+                Some(input) => load(&input)?,
+                None => World::new(),
+            };
+            let text = std::fs::read_to_string(&script)
+                .with_context(|| format!("Could not read {}", script.display()))?;
+            script::run_script(&mut world, &text)
+                .with_context(|| format!("Could not apply {}", script.display()))?;
+
+            let violations = world.validate();
+            if !violations.is_empty() {
+                for violation in violations {
+                    eprintln!("{}", violation);
+                }
+                bail!("The edited road graph is invalid");
+            }
+            let format = if ron {
+                SaveFormat::Ron
+            } else {
+                SaveFormat::Binary
+            };
+            world
+                .save(&output, format)
+                .with_context(|| format!("Could not save {}", output.display()))?;
+            Ok(true)
+        }
+    }
+}
+
+fn load(path: &Path) -> anyhow::Result<World> {
+    World::load(path).with_context(|| format!("Could not load {}", path.display()))
+}
+
+/// Loading a save game already validates it, so the violations are taken from the error.
+fn validate(path: &Path) -> anyhow::Result<bool> {
+    match World::load(path) {
+        Ok(world) => {
+            println!(
+                "{}: valid, {} nodes and {} segments",
+                path.display(),
+                world.nodes().count(),
+                world.segments().count()
+            );
+            Ok(true)
+        }
+        Err(SaveError::InvalidGraph(violations)) => {
+            println!("{}: {} violations", path.display(), violations.len());
+            for violation in violations {
+                println!("  {}", violation);
+            }
+            Ok(false)
+        }
+        Err(err) => Err(err).with_context(|| format!("Could not load {}", path.display())),
+    }
+}
+
+fn parse_origin(arg: &str) -> Result<GeoOrigin, String> {
+    let parse = |value: Option<&str>| -> Option<f64> { value?.trim().parse().ok() };
+    let mut values = arg.split(',');
+    match (parse(values.next()), parse(values.next()), values.next()) {
+        (Some(lon), Some(lat), None) => Ok(GeoOrigin::new(lon, lat)),
+        _ => Err("expected <lon>,<lat> in degrees".to_string()),
+    }
+}
@@ -0,0 +1,281 @@
+//! Edit scripts change a world one command at a time. Every line holds a single command followed
+//! by its arguments separated by whitespace, and everything after a `#` is a comment. The script
+//! stops at the first command that fails, such that a broken script never leaves a half edited
+//! world behind.
+use world::World;
+use world_api::{
+    HistoryManipulator, LNodeBuilderType, LRoadBuilder, LSegmentBuilder, LaneWidth, NodeType,
+    RoadGenErr, RoadManipulator, RoadQuery, Side, SnapConfig, Tree, TreeManipulator,
+};
+use world_io::OsmOptions;
+
+use curves::{Curve, CurveShared, CurveSum, Straight};
+use utils::consts::{MAX_NO_LANES, ROAD_MIN_LENGTH, TREE_MODEL_ID};
+use utils::math::{DirXZ, Loc};
+
+use anyhow::{bail, Context};
+use glam::{Vec3, Vec3Swizzles};
+
+use std::fmt::Display;
+use std::path::Path;
+
+/// The commands of edit scripts, shown in the help of the edit command.
+pub const HELP: &str = "\
+Script commands, one per line. Positions are given as <x> <z> in meters, with x pointing east and
+z pointing south, and lie at most 100 km from the origin. Ids are the ones shown in exported files.
+
+  road <x1> <z1> <x2> <z2> [lanes] [narrow|standard|wide]
+      Builds a straight one-way road of at least 10 meters driving from the first to the second
+      position. Each end connects to an existing node that lies there and points the same way.
+  remove-segment <segment>
+  remove-node <node>          Removes the node and every segment attached to it.
+  split-segment <segment> <meters>
+      Splits the segment in two the given distance along it.
+  merge-segments <node>       Joins the two segments of a node that splitting would create.
+  tree <x> <z>
+  remove-tree <tree>
+  min-clearance <meters>      Sets the vertical distance that crossing roads must keep.
+  import-osm <path>           Imports the roads of an OpenStreetMap XML file.
+  undo
+  redo";
+
+const COMMANDS: [&str; 11] = [
+    "road",
+    "remove-segment",
+    "remove-node",
+    "split-segment",
+    "merge-segments",
+    "tree",
+    "remove-tree",
+    "min-clearance",
+    "import-osm",
+    "undo",
+    "redo",
+];
+
+/// An existing node can be snapped to if the end of a road is closer to it than this.
+const SNAP_DISTANCE: f32 = 0.5;
+
+/// Positions are at most this many meters from the origin in either direction.
+const MAX_COORDINATE: f32 = 100_000.0;
+
+/// Applies every command of the script to the world, in order.
+pub fn run_script(world: &mut World, script: &str) -> anyhow::Result<()> {
+    for (i, line) in script.lines().enumerate() {
+        let line = line.split('#').next().unwrap_or_default();
+        let mut args = line.split_whitespace();
+        let Some(command) = args.next() else {
+            continue;
+        };
+        let args: Vec<&str> = args.collect();
+        run_command(world, command, &args).with_context(|| format!("Line {}", i + 1))?;
+    }
+    Ok(())
+}
+
+fn run_command(world: &mut World, command: &str, args: &[&str]) -> anyhow::Result<()> {
+    match (command, args) {
+        ("road", [x1, z1, x2, z2, rest @ ..]) if rest.len() <= 2 => {
+            let from = parse_pos(x1, z1)?;
+            let to = parse_pos(x2, z2)?;
+            let no_lanes = rest
+                .first()
+                .map_or(Ok(NodeType::default().no_lanes()), parse)?;
+            if no_lanes == 0 || no_lanes > MAX_NO_LANES {
+                bail!("A road has between 1 and {} lanes", MAX_NO_LANES);
+            }
+            let lane_width = rest
+                .get(1)
+                .map_or(Ok(LaneWidth::default()), parse_lane_width)?;
+            add_straight_road(world, from, to, NodeType::new(lane_width, no_lanes))
+        }
+        ("remove-segment", [id]) => {
+            let id = find_id(world.segments().map(|s| s.id()), id, "segment")?;
+            if !world.remove_segment(id) {
+                bail!("Segment {} cannot be removed", id);
+            }
+            Ok(())
+        }
+        ("remove-node", [id]) => {
+            let id = find_id(world.nodes().map(|n| n.id()), id, "node")?;
+            if world.remove_node(id).is_none() {
+                bail!("Node {} cannot be removed", id);
+            }
+            Ok(())
+        }
+        ("split-segment", [id, length]) => {
+            let id = find_id(world.segments().map(|s| s.id()), id, "segment")?;
+            if world.split_segment(id, parse(length)?).is_none() {
+                bail!("Segment {} cannot be split there", id);
+            }
+            Ok(())
+        }
+        ("merge-segments", [id]) => {
+            let id = find_id(world.nodes().map(|n| n.id()), id, "node")?;
+            if world.merge_segments(id).is_none() {
+                bail!("The segments of node {} cannot be merged", id);
+            }
+            Ok(())
+        }
+        ("tree", [x, z]) => {
+            let pos = parse_pos(x, z)?;
+            world.add_tree(Tree::new(pos), TREE_MODEL_ID);
+            Ok(())
+        }
+        ("remove-tree", [id]) => {
+            let trees = world.get_trees();
+            let ids = trees
+                .iter()
+                .flat_map(|(_, trees)| trees.iter().map(|(id, _)| *id));
+            let id = find_id(ids, id, "tree")?;
+            world.remove_tree(id);
+            Ok(())
+        }
+        ("min-clearance", [clearance]) => {
+            let clearance = parse(clearance)?;
+            if !world.set_min_clearance(clearance) {
+                bail!("{} is not a valid clearance", clearance);
+            }
+            Ok(())
+        }
+        ("import-osm", [path]) => {
+            let report = world_io::import_osm(world, Path::new(path), &OsmOptions::default())?;
+            for (way, err) in report.skipped {
+                eprintln!("Skipped a road of way {}: {}", way, describe_err(&err));
+            }
+            Ok(())
+        }
+        ("undo", []) => {
+            if !world.undo() {
+                bail!("There is nothing to undo");
+            }
+            Ok(())
+        }
+        ("redo", []) => {
+            if !world.redo() {
+                bail!("There is nothing to redo");
+            }
+            Ok(())
+        }
+        _ if COMMANDS.contains(&command) => bail!("Wrong arguments for {}", command),
+        _ => bail!("Unknown command {}", command),
+    }
+}
+
+/// Adds a straight road driving from `from` to `to`. An end that lies on an existing node that
+/// points the same way as the road is snapped to it, in which case the road starts or ends exactly
+/// at the node.
+fn add_straight_road(
+    world: &mut World,
+    from: Vec3,
+    to: Vec3,
+    node_type: NodeType,
+) -> anyhow::Result<()> {
+    if (to - from).xz().length() < ROAD_MIN_LENGTH {
+        bail!("A road is at least {} meters long", ROAD_MIN_LENGTH);
+    }
+    let dir = DirXZ::from(to - from);
+    let first_snap = find_snap(world, Loc::new(from, dir), node_type, Side::Out);
+    let last_snap = find_snap(world, Loc::new(to, dir), node_type, Side::In);
+
+    let snap_loc = |snap: &SnapConfig| Loc::new(snap.pos(), snap.dir());
+    let curve = match (
+        first_snap.as_ref().map(snap_loc),
+        last_snap.as_ref().map(snap_loc),
+    ) {
+        (Some(first), Some(last)) => Curve::<Straight>::from_both_locked(first, last)
+            .ok()
+            .context("The nodes at the ends of the road do not line up")?,
+        (Some(first), None) => Curve::<Straight>::from_first_locked(first, to).0,
+        (None, Some(last)) => {
+            // The start is moved onto the line through the node, such that the road points
+            // exactly the same way as the node.
+            let from = last.pos - *last.dir * (last.pos - from).dot(*last.dir);
+            Curve::<Straight>::from_last_locked(from, last)
+                .ok()
+                .context("The road is too short")?
+        }
+        (None, None) => Curve::<Straight>::from_free(from, to).0,
+    };
+    let curve: CurveSum = curve.into();
+
+    let first_type = first_snap.as_ref().map_or(node_type, |s| s.node_type());
+    let last_type = last_snap.as_ref().map_or(node_type, |s| s.node_type());
+    let nodes = vec![
+        match first_snap {
+            Some(snap) => LNodeBuilderType::Old(snap),
+            None => LNodeBuilderType::new(curve.first(), node_type),
+        },
+        match last_snap {
+            Some(snap) => LNodeBuilderType::Old(snap),
+            None => LNodeBuilderType::new(curve.last(), node_type),
+        },
+    ];
+    let segments = vec![LSegmentBuilder::transition(first_type, last_type, curve)];
+    match world.add_road(LRoadBuilder::new(nodes, segments, false), node_type) {
+        Ok(_) => Ok(()),
+        Err(err) => bail!("{}", describe_err(&err)),
+    }
+}
+
+/// Describes why a road could not be built, with ids written the same way as in exported files.
+fn describe_err(err: &RoadGenErr) -> String {
+    match err {
+        RoadGenErr::Collision(ids) => {
+            let ids: Vec<String> = ids.iter().map(|id| id.to_string()).collect();
+            format!("The road overlaps the segments {}", ids.join(", "))
+        }
+        RoadGenErr::CurveFitFailed => "No curves could be fitted to the road".to_string(),
+        RoadGenErr::Placeholder | RoadGenErr::CCSFailed | RoadGenErr::DoubleSnapFailed => {
+            "The road could not be built".to_string()
+        }
+    }
+}
+
+/// Returns the snap config of the node at the location, where a road with the same direction and
+/// lane width can connect on the given side.
+fn find_snap(world: &World, loc: Loc, node_type: NodeType, side: Side) -> Option<SnapConfig> {
+    let (_, configs) = world.get_snap_configs_closest_node(loc.pos, node_type)?;
+    configs.into_iter().find(|snap| {
+        snap.side() == side
+            && snap.node_type().lane_width() == node_type.lane_width()
+            && snap.dir() == loc.dir
+            && (snap.pos() - loc.pos).xz().length() < SNAP_DISTANCE
+    })
+}
+
+/// Returns the id among the given ids that is written as `arg`.
+fn find_id<I: Display>(ids: impl Iterator<Item = I>, arg: &str, kind: &str) -> anyhow::Result<I> {
+    let mut ids = ids;
+    ids.find(|id| id.to_string() == arg)
+        .with_context(|| format!("There is no {} {}", kind, arg))
+}
+
+/// Parses a number. Numbers that are not finite, such as `NaN` and `inf`, are rejected.
+fn parse<T: std::str::FromStr + Into<f64> + Copy>(arg: &&str) -> anyhow::Result<T> {
+    arg.parse()
+        .ok()
+        .filter(|value: &T| (*value).into().is_finite())
+        .with_context(|| format!("{} is not a valid number", arg))
+}
+
+/// Parses the position on the ground at the given x and z coordinates.
+fn parse_pos(x: &&str, z: &&str) -> anyhow::Result<Vec3> {
+    let (x, z): (f32, f32) = (parse(x)?, parse(z)?);
+    if x.abs() > MAX_COORDINATE || z.abs() > MAX_COORDINATE {
+        bail!(
+            "Positions are at most {} meters from the origin",
+            MAX_COORDINATE
+        );
+    }
+    Ok(Vec3::new(x, 0.0, z))
+}
+
+fn parse_lane_width(arg: &&str) -> anyhow::Result<LaneWidth> {
+    match *arg {
+        "narrow" => Ok(LaneWidth::Narrow),
+        "standard" => Ok(LaneWidth::Standard),
+        "wide" => Ok(LaneWidth::Wide),
+        _ => bail!("{} is not a lane width", arg),
+    }
+}
@@ -23,6 +23,19 @@ pub fn get_config_dir() -> std::path::PathBuf {
     path
 }
 
+/// Returns the path of the save game that is written and read with the save and load actions:
+///
+/// Linux: /home/Alice/.local/share/hw-architect/quicksave.hwas
+/// Windows: C:\Users\Alice\AppData\Roaming\simaflux\hw-architect\data\quicksave.hwas
+/// Mac: /Users/Alice/Library/Application Support/com.simaflux.hw-architect/quicksave.hwas
+pub fn get_save_path() -> std::path::PathBuf {
+    let mut path = ProjectDirs::from("com", "simaflux", "hw_architect")
+        .map(|dir| dir.data_dir().to_path_buf())
+        .expect("no valid home directory found using the projectdirs crate, can't use data dir");
+    path.push("quicksave.hwas");
+    path
+}
+
 /// Configuration of the window.
 #[derive(Debug, PartialEq, Serialize, Deserialize, Clone)]
 pub struct WindowConfig {
@@ -1,8 +1,11 @@
 use super::camera_controller::CameraController;
+use super::config;
 
 use gfx_api::GfxSuper;
 use tool::ToolHandler;
 use utils::input::{self, InputState};
+use world::{SaveFormat, World};
+use world_api::RoadQuery;
 
 use glam::*;
 
@@ -13,7 +16,7 @@ pub struct State<G: GfxSuper> {
     window_width: u32,
     window_height: u32,
     camera_controller: CameraController,
-    tool: ToolHandler<G, world::World>,
+    tool: ToolHandler<G, World>,
     input_state: InputState,
     ground_pos: Vec3,
 }
@@ -27,7 +30,7 @@ impl<G: GfxSuper> State<G> {
             100.0,
         );
 
-        let world = world::World::new();
+        let world = World::new();
         let tool = ToolHandler::new(&mut gfx_handle, world);
 
         Self {
@@ -42,10 +45,47 @@ impl<G: GfxSuper> State<G> {
     }
 
     pub fn key_input(&mut self, action: input::KeyAction) {
+        use input::Action::*;
+        use input::KeyState::*;
+        match action {
+            (SaveGame, Press) => self.save_game(),
+            (LoadGame, Press) => self.load_game(),
+            (PrintStats, Press) => {
+                log::info!("Road network:\n{}", self.tool.get_world().compute_stats())
+            }
+            _ => {}
+        }
         self.camera_controller.process_keyboard(action);
         self.tool.process_keyboard(&mut self.gfx_handle, action);
     }
 
+    /// Writes the current world to the save game file.
+    fn save_game(&mut self) {
+        let path = config::get_save_path();
+        if let Some(dir) = path.parent() {
+            if let Err(err) = std::fs::create_dir_all(dir) {
+                log::error!("Could not create save game directory: {}", err);
+                return;
+            }
+        }
+        match self.tool.get_world().save(&path, SaveFormat::Binary) {
+            Ok(()) => log::info!("Saved game to {}", path.display()),
+            Err(err) => log::error!("Could not save game: {}", err),
+        }
+    }
+
+    /// Replaces the current world with the one in the save game file, if it can be loaded.
+    fn load_game(&mut self) {
+        let path = config::get_save_path();
+        match World::load(&path) {
+            Ok(world) => {
+                self.tool.replace_world(&mut self.gfx_handle, world);
+                log::info!("Loaded game from {}", path.display());
+            }
+            Err(err) => log::error!("Could not load game: {}", err),
+        }
+    }
+
     pub fn mouse_input(&mut self, event: input::MouseEvent) {
         self.camera_controller.process_mouse(event);
         match event {
@@ -64,7 +104,7 @@ impl<G: GfxSuper> State<G> {
         if self.camera_controller.update_camera(dt) {
             self.update_ground_pos();
         }
-        self.tool.update(dt);
+        self.tool.update(&mut self.gfx_handle, dt);
         self.gfx_handle
             .update_camera(self.camera_controller.get_raw_camera());
         self.gfx_handle.update(dt);
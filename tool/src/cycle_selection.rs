@@ -1,5 +1,5 @@
 use utils::{consts, input::ScrollState};
-use world_api::LaneWidth;
+use world_api::{LaneWidth, MedianWidth};
 
 use crate::tool_state::CurveType;
 
@@ -33,6 +33,24 @@ impl CycleSelection for LaneWidth {
     }
 }
 
+impl CycleSelection for MedianWidth {
+    fn prev(&self) -> Self {
+        match self {
+            Self::None => Self::Wide,
+            Self::Narrow => Self::None,
+            Self::Wide => Self::Narrow,
+        }
+    }
+
+    fn next(&self) -> Self {
+        match self {
+            Self::None => Self::Narrow,
+            Self::Narrow => Self::Wide,
+            Self::Wide => Self::None,
+        }
+    }
+}
+
 impl CycleSelection for CurveType {
     fn prev(&self) -> Self {
         match self {
@@ -1 +1,3 @@
+pub mod mesh_export;
 pub mod segment_gen;
+pub mod world_gen;
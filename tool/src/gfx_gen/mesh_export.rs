@@ -0,0 +1,443 @@
+//! Writes the meshes of the road network and the trees to files that 3D modelling tools such as
+//! Blender can open, for presentation renders of a design. The road meshes are the same as the
+//! ones that are shown in the game, with the asphalt and the lane markings as separate materials.
+//! Every segment and every tree is its own object, and all trees share the mesh of the tree model.
+//!
+//! Positions are written in the coordinates of the world, which are in meters with y pointing up.
+use super::world_gen;
+
+use gfx_api::colors::{RGBColor, ASPHALT_COLOR, LANE_MARKINGS_COLOR};
+use gfx_api::RoadMesh;
+use world_api::WorldManipulator;
+
+use glam::{Quat, Vec3};
+use serde_json::{json, Value};
+use thiserror::Error;
+
+use std::fs::File;
+use std::io::{BufReader, BufWriter, Cursor, Write};
+use std::path::Path;
+
+/// The name of the model that trees are drawn with.
+const TREE_MODEL: &str = "tree_test";
+
+/// The model of the trees is only textured, so each of its materials is given a plain color in
+/// the order that they appear in the model.
+const TREE_COLORS: [RGBColor; 2] = [[0.35, 0.22, 0.12], [0.18, 0.42, 0.15]];
+
+const ASPHALT: usize = 0;
+const LANE_MARKINGS: usize = 1;
+
+const GLB_MAGIC: u32 = 0x4654_6C67;
+const GLB_JSON_CHUNK: u32 = 0x4E4F_534A;
+const GLB_BIN_CHUNK: u32 = 0x004E_4942;
+const ARRAY_BUFFER: u32 = 34962;
+const ELEMENT_ARRAY_BUFFER: u32 = 34963;
+const FLOAT: u32 = 5126;
+const UNSIGNED_INT: u32 = 5125;
+
+#[derive(Error, Debug)]
+pub enum MeshExportError {
+    /// The exported file could not be written.
+    #[error("Could not write the exported file")]
+    Io(#[from] std::io::Error),
+
+    /// The glTF description of the scene could not be encoded as JSON.
+    #[error("The JSON encoding failed: {0}")]
+    Json(#[from] serde_json::Error),
+
+    /// The model of the trees could not be loaded from the resources.
+    #[error("Could not load the tree model: {0}")]
+    Model(String),
+}
+
+pub type MeshExportResult = Result<(), MeshExportError>;
+
+struct Material {
+    name: String,
+    color: RGBColor,
+}
+
+/// The part of a mesh that has a single material.
+struct Primitive {
+    material: usize,
+    positions: Vec<[f32; 3]>,
+    normals: Vec<[f32; 3]>,
+    indices: Vec<u32>,
+}
+
+struct Mesh {
+    name: String,
+    primitives: Vec<Primitive>,
+}
+
+/// An object of the scene, which places a mesh in the world.
+struct Node {
+    name: String,
+    mesh: usize,
+    translation: Vec3,
+    rotation: Quat,
+}
+
+struct Scene {
+    materials: Vec<Material>,
+    meshes: Vec<Mesh>,
+    nodes: Vec<Node>,
+}
+
+/// Writes the roads and trees of the world to the given path as a binary glTF 2.0 file, which
+/// usually has the extension `.glb`.
+pub fn export_gltf<W: WorldManipulator>(world: &W, path: &Path) -> MeshExportResult {
+    let scene = gen_scene(world)?;
+    let mut writer = BufWriter::new(File::create(path)?);
+    write_glb(&scene, &mut writer)?;
+    writer.flush()?;
+    Ok(())
+}
+
+/// Writes the roads and trees of the world to the given path as a Wavefront OBJ file. The
+/// materials are written next to it, to a file with the same name and the extension `.mtl`.
+pub fn export_obj<W: WorldManipulator>(world: &W, path: &Path) -> MeshExportResult {
+    let scene = gen_scene(world)?;
+    let mtl_path = path.with_extension("mtl");
+    let mtl_name = mtl_path
+        .file_name()
+        .map_or("materials.mtl".to_string(), |n| n.to_string_lossy().into());
+
+    let mut writer = BufWriter::new(File::create(path)?);
+    write_obj(&scene, &mtl_name, &mut writer)?;
+    writer.flush()?;
+    let mut writer = BufWriter::new(File::create(&mtl_path)?);
+    write_mtl(&scene, &mut writer)?;
+    writer.flush()?;
+    Ok(())
+}
+
+fn gen_scene<W: WorldManipulator>(world: &W) -> Result<Scene, MeshExportError> {
+    let mut scene = Scene {
+        materials: vec![
+            Material {
+                name: "asphalt".to_string(),
+                color: ASPHALT_COLOR,
+            },
+            Material {
+                name: "lane_markings".to_string(),
+                color: LANE_MARKINGS_COLOR,
+            },
+        ],
+        meshes: vec![],
+        nodes: vec![],
+    };
+
+    // Segments are written in the order of their ids, such that exports of the same world are
+    // identical.
+    let road_meshes = world_gen::gen_segment_meshes(world, world.get_segment_ids());
+    for (id, road_mesh) in road_meshes.iter() {
+        let name = format!("segment_{}", id);
+        scene.nodes.push(Node {
+            name: name.clone(),
+            mesh: scene.meshes.len(),
+            translation: Vec3::ZERO,
+            rotation: Quat::IDENTITY,
+        });
+        scene.meshes.push(road_mesh_to_mesh(name, road_mesh));
+    }
+
+    let trees: Vec<_> = world
+        .get_trees()
+        .into_iter()
+        .flat_map(|(_, trees)| trees)
+        .collect();
+    if !trees.is_empty() {
+        let tree_mesh = scene.meshes.len();
+        let (mesh, mut materials) = load_tree_model(scene.materials.len())?;
+        scene.meshes.push(mesh);
+        scene.materials.append(&mut materials);
+        for (id, tree) in trees {
+            scene.nodes.push(Node {
+                name: format!("tree_{}", id),
+                mesh: tree_mesh,
+                translation: tree.pos(),
+                rotation: Quat::from_rotation_y(tree.yrot()),
+            });
+        }
+    }
+    Ok(scene)
+}
+
+fn road_mesh_to_mesh(name: String, road_mesh: &RoadMesh) -> Mesh {
+    let primitives = [
+        (ASPHALT, &road_mesh.vertices, &road_mesh.indices),
+        (
+            LANE_MARKINGS,
+            &road_mesh.lane_vertices,
+            &road_mesh.lane_indices,
+        ),
+    ]
+    .into_iter()
+    .filter(|(_, _, indices)| !indices.is_empty())
+    .map(|(material, positions, indices)| Primitive {
+        material,
+        positions: positions.clone(),
+        normals: vec![],
+        indices: indices.clone(),
+    })
+    .collect();
+    Mesh { name, primitives }
+}
+
+/// The model that trees are drawn with. It is built into the binary, such that exports work from
+/// any directory and without the resources of the app.
+const TREE_OBJ: &str = include_str!("../../../res/models/tree_test/tree_test.obj");
+const TREE_MTL: &str = include_str!("../../../res/models/tree_test/tree_test.mtl");
+
+/// Loads the model that trees are drawn with. The materials of the model are numbered from the
+/// given index.
+fn load_tree_model(first_material: usize) -> Result<(Mesh, Vec<Material>), MeshExportError> {
+    let (models, materials) = tobj::load_obj_buf(
+        &mut BufReader::new(Cursor::new(TREE_OBJ)),
+        &tobj::LoadOptions {
+            triangulate: true,
+            single_index: true,
+            ..Default::default()
+        },
+        |p| {
+            if p.to_string_lossy() != format!("{TREE_MODEL}.mtl") {
+                return Err(tobj::LoadError::OpenFileFailed);
+            }
+            tobj::load_mtl_buf(&mut BufReader::new(Cursor::new(TREE_MTL)))
+        },
+    )
+    .map_err(|e| MeshExportError::Model(e.to_string()))?;
+    let materials = materials.map_err(|e| MeshExportError::Model(e.to_string()))?;
+
+    let primitives = models
+        .into_iter()
+        .map(|model| {
+            let mesh = model.mesh;
+            Primitive {
+                material: first_material + mesh.material_id.unwrap_or(0),
+                positions: mesh
+                    .positions
+                    .chunks(3)
+                    .map(|p| [p[0], p[1], p[2]])
+                    .collect(),
+                normals: mesh.normals.chunks(3).map(|n| [n[0], n[1], n[2]]).collect(),
+                indices: mesh.indices,
+            }
+        })
+        .collect();
+    let no_materials = materials.len().max(1);
+    let materials = (0..no_materials)
+        .map(|i| Material {
+            name: format!("tree_{}", materials.get(i).map_or("default", |m| &m.name)),
+            color: TREE_COLORS[i.min(TREE_COLORS.len() - 1)],
+        })
+        .collect();
+    let mesh = Mesh {
+        name: TREE_MODEL.to_string(),
+        primitives,
+    };
+    Ok((mesh, materials))
+}
+
+// #################################################################################################
+// glTF
+// #################################################################################################
+fn write_glb(scene: &Scene, writer: &mut impl Write) -> MeshExportResult {
+    let mut buffer: Vec<u8> = vec![];
+    let mut buffer_views: Vec<Value> = vec![];
+    let mut accessors: Vec<Value> = vec![];
+    let mut add_accessor = |buffer: &mut Vec<u8>, bytes: Vec<u8>, target: u32, accessor: Value| {
+        // Every view starts at a multiple of four bytes, as every component is four bytes.
+        buffer.resize(buffer.len().next_multiple_of(4), 0);
+        buffer_views.push(json!({
+            "buffer": 0,
+            "byteOffset": buffer.len(),
+            "byteLength": bytes.len(),
+            "target": target,
+        }));
+        buffer.extend(bytes);
+        let mut accessor = accessor;
+        accessor["bufferView"] = json!(buffer_views.len() - 1);
+        accessors.push(accessor);
+        accessors.len() - 1
+    };
+
+    let mut meshes = vec![];
+    for mesh in scene.meshes.iter() {
+        let mut primitives = vec![];
+        for primitive in mesh.primitives.iter() {
+            let (min, max) = bounds(&primitive.positions);
+            let positions = add_accessor(
+                &mut buffer,
+                bytemuck::cast_slice(&primitive.positions).to_vec(),
+                ARRAY_BUFFER,
+                json!({
+                    "componentType": FLOAT,
+                    "count": primitive.positions.len(),
+                    "type": "VEC3",
+                    "min": min,
+                    "max": max,
+                }),
+            );
+            let mut attributes = json!({ "POSITION": positions });
+            if !primitive.normals.is_empty() {
+                attributes["NORMAL"] = json!(add_accessor(
+                    &mut buffer,
+                    bytemuck::cast_slice(&primitive.normals).to_vec(),
+                    ARRAY_BUFFER,
+                    json!({
+                        "componentType": FLOAT,
+                        "count": primitive.normals.len(),
+                        "type": "VEC3",
+                    }),
+                ));
+            }
+            let indices = add_accessor(
+                &mut buffer,
+                bytemuck::cast_slice(&primitive.indices).to_vec(),
+                ELEMENT_ARRAY_BUFFER,
+                json!({
+                    "componentType": UNSIGNED_INT,
+                    "count": primitive.indices.len(),
+                    "type": "SCALAR",
+                }),
+            );
+            primitives.push(json!({
+                "attributes": attributes,
+                "indices": indices,
+                "material": primitive.material,
+            }));
+        }
+        meshes.push(json!({ "name": mesh.name, "primitives": primitives }));
+    }
+
+    let materials: Vec<Value> = scene
+        .materials
+        .iter()
+        .map(|m| {
+            json!({
+                "name": m.name,
+                "pbrMetallicRoughness": {
+                    "baseColorFactor": [m.color[0], m.color[1], m.color[2], 1.0],
+                    "metallicFactor": 0.0,
+                    "roughnessFactor": 1.0,
+                },
+                "doubleSided": true,
+            })
+        })
+        .collect();
+    let nodes: Vec<Value> = scene
+        .nodes
+        .iter()
+        .map(|n| {
+            json!({
+                "name": n.name,
+                "mesh": n.mesh,
+                "translation": n.translation.to_array(),
+                "rotation": n.rotation.to_array(),
+            })
+        })
+        .collect();
+    let gltf = json!({
+        "asset": { "version": "2.0", "generator": "hw-architect" },
+        "scene": 0,
+        "scenes": [{ "nodes": (0..nodes.len()).collect::<Vec<_>>() }],
+        "nodes": nodes,
+        "meshes": meshes,
+        "materials": materials,
+        "accessors": accessors,
+        "bufferViews": buffer_views,
+        "buffers": [{ "byteLength": buffer.len() }],
+    });
+
+    // Both chunks must have a length that is a multiple of four, the JSON padded with spaces and
+    // the binary buffer with zeros.
+    let mut json = serde_json::to_vec(&gltf)?;
+    json.resize(json.len().next_multiple_of(4), b' ');
+    buffer.resize(buffer.len().next_multiple_of(4), 0);
+    let length = 12 + 8 + json.len() + 8 + buffer.len();
+
+    writer.write_all(&GLB_MAGIC.to_le_bytes())?;
+    writer.write_all(&2u32.to_le_bytes())?;
+    writer.write_all(&(length as u32).to_le_bytes())?;
+    writer.write_all(&(json.len() as u32).to_le_bytes())?;
+    writer.write_all(&GLB_JSON_CHUNK.to_le_bytes())?;
+    writer.write_all(&json)?;
+    writer.write_all(&(buffer.len() as u32).to_le_bytes())?;
+    writer.write_all(&GLB_BIN_CHUNK.to_le_bytes())?;
+    writer.write_all(&buffer)?;
+    Ok(())
+}
+
+/// Returns the smallest and largest value of each coordinate of the positions.
+fn bounds(positions: &[[f32; 3]]) -> ([f32; 3], [f32; 3]) {
+    positions.iter().fold(
+        ([f32::INFINITY; 3], [f32::NEG_INFINITY; 3]),
+        |(min, max), pos| {
+            (
+                Vec3::from(min).min(Vec3::from(*pos)).into(),
+                Vec3::from(max).max(Vec3::from(*pos)).into(),
+            )
+        },
+    )
+}
+
+// #################################################################################################
+// OBJ
+// #################################################################################################
+fn write_obj(scene: &Scene, mtl_name: &str, writer: &mut impl Write) -> MeshExportResult {
+    writeln!(writer, "# hw-architect")?;
+    writeln!(writer, "mtllib {}", mtl_name)?;
+    // OBJ has no instances, so every object gets its own copy of the vertices of its mesh, moved
+    // into place. Vertices and normals are numbered from one across the whole file.
+    let (mut no_positions, mut no_normals) = (0, 0);
+    for node in scene.nodes.iter() {
+        writeln!(writer, "o {}", node.name)?;
+        for primitive in scene.meshes[node.mesh].primitives.iter() {
+            for pos in primitive.positions.iter() {
+                let pos = node.translation + node.rotation * Vec3::from(*pos);
+                writeln!(writer, "v {} {} {}", pos.x, pos.y, pos.z)?;
+            }
+            for normal in primitive.normals.iter() {
+                let normal = node.rotation * Vec3::from(*normal);
+                writeln!(writer, "vn {} {} {}", normal.x, normal.y, normal.z)?;
+            }
+            writeln!(
+                writer,
+                "usemtl {}",
+                scene.materials[primitive.material].name
+            )?;
+            let has_normals = !primitive.normals.is_empty();
+            for triangle in primitive.indices.chunks_exact(3) {
+                write!(writer, "f")?;
+                for index in triangle {
+                    let v = no_positions + *index as usize + 1;
+                    if has_normals {
+                        write!(writer, " {}//{}", v, no_normals + *index as usize + 1)?;
+                    } else {
+                        write!(writer, " {}", v)?;
+                    }
+                }
+                writeln!(writer)?;
+            }
+            no_positions += primitive.positions.len();
+            no_normals += primitive.normals.len();
+        }
+    }
+    Ok(())
+}
+
+fn write_mtl(scene: &Scene, writer: &mut impl Write) -> MeshExportResult {
+    writeln!(writer, "# hw-architect")?;
+    for material in scene.materials.iter() {
+        let [r, g, b] = material.color;
+        writeln!(writer)?;
+        writeln!(writer, "newmtl {}", material.name)?;
+        writeln!(writer, "Kd {} {} {}", r, g, b)?;
+        writeln!(writer, "d 1.0")?;
+        writeln!(writer, "illum 1")?;
+    }
+    Ok(())
+}
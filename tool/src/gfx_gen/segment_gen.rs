@@ -3,14 +3,25 @@ use utils::consts::{LANE_MARKINGS_WIDTH, ROAD_HEIGHT};
 use utils::math::DirXZ;
 
 use gfx_api::RoadMesh;
-use world_api::NodeType;
+use world_api::SegmentType;
 
 use glam::*;
 
+/// The distance from the top of a bridge deck to its bottom.
+const DECK_THICKNESS: f32 = 1.0;
+/// The width of the square piers that hold up bridges.
+const PIER_WIDTH: f32 = 1.5;
+/// The longest that a bridge goes between two piers.
+const PIER_SPACING: f32 = 30.0;
+
 /// Generates and returns the road mesh generated from the given uniform spine points and the type
-/// of the node, which is used to get the lane width and total width of the mesh to generate.
-pub fn gen_road_mesh_with_lanes(spine: &Spine, node_type: NodeType) -> RoadMesh {
-    let no_lanes = node_type.no_lanes();
+/// of the segment, which is used to get the width of each lane along the mesh to generate. For
+/// transition segments the lanes are tapered, such that lanes that are added or dropped have zero
+/// width at the end where they do not exist.
+pub fn gen_road_mesh_with_lanes(spine: &Spine, segment_type: SegmentType) -> RoadMesh {
+    let no_lanes = segment_type.max_no_lanes();
+    let lane_widths_at =
+        |i: usize| segment_type.lane_widths_at(i as f32 / (spine.len() - 1) as f32);
 
     let mut vertices = vec![];
     let mut indices = vec![];
@@ -20,7 +31,7 @@ pub fn gen_road_mesh_with_lanes(spine: &Spine, node_type: NodeType) -> RoadMesh
 
     let first_pos = spine[0].pos;
     let first_dir = spine[0].dir;
-    let first_cut = generate_clean_cut(first_pos, first_dir, node_type);
+    let first_cut = generate_clean_cut(first_pos, first_dir, lane_widths_at(0).iter().sum());
     vertices.append(&mut first_cut.clone());
     lane_vertices.append(&mut first_cut[1..5].to_vec());
 
@@ -28,7 +39,7 @@ pub fn gen_road_mesh_with_lanes(spine: &Spine, node_type: NodeType) -> RoadMesh
         let pos = spine[i].pos;
         let dir = spine[i].dir;
         if i % 3 == 0 {
-            let cut = generate_clean_cut(pos, dir, node_type);
+            let cut = generate_clean_cut(pos, dir, lane_widths_at(i).iter().sum());
 
             let previ = (vertices.len() - 4 - m_verts as usize) as u32;
             let curri = vertices.len() as u32;
@@ -78,7 +89,7 @@ pub fn gen_road_mesh_with_lanes(spine: &Spine, node_type: NodeType) -> RoadMesh
                 curri + 3,
             ]);
         } else if i % 3 == 1 {
-            let cut = generate_markings_cut(pos, dir, node_type);
+            let cut = generate_markings_cut(pos, dir, &lane_widths_at(i));
 
             let previ = (vertices.len() - 6) as u32;
             let curri = vertices.len() as u32;
@@ -129,7 +140,7 @@ pub fn gen_road_mesh_with_lanes(spine: &Spine, node_type: NodeType) -> RoadMesh
             ]);
         } else {
             // generates lanes
-            let cut = generate_markings_cut(pos, dir, node_type);
+            let cut = generate_markings_cut(pos, dir, &lane_widths_at(i));
 
             let previ = (vertices.len() - 4 - m_verts as usize) as u32;
             let curri = vertices.len() as u32;
@@ -189,12 +200,114 @@ pub fn gen_road_mesh_with_lanes(spine: &Spine, node_type: NodeType) -> RoadMesh
     }
 }
 
+/// Generates the mesh of a segment, which for bridges includes the deck and the piers.
+pub fn gen_segment_mesh(spine: &Spine, segment_type: SegmentType, bridge: bool) -> RoadMesh {
+    let road_mesh = gen_road_mesh_with_lanes(spine, segment_type);
+    if !bridge {
+        return road_mesh;
+    }
+    combine_road_meshes_bad(vec![road_mesh, gen_bridge_mesh(spine, segment_type)])
+}
+
+/// Generates the deck that carries a bridge segment and the piers that hold it up, given the
+/// uniform spine points of the segment. The deck is the width of the road mesh, and the piers are
+/// spread evenly along the segment, going from the ground to the bottom of the deck.
+fn gen_bridge_mesh(spine: &Spine, segment_type: SegmentType) -> RoadMesh {
+    let half_width_at = |i: usize| {
+        let t = i as f32 / (spine.len() - 1) as f32;
+        segment_type.lane_widths_at(t).iter().sum::<f32>() / 2.0 + LANE_MARKINGS_WIDTH * 1.5
+    };
+    let deck_depth = Vec3::new(0.0, DECK_THICKNESS, 0.0);
+
+    let mut vertices: Vec<[f32; 3]> = vec![];
+    let mut indices = vec![];
+
+    // Each cut of the deck goes from the top left corner, down and around to the top right corner.
+    for (i, loc) in spine.iter().enumerate() {
+        let right = Vec3::from(loc.dir.right_hand()) * half_width_at(i);
+        let curri = vertices.len() as u32;
+        vertices.append(&mut vec![
+            (loc.pos - right).into(),
+            (loc.pos - right - deck_depth).into(),
+            (loc.pos + right - deck_depth).into(),
+            (loc.pos + right).into(),
+        ]);
+        if i == 0 {
+            continue;
+        }
+        let previ = curri - 4;
+        for k in 0..3 {
+            indices.append(&mut vec![
+                previ + k + 1,
+                previ + k,
+                curri + k + 1,
+                curri + k + 1,
+                previ + k,
+                curri + k,
+            ]);
+        }
+    }
+    let last = vertices.len() as u32 - 4;
+    indices.append(&mut vec![0, 1, 2, 0, 2, 3]);
+    indices.append(&mut vec![
+        last,
+        last + 2,
+        last + 1,
+        last,
+        last + 3,
+        last + 2,
+    ]);
+
+    let length = spine.compute_horizontal_length();
+    let no_spans = (length / PIER_SPACING).ceil().max(1.0) as usize;
+    let mut covered = 0.0;
+    let mut next_pier = 0;
+    for (i, loc) in spine.iter().enumerate() {
+        if i > 0 {
+            covered += (loc.pos - spine[i - 1].pos).xz().length();
+        }
+        if next_pier > no_spans || covered < next_pier as f32 / no_spans as f32 * length {
+            continue;
+        }
+        next_pier += 1;
+        let top = loc.pos.y - DECK_THICKNESS;
+        if top <= 0.0 {
+            continue;
+        }
+        let forward = Vec3::from(loc.dir) * PIER_WIDTH / 2.0;
+        let right = Vec3::from(loc.dir.right_hand()) * PIER_WIDTH / 2.0;
+        let ground = Vec3::new(loc.pos.x, 0.0, loc.pos.z);
+        let corners = [
+            ground - forward - right,
+            ground + forward - right,
+            ground + forward + right,
+            ground - forward + right,
+        ];
+        let curri = vertices.len() as u32;
+        for corner in corners {
+            vertices.push(corner.into());
+            vertices.push((corner + Vec3::new(0.0, top, 0.0)).into());
+        }
+        for k in 0..4 {
+            let (b0, t0) = (curri + k * 2, curri + k * 2 + 1);
+            let (b1, t1) = (curri + (k + 1) % 4 * 2, curri + (k + 1) % 4 * 2 + 1);
+            indices.append(&mut vec![b0, t0, b1, b1, t0, t1]);
+        }
+    }
+
+    RoadMesh {
+        vertices,
+        indices,
+        lane_vertices: vec![],
+        lane_indices: vec![],
+    }
+}
+
 /// Generates the cut where no lane markings are present.
-fn generate_clean_cut(pos: Vec3, dir: DirXZ, node_type: NodeType) -> Vec<[f32; 3]> {
+fn generate_clean_cut(pos: Vec3, dir: DirXZ, road_width: f32) -> Vec<[f32; 3]> {
     let right_dir = dir.right_hand();
     let mut vertices = vec![];
     let height = Vec3::new(0.0, ROAD_HEIGHT, 0.0);
-    let road_width = node_type.lane_width_f32() * node_type.no_lanes() as f32;
 
     let mut pos = pos - Vec3::from(right_dir) * (LANE_MARKINGS_WIDTH * 1.5 + road_width / 2.0);
     vertices.push(pos.into());
@@ -217,14 +330,13 @@ fn generate_clean_cut(pos: Vec3, dir: DirXZ, node_type: NodeType) -> Vec<[f32; 3
     vertices
 }
 
-/// Generates the cut where lane markings are present.
-fn generate_markings_cut(pos: Vec3, dir: DirXZ, node_type: NodeType) -> Vec<[f32; 3]> {
+/// Generates the cut where lane markings are present, given the width of each lane from left to
+/// right. Lanes that are narrower than the lane markings get markings as wide as the lane.
+fn generate_markings_cut(pos: Vec3, dir: DirXZ, lane_widths: &[f32]) -> Vec<[f32; 3]> {
     let right_dir = dir.right_hand();
     let mut vertices = vec![];
     let height = Vec3::new(0.0, ROAD_HEIGHT, 0.0);
-    let lane_width = node_type.lane_width_f32();
-    let no_lanes = node_type.no_lanes();
-    let road_width = lane_width * no_lanes as f32;
+    let road_width: f32 = lane_widths.iter().sum();
 
     let mut pos = pos - Vec3::from(right_dir) * (LANE_MARKINGS_WIDTH * 1.5 + road_width / 2.0);
     vertices.push(pos.into());
@@ -235,21 +347,16 @@ fn generate_markings_cut(pos: Vec3, dir: DirXZ, node_type: NodeType) -> Vec<[f32
     pos += Vec3::from(right_dir) * LANE_MARKINGS_WIDTH;
     vertices.push(pos.into());
 
-    // Lanes in between outer lanes
-    for _ in 0..no_lanes - 1 {
-        pos += Vec3::from(right_dir) * (lane_width - LANE_MARKINGS_WIDTH);
+    // Each lane followed by the marking to its right
+    for lane_width in lane_widths {
+        let lane_space = (lane_width - LANE_MARKINGS_WIDTH).max(0.0);
+        pos += Vec3::from(right_dir) * lane_space;
         vertices.push(pos.into());
 
-        pos += Vec3::from(right_dir) * LANE_MARKINGS_WIDTH;
+        pos += Vec3::from(right_dir) * (lane_width - lane_space);
         vertices.push(pos.into());
     }
 
-    pos += Vec3::from(right_dir) * (lane_width - LANE_MARKINGS_WIDTH);
-    vertices.push(pos.into());
-
-    pos += Vec3::from(right_dir) * LANE_MARKINGS_WIDTH;
-    vertices.push(pos.into());
-
     pos += Vec3::from(right_dir) * LANE_MARKINGS_WIDTH - height;
     vertices.push(pos.into());
 
@@ -0,0 +1,82 @@
+use super::segment_gen;
+
+use curves::CurveShared;
+use utils::id::{IdMap, SegmentId};
+use world_api::{WorldEvent, WorldManipulator};
+
+use gfx_api::{GfxWorldData, RoadMesh};
+
+/// Generates the road meshes and tree instances of everything in the given world and sends them
+/// to the gpu in bulk. Used when a world has not been built incrementally by the tools, such as
+/// when a save game has been loaded.
+pub fn gen_world_gfx<G: GfxWorldData, W: WorldManipulator>(gfx_handle: &mut G, world: &W) {
+    gfx_handle.add_road_meshes(gen_segment_meshes(world, world.get_segment_ids()));
+
+    for (model_id, trees) in world.get_trees() {
+        let raw_trees = trees
+            .into_iter()
+            .map(|(id, tree)| (id, tree.pos().into(), tree.yrot()))
+            .collect();
+        gfx_handle.add_trees(model_id, raw_trees);
+    }
+}
+
+/// Removes the road meshes and tree instances of everything in the given world from the gpu.
+pub fn clear_world_gfx<G: GfxWorldData, W: WorldManipulator>(gfx_handle: &mut G, world: &W) {
+    gfx_handle.remove_road_meshes(world.get_segment_ids());
+
+    for (model_id, trees) in world.get_trees() {
+        for (id, _) in trees {
+            gfx_handle.remove_tree(id, model_id);
+        }
+    }
+}
+
+/// Updates the road meshes and tree instances on the gpu according to the given events, which
+/// describe how the world changed since the events were last applied. Only the meshes of segments
+/// that still exist are generated, so a segment that was added and removed again is never shown.
+pub fn apply_world_events<G: GfxWorldData, W: WorldManipulator>(
+    gfx_handle: &mut G,
+    world: &W,
+    events: Vec<WorldEvent>,
+) {
+    let mut removed_segments = vec![];
+    let mut added_segments = vec![];
+    for event in events {
+        match event {
+            WorldEvent::SegmentAdded(id) => added_segments.push(id),
+            WorldEvent::SegmentRemoved(id) => {
+                added_segments.retain(|s| *s != id);
+                removed_segments.push(id);
+            }
+            WorldEvent::TreeAdded(id, tree, model_id) => {
+                gfx_handle.add_trees(model_id, vec![(id, tree.pos().into(), tree.yrot())]);
+            }
+            WorldEvent::TreeRemoved(id, model_id) => gfx_handle.remove_tree(id, model_id),
+            WorldEvent::NodeAdded(_) | WorldEvent::NodeChanged(_) | WorldEvent::NodeRemoved(_) => {}
+        }
+    }
+    if !removed_segments.is_empty() {
+        gfx_handle.remove_road_meshes(removed_segments);
+    }
+    if !added_segments.is_empty() {
+        gfx_handle.add_road_meshes(gen_segment_meshes(world, added_segments));
+    }
+}
+
+/// Generates the road meshes of the given segments from their curves in the world.
+pub fn gen_segment_meshes<W: WorldManipulator>(
+    world: &W,
+    segments: Vec<SegmentId>,
+) -> IdMap<SegmentId, RoadMesh> {
+    let mut mesh_map: IdMap<SegmentId, RoadMesh> = IdMap::new();
+    for id in segments {
+        let (curve, segment_type) = world.get_segment_curve(id);
+        let bridge = world.is_segment_bridge(id);
+        mesh_map.insert(
+            id,
+            segment_gen::gen_segment_mesh(curve.get_spine(), segment_type, bridge),
+        );
+    }
+    mesh_map
+}
@@ -6,4 +6,5 @@ mod tool_handler;
 mod tool_state;
 mod tools;
 
+pub use gfx_gen::mesh_export::{export_gltf, export_obj, MeshExportError, MeshExportResult};
 pub use tool_handler::ToolHandler;
@@ -1,9 +1,10 @@
+use crate::gfx_gen::world_gen;
 use crate::tool_state::ToolState;
 use crate::tools::{Bulldoze, Construct, DummyTool, NoTool, Tool, ToolSpec, TreePlopper};
 
 use gfx_api::GfxWorldData;
 use utils::input;
-use world_api::WorldManipulator;
+use world_api::{SubscriberId, WorldManipulator};
 
 use glam::Vec3;
 
@@ -24,10 +25,13 @@ pub struct ToolHandler<G: GfxWorldData, W: WorldManipulator> {
     curr_tool_handle: Box<dyn ToolSpec<G, W>>,
     curr_tool: ToolMarker,
     saved_tool: Option<ToolMarker>,
+    /// Receives the changes of the world, which are mirrored into the graphics every frame.
+    gfx_subscriber: SubscriberId,
 }
 
 impl<G: GfxWorldData, W: WorldManipulator + 'static> ToolHandler<G, W> {
-    pub fn new(gfx_handle: &mut G, world: W) -> Self {
+    pub fn new(gfx_handle: &mut G, mut world: W) -> Self {
+        let gfx_subscriber = world.subscribe();
         let state = ToolState::default();
         let start_tool = Box::new(Tool::<NoTool, W>::new(state, world, Vec3::ZERO));
         let mut result = ToolHandler {
@@ -35,6 +39,7 @@ impl<G: GfxWorldData, W: WorldManipulator + 'static> ToolHandler<G, W> {
             curr_tool_handle: start_tool,
             curr_tool: ToolMarker::NoTool,
             saved_tool: None,
+            gfx_subscriber,
         };
         result.enter_construct_mode(gfx_handle);
         result
@@ -75,6 +80,59 @@ impl<G: GfxWorldData, W: WorldManipulator + 'static> ToolHandler<G, W> {
         self.curr_tool_handle.init(gfx_handle);
     }
 
+    /// Recreates the current tool, such that it does not hold on to any state of the world.
+    fn reenter_tool(&mut self, gfx_handle: &mut G) {
+        match self.curr_tool {
+            ToolMarker::NoTool => self.enter_tool::<NoTool>(gfx_handle),
+            ToolMarker::Construct => self.enter_tool::<Construct>(gfx_handle),
+            ToolMarker::Bulldoze => self.enter_tool::<Bulldoze>(gfx_handle),
+            ToolMarker::TreePlopper => self.enter_tool::<TreePlopper>(gfx_handle),
+        }
+    }
+
+    /// Returns the world that the tools are manipulating.
+    pub fn get_world(&self) -> &W {
+        self.curr_tool_handle.get_world()
+    }
+
+    /// Replaces the world that the tools are manipulating and regenerates all graphics from the
+    /// new world, such that a loaded save game renders exactly as when it was saved. Returns the
+    /// old world.
+    pub fn replace_world(&mut self, gfx_handle: &mut G, world: W) -> W {
+        self.curr_tool_handle.clean_gfx(gfx_handle);
+        // The graphics must match the old world before they are cleared, or the meshes and trees
+        // of changes that have not been applied yet are left behind.
+        let events = self
+            .curr_tool_handle
+            .get_world_mut()
+            .drain_events(self.gfx_subscriber);
+        world_gen::apply_world_events(gfx_handle, self.curr_tool_handle.get_world(), events);
+        world_gen::clear_world_gfx(gfx_handle, self.curr_tool_handle.get_world());
+
+        let mut old_world = std::mem::replace(self.curr_tool_handle.get_world_mut(), world);
+        old_world.unsubscribe(self.gfx_subscriber);
+        self.gfx_subscriber = self.curr_tool_handle.get_world_mut().subscribe();
+        world_gen::gen_world_gfx(gfx_handle, self.curr_tool_handle.get_world());
+
+        self.reenter_tool(gfx_handle);
+        old_world
+    }
+
+    /// Undoes or redoes the most recent edit of the world. The graphics are updated from the
+    /// events of the world.
+    fn step_history(&mut self, gfx_handle: &mut G, undo: bool) {
+        self.curr_tool_handle.clean_gfx(gfx_handle);
+
+        let world = self.curr_tool_handle.get_world_mut();
+        if undo {
+            world.undo();
+        } else {
+            world.redo();
+        }
+
+        self.reenter_tool(gfx_handle);
+    }
+
     pub fn process_keyboard(&mut self, gfx_handle: &mut G, key: input::KeyAction) {
         // TODO add leader keybindings, but maybe they should be in InputHandler.
         use input::Action::*;
@@ -105,6 +163,8 @@ impl<G: GfxWorldData, W: WorldManipulator + 'static> ToolHandler<G, W> {
                 NoTool => return,
                 _ => self.enter_no_tool(gfx_handle),
             },
+            (Undo, Press) => self.step_history(gfx_handle, true),
+            (Redo, Press) => self.step_history(gfx_handle, false),
             _ => self.curr_tool_handle.process_keyboard(gfx_handle, key),
         }
     }
@@ -129,7 +189,12 @@ impl<G: GfxWorldData, W: WorldManipulator + 'static> ToolHandler<G, W> {
         self.curr_tool_handle.update_view(gfx_handle);
     }
 
-    pub fn update(&mut self, dt: Duration) {
-        self.curr_tool_handle.get_world_mut().update(dt);
+    /// Updates the world, and mirrors every change of the world since the last update into the
+    /// graphics.
+    pub fn update(&mut self, gfx_handle: &mut G, dt: Duration) {
+        let world = self.curr_tool_handle.get_world_mut();
+        world.update(dt);
+        let events = world.drain_events(self.gfx_subscriber);
+        world_gen::apply_world_events(gfx_handle, self.curr_tool_handle.get_world(), events);
     }
 }
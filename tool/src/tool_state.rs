@@ -1,4 +1,5 @@
-use world_api::{LaneWidth, NodeType};
+use utils::consts::{BRIDGE_DECK_HEIGHT, MAX_GRADE};
+use world_api::{LaneWidth, MedianWidth, NodeType, SuperNodeType};
 
 #[derive(Debug, Clone, Copy, Default)]
 pub struct ToolState {
@@ -10,6 +11,8 @@ pub struct ToolState {
 pub struct BulldozeState {
     pub bulldoze_segments: bool,
     pub bulldoze_trees: bool,
+    /// Bulldoze whole nodes together with all their segments, instead of single segments.
+    pub bulldoze_nodes: bool,
 }
 
 impl Default for BulldozeState {
@@ -17,6 +20,7 @@ impl Default for BulldozeState {
         Self {
             bulldoze_segments: true,
             bulldoze_trees: true,
+            bulldoze_nodes: false,
         }
     }
 }
@@ -26,6 +30,19 @@ pub struct RoadState {
     pub selected_road: SelectedRoad,
     pub snapping: bool,
     pub reverse: bool,
+    /// When snapping to a node with a different number of lanes, build a transition segment that
+    /// covers all lanes of the node instead of attaching to some of them.
+    pub transition: bool,
+    /// Build both directions of the road at once, separated by the selected median.
+    pub bidirectional: bool,
+    /// The height that road ends are built at when they are not snapped to a node.
+    pub elevation: f32,
+    /// Roads steeper than this are not built, given as rise per horizontal meter.
+    pub max_grade: f32,
+    /// Build the road as a bridge, whose ends are at the deck height unless they are snapped.
+    pub bridge: bool,
+    /// The height that bridge ends are built at when they are not snapped to a node.
+    pub deck_height: f32,
 }
 
 impl Default for RoadState {
@@ -34,6 +51,12 @@ impl Default for RoadState {
             selected_road: SelectedRoad::default(),
             snapping: true,
             reverse: false,
+            transition: false,
+            bidirectional: false,
+            elevation: 0.0,
+            max_grade: MAX_GRADE,
+            bridge: false,
+            deck_height: BRIDGE_DECK_HEIGHT,
         }
     }
 }
@@ -50,6 +73,10 @@ impl RoadState {
     pub fn set_no_lanes(&mut self, no_lanes: u8) {
         self.selected_road.node_type.set_no_lanes(no_lanes);
     }
+
+    pub fn set_median(&mut self, median: MedianWidth) {
+        self.selected_road.median = median;
+    }
 }
 
 /// The type of curve to be constructed
@@ -65,6 +92,8 @@ pub enum CurveType {
 pub struct SelectedRoad {
     pub node_type: NodeType,
     pub curve_type: CurveType,
+    /// Only used when building bidirectional roads.
+    pub median: MedianWidth,
 }
 
 impl SelectedRoad {
@@ -72,6 +101,12 @@ impl SelectedRoad {
         Self {
             node_type,
             curve_type,
+            median: MedianWidth::default(),
         }
     }
+
+    /// The type of the super nodes of a bidirectional road of this type.
+    pub fn super_node_type(&self) -> SuperNodeType {
+        SuperNodeType::new(self.node_type, self.median)
+    }
 }
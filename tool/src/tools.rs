@@ -22,7 +22,7 @@ pub trait ToolShared<G: GfxWorldData, W: WorldManipulator> {
     fn destroy(self: Box<Self>) -> (ToolState, W);
 
     // fn get_state(&self) -> &ToolState;
-    // fn get_world(&self) -> &Box<dyn WorldManipulator>;
+    fn get_world(&self) -> &W;
     fn get_world_mut(&mut self) -> &mut W;
 
     fn update_ground_pos(&mut self, ground_pos: Vec3);
@@ -80,9 +80,9 @@ impl<T: Default, G: GfxWorldData, W: WorldManipulator> ToolShared<G, W> for Tool
     //     &self.state_handle
     // }
 
-    // fn get_world(&self) -> &Box<dyn WorldManipulator> {
-    //     &self.world
-    // }
+    fn get_world(&self) -> &W {
+        &self.world
+    }
 
     fn get_world_mut(&mut self) -> &mut W {
         &mut self.world
@@ -131,9 +131,9 @@ impl<G: GfxWorldData, W: WorldManipulator> ToolShared<G, W> for DummyTool {
     //     unreachable!()
     // }
 
-    // fn get_world(&self) -> &Box<dyn WorldManipulator> {
-    //     unreachable!()
-    // }
+    fn get_world(&self) -> &W {
+        unreachable!()
+    }
 
     fn get_world_mut(&mut self) -> &mut W {
         unreachable!()
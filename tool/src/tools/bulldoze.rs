@@ -34,6 +34,11 @@ impl<G: GfxWorldData, W: WorldManipulator> ToolUnique<G> for Tool<Bulldoze, W> {
                 self.state_handle.bulldoze_state.bulldoze_trees = !curr;
                 self.update_markings(gfx_handle);
             }
+            (ToggleBulldozeNodes, Press) => {
+                let curr = self.bd_nodes();
+                self.state_handle.bulldoze_state.bulldoze_nodes = !curr;
+                self.update_markings(gfx_handle);
+            }
             _ => {}
         }
     }
@@ -42,16 +47,23 @@ impl<G: GfxWorldData, W: WorldManipulator> ToolUnique<G> for Tool<Bulldoze, W> {
         if self.bd_trees() {
             if let Some(tree_id) = self.world.get_tree_from_pos(self.ground_pos) {
                 self.world.remove_tree(tree_id);
-                gfx_handle.remove_tree(tree_id, utils::consts::TREE_MODEL_ID);
                 self.update_markings(gfx_handle);
                 return;
             }
         }
 
+        if self.bd_nodes() {
+            if let Some(node_id) = self.world.get_node_from_pos(self.ground_pos) {
+                if self.world.remove_node(node_id).is_some() {
+                    self.update_markings(gfx_handle);
+                }
+                return;
+            }
+        }
+
         if self.bd_segments() {
             if let Some(segment_id) = self.world.get_segment_from_pos(self.ground_pos) {
                 if self.world.remove_segment(segment_id) {
-                    gfx_handle.remove_road_meshes(vec![segment_id]);
                     self.update_markings(gfx_handle);
                 }
             }
@@ -80,6 +92,10 @@ impl<W: WorldManipulator> Tool<Bulldoze, W> {
         self.state_handle.bulldoze_state.bulldoze_segments
     }
 
+    fn bd_nodes(&self) -> bool {
+        self.state_handle.bulldoze_state.bulldoze_nodes
+    }
+
     fn update_markings<G: GfxWorldData>(&mut self, gfx_handle: &mut G) {
         gfx_handle.set_tree_markers(vec![], None);
         gfx_handle.mark_road_segments(vec![]);
@@ -93,6 +109,12 @@ impl<W: WorldManipulator> Tool<Bulldoze, W> {
                 return;
             }
         }
+        if self.bd_nodes() {
+            if let Some(id) = self.world.get_node_from_pos(self.ground_pos) {
+                gfx_handle.mark_road_segments(self.world.get_node_segments(id));
+                return;
+            }
+        }
         if self.bd_segments() {
             if let Some(id) = self.world.get_segment_from_pos(self.ground_pos) {
                 gfx_handle.mark_road_segments(vec![id]);
@@ -12,21 +12,29 @@ use crate::cycle_selection;
 use crate::gfx_gen::segment_gen;
 use crate::tool_state::{CurveType, SelectedRoad};
 
-use curves::{Circular, CompositeCurveSum, Curve, CurveError, CurveShared, Straight};
-use utils::id::{IdMap, SegmentId};
+use curves::{
+    Circular, CompositeCurveSum, Curve, CurveError, CurveShared, CurveSum, Straight,
+    VerticalProfile,
+};
 use utils::input;
 use utils::math::Loc;
 use world_api::{
-    LNodeBuilder, LNodeBuilderType, LRoadBuilder, LSegmentBuilder, LaneWidth, NodeType, SnapConfig,
+    LNodeBuilder, LNodeBuilderType, LRoadBuilder, LSegmentBuilder, LSuperRoadBuilder, LaneWidth,
+    MedianWidth, NodeType, RoadGenErr, SegmentType, SnapConfig, SuperNodeType, SuperSnapConfig,
     WorldManipulator,
 };
 
 use gfx_api::{GfxWorldData, RoadMesh};
 use glam::*;
 
+/// How much the elevation changes with each key press, in meters.
+const ELEVATION_STEP: f32 = 1.0;
+
 pub struct Construct {
     curve_tool: CurveToolSum,
     dir_marker: Option<Loc>,
+    /// The elevation when the first point was selected, used if it is not snapped to a node.
+    first_height: f32,
 }
 
 impl Default for Construct {
@@ -34,6 +42,7 @@ impl Default for Construct {
         Self {
             curve_tool: CurveTool::<CircularTool, Curve<Circular>>::default().into(),
             dir_marker: None,
+            first_height: 0.0,
         }
     }
 }
@@ -59,6 +68,29 @@ impl<G: GfxWorldData, W: WorldManipulator> ToolUnique<G> for Tool<Construct, W>
                 self.state_handle.road_state.reverse = !self.state_handle.road_state.reverse;
                 dbg!(self.state_handle.road_state.reverse);
             }
+            (ToggleTransition, Press) => {
+                self.state_handle.road_state.transition = !self.state_handle.road_state.transition;
+                dbg!(self.state_handle.road_state.transition);
+
+                self.update_view(gfx_handle);
+                self.show_snappable_nodes(gfx_handle);
+            }
+            (ToggleBidirectional, Press) => {
+                self.state_handle.road_state.bidirectional =
+                    !self.state_handle.road_state.bidirectional;
+                dbg!(self.state_handle.road_state.bidirectional);
+
+                self.instance.curve_tool.reset(None);
+                self.update_view(gfx_handle);
+                self.show_snappable_nodes(gfx_handle);
+            }
+            (ToggleBridge, Press) => {
+                self.state_handle.road_state.bridge = !self.state_handle.road_state.bridge;
+                dbg!(self.state_handle.road_state.bridge);
+
+                self.update_view(gfx_handle);
+                self.show_snappable_nodes(gfx_handle);
+            }
             (CycleCurveType, Scroll(scroll_state)) => {
                 let new_curve_type =
                     cycle_selection::scroll(self.get_sel_curve_type(), scroll_state);
@@ -99,12 +131,39 @@ impl<G: GfxWorldData, W: WorldManipulator> ToolUnique<G> for Tool<Construct, W>
                 self.update_view(gfx_handle);
                 self.show_snappable_nodes(gfx_handle);
             }
+            (CycleMedianWidth, Scroll(scroll_state)) => {
+                let new_median = cycle_selection::scroll(self.get_sel_median(), scroll_state);
+                dbg!(new_median);
+                self.state_handle.road_state.set_median(new_median);
+
+                self.instance.curve_tool.reset(None);
+                self.update_view(gfx_handle);
+                self.show_snappable_nodes(gfx_handle);
+            }
+            (RaiseElevation, Press) => {
+                self.state_handle.road_state.elevation += ELEVATION_STEP;
+                dbg!(self.state_handle.road_state.elevation);
+
+                self.update_view(gfx_handle);
+                self.show_snappable_nodes(gfx_handle);
+            }
+            (LowerElevation, Press) => {
+                self.state_handle.road_state.elevation -= ELEVATION_STEP;
+                dbg!(self.state_handle.road_state.elevation);
+
+                self.update_view(gfx_handle);
+                self.show_snappable_nodes(gfx_handle);
+            }
             _ => {}
         }
     }
 
     fn left_click(&mut self, gfx_handle: &mut G) {
+        let had_first_point = self.instance.curve_tool.has_first_point();
         let action = self.instance.curve_tool.left_click(self.ground_pos);
+        if !had_first_point && self.instance.curve_tool.has_first_point() {
+            self.instance.first_height = self.get_end_height();
+        }
         self.handle_curve_action_result(gfx_handle, action);
     }
 
@@ -126,6 +185,7 @@ impl<G: GfxWorldData, W: WorldManipulator> ToolUnique<G> for Tool<Construct, W>
     fn clean_gfx(&mut self, gfx_handle: &mut G) {
         gfx_handle.set_node_markers(vec![]);
         gfx_handle.set_road_tool_mesh(None);
+        gfx_handle.mark_road_segments(vec![]);
     }
 }
 
@@ -150,10 +210,70 @@ impl<W: WorldManipulator> Tool<Construct, W> {
         self.get_sel_node_type().no_lanes()
     }
 
+    fn get_sel_median(&self) -> MedianWidth {
+        self.state_handle.road_state.selected_road.median
+    }
+
+    fn get_sel_super_node_type(&self) -> SuperNodeType {
+        self.state_handle.road_state.selected_road.super_node_type()
+    }
+
     fn is_reverse(&self) -> bool {
         self.state_handle.road_state.reverse
     }
 
+    fn is_transition(&self) -> bool {
+        self.state_handle.road_state.transition
+    }
+
+    fn is_bidirectional(&self) -> bool {
+        self.state_handle.road_state.bidirectional
+    }
+
+    fn get_max_grade(&self) -> f32 {
+        self.state_handle.road_state.max_grade
+    }
+
+    fn is_bridge(&self) -> bool {
+        self.state_handle.road_state.bridge
+    }
+
+    /// Returns the height of a road end that is not snapped to a node.
+    fn get_end_height(&self) -> f32 {
+        if self.is_bridge() {
+            self.state_handle.road_state.deck_height
+        } else {
+            self.state_handle.road_state.elevation
+        }
+    }
+
+    /// Returns the types of the segments of a road consisting of `no_segments` segments, going from
+    /// a node of type `first` to a node of type `last`. Any new node in between has the selected
+    /// type, such that only the segments at the ends are transition segments.
+    fn compute_segment_types(
+        &self,
+        first: NodeType,
+        last: NodeType,
+        no_segments: usize,
+    ) -> Vec<SegmentType> {
+        let sel_node_type = self.get_sel_node_type();
+        if no_segments == 1 {
+            return vec![SegmentType::new(first, last)];
+        }
+        let mut segment_types = vec![SegmentType::new(first, sel_node_type)];
+        for _ in 1..no_segments - 1 {
+            segment_types.push(sel_node_type.into());
+        }
+        segment_types.push(SegmentType::new(sel_node_type, last));
+        segment_types
+    }
+
+    /// Returns the type that a road end will have, depending on the node it is snapped to.
+    fn end_node_type(&self, snap: &Option<SnapConfig>) -> NodeType {
+        snap.as_ref()
+            .map_or(self.get_sel_node_type(), |snap| snap.node_type())
+    }
+
     // #############################################################################################
     // Handle curve actions
     // #############################################################################################
@@ -174,9 +294,21 @@ impl<W: WorldManipulator> Tool<Construct, W> {
         use CurveAction::*;
         self.instance.dir_marker = None;
         match action {
-            Construct(curve) => self.construct_road(gfx_handle, curve),
+            Construct(curve) => {
+                let curve = self.apply_profile(curve);
+                let grade = max_grade(&curve);
+                if grade > self.get_max_grade() {
+                    dbg!("The road is too steep", grade);
+                    return;
+                }
+                self.construct_road(gfx_handle, curve)
+            }
             Render(curve, curve_info) => {
-                self.set_road_tool_mesh(gfx_handle, curve, self.get_sel_node_type());
+                let curve = self.apply_profile(curve);
+                let first = self.end_node_type(&self.instance.curve_tool.get_selected_node());
+                let last = self.end_node_type(&self.instance.curve_tool.get_snapped_node());
+                self.set_road_tool_mesh(gfx_handle, curve.clone(), first, last);
+                self.show_collisions(gfx_handle, curve);
                 dbg!(curve_info);
             }
             Direction(loc, _pos) => {
@@ -191,7 +323,8 @@ impl<W: WorldManipulator> Tool<Construct, W> {
                     .is_building_reverse(self.is_reverse());
                 let (curve, _) =
                     Curve::<Straight>::from_free(loc.pos, loc.pos + loc.dir.flip(!reverse));
-                self.set_road_tool_mesh(gfx_handle, curve.into(), self.get_sel_node_type());
+                let node_type = self.get_sel_node_type();
+                self.set_road_tool_mesh(gfx_handle, curve.into(), node_type, node_type);
             }
             Nothing => {}
         }
@@ -202,19 +335,74 @@ impl<W: WorldManipulator> Tool<Construct, W> {
     }
 
     fn construct_road<G: GfxWorldData>(&mut self, gfx_handle: &mut G, curve: CompositeCurveSum) {
-        let road_builder = match curve {
+        if self.is_bidirectional() {
+            self.construct_bidirectional_road(gfx_handle, curve);
+            return;
+        }
+
+        let road_builder = self.gen_road_builder(curve);
+        let (new_snap, _) = match self.world.add_road(road_builder, self.get_sel_node_type()) {
+            Ok(result) => result,
+            Err(err) => {
+                self.handle_road_gen_error(gfx_handle, err);
+                return;
+            }
+        };
+
+        self.instance.curve_tool.reset(new_snap);
+        self.update_view(gfx_handle);
+    }
+
+    /// Builds both directions of a road along the given curve, which is the center of the road.
+    fn construct_bidirectional_road<G: GfxWorldData>(
+        &mut self,
+        gfx_handle: &mut G,
+        curve: CompositeCurveSum,
+    ) {
+        let road_builder = self.gen_super_road_builder(curve);
+        let (new_snap, _) = match self.world.add_bidirectional_road(road_builder) {
+            Ok(result) => result,
+            Err(err) => {
+                self.handle_road_gen_error(gfx_handle, err);
+                return;
+            }
+        };
+
+        self.instance
+            .curve_tool
+            .reset(new_snap.map(|s| s.center_snap()));
+        self.update_view(gfx_handle);
+    }
+
+    /// The road was not built, so it is still shown together with the reason it was not built.
+    fn handle_road_gen_error<G: GfxWorldData>(&mut self, gfx_handle: &mut G, error: RoadGenErr) {
+        if let RoadGenErr::Collision(segments) = &error {
+            gfx_handle.mark_road_segments(segments.clone());
+        }
+        dbg!(error);
+    }
+
+    /// Generates the road that the curve tool would construct from the given curve.
+    fn gen_road_builder(&self, curve: CompositeCurveSum) -> LRoadBuilder {
+        let mut road_builder = match curve {
             CompositeCurveSum::Single(mut curve) => {
                 let (first, last, reverse) = self.construct_compute_end_nodes();
                 if reverse {
                     curve.reverse();
                 }
 
+                let segment_types = self.compute_segment_types(
+                    self.end_node_type(&first),
+                    self.end_node_type(&last),
+                    1,
+                );
                 let nodes = vec![
                     self.map_end_point(first, curve.first()),
                     self.map_end_point(last, curve.last()),
                 ];
-                let segments = vec![LSegmentBuilder::new(
-                    self.get_sel_road_type().node_type,
+                let segments = vec![LSegmentBuilder::transition(
+                    segment_types[0].from_type(),
+                    segment_types[0].to_type(),
                     curve,
                 )];
                 LRoadBuilder::new(nodes, segments, reverse)
@@ -229,30 +417,66 @@ impl<W: WorldManipulator> Tool<Construct, W> {
                     curve2 = temp;
                 }
 
+                let segment_types = self.compute_segment_types(
+                    self.end_node_type(&first),
+                    self.end_node_type(&last),
+                    2,
+                );
                 let nodes = vec![
                     self.map_end_point(first, curve1.first()),
                     self.map_end_point(None, curve1.last()),
                     self.map_end_point(last, curve2.last()),
                 ];
                 let segments = vec![
-                    LSegmentBuilder::new(self.get_sel_road_type().node_type, curve1),
-                    LSegmentBuilder::new(self.get_sel_road_type().node_type, curve2),
+                    LSegmentBuilder::transition(
+                        segment_types[0].from_type(),
+                        segment_types[0].to_type(),
+                        curve1,
+                    ),
+                    LSegmentBuilder::transition(
+                        segment_types[1].from_type(),
+                        segment_types[1].to_type(),
+                        curve2,
+                    ),
                 ];
                 LRoadBuilder::new(nodes, segments, reverse)
             }
         };
+        road_builder.set_bridge(self.is_bridge());
+        road_builder
+    }
 
-        let road_meshes = self.gen_road_mesh_from_builder(&road_builder, self.get_sel_node_type());
-        let (new_snap, segment_ids) = self.world.add_road(road_builder, self.get_sel_node_type());
-
-        let mut mesh_map: IdMap<SegmentId, RoadMesh> = IdMap::new();
-        for i in 0..segment_ids.len() {
-            mesh_map.insert(segment_ids[i], road_meshes[i].clone());
+    /// Generates the bidirectional road that the curve tool would construct from the given curve.
+    /// The curve tool only knows about the centers of the super nodes, so the super nodes are
+    /// looked up again in the world.
+    fn gen_super_road_builder(&self, curve: CompositeCurveSum) -> LSuperRoadBuilder {
+        let (first, last, reverse) = self.construct_compute_end_nodes();
+        let mut curves: Vec<CurveSum> = match curve {
+            CompositeCurveSum::Single(curve) => vec![curve],
+            CompositeCurveSum::Double(curve1, curve2) => vec![curve1, curve2],
+        };
+        if reverse {
+            curves.reverse();
+            curves.iter_mut().for_each(|c| c.reverse());
         }
-        gfx_handle.add_road_meshes(mesh_map);
 
-        self.instance.curve_tool.reset(new_snap);
-        self.update_view(gfx_handle);
+        let mut road_builder = LSuperRoadBuilder::new(
+            curves,
+            self.get_sel_super_node_type(),
+            first.and_then(|s| self.find_super_snap(&s)),
+            last.and_then(|s| self.find_super_snap(&s)),
+            reverse,
+        );
+        road_builder.set_bridge(self.is_bridge());
+        road_builder
+    }
+
+    /// Finds the {`SuperSnapConfig`} whose center is the given snap config.
+    fn find_super_snap(&self, center: &SnapConfig) -> Option<SuperSnapConfig> {
+        let (_, super_snaps) = self
+            .world
+            .get_super_snap_configs_closest_node(center.pos(), self.get_sel_super_node_type())?;
+        super_snaps.into_iter().find(|s| s.center_snap() == *center)
     }
 
     fn construct_compute_end_nodes(&self) -> (Option<SnapConfig>, Option<SnapConfig>, bool) {
@@ -281,12 +505,63 @@ impl<W: WorldManipulator> Tool<Construct, W> {
         }
     }
 
+    /// Lifts the curve from the ground plane up to the heights of its ends. An end that is snapped
+    /// to a node continues the grade of that node, and any other end is at the selected elevation,
+    /// or at the deck height when building bridges.
+    fn apply_profile(&self, curve: CompositeCurveSum) -> CompositeCurveSum {
+        let (first_height, first_grade) =
+            self.end_height(self.instance.curve_tool.get_selected_node(), false);
+        let (last_height, last_grade) =
+            self.end_height(self.instance.curve_tool.get_snapped_node(), true);
+        let fit = |length| {
+            VerticalProfile::fit(first_height, first_grade, last_height, last_grade, length)
+        };
+        match curve {
+            CompositeCurveSum::Single(mut curve) => {
+                curve.set_profile(fit(curve.get_profile().length()));
+                CompositeCurveSum::Single(curve)
+            }
+            CompositeCurveSum::Double(mut curve1, mut curve2) => {
+                let length1 = curve1.get_profile().length();
+                let profile = fit(length1 + curve2.get_profile().length());
+                let (profile1, profile2) = profile.split(length1);
+                curve1.set_profile(profile1);
+                curve2.set_profile(profile2);
+                CompositeCurveSum::Double(curve1, curve2)
+            }
+        }
+    }
+
+    /// Returns the height at an end of the curve being built, and the grade in the direction of
+    /// the curve if the end is snapped to a node.
+    fn end_height(&self, snap: Option<SnapConfig>, is_last_end: bool) -> (f32, Option<f32>) {
+        let Some(snap) = snap else {
+            let height = if is_last_end {
+                self.get_end_height()
+            } else {
+                self.instance.first_height
+            };
+            return (height, None);
+        };
+        let grade = self.world.get_node_grade(snap.id()).unwrap_or(0.0);
+        let grade = if snap.is_reverse() != is_last_end {
+            -grade
+        } else {
+            grade
+        };
+        (snap.pos().y, Some(grade))
+    }
+
     fn check_snapping(&mut self) -> Option<SnapConfig> {
         // TODO add functionality to report why a node cannot be snapped to.
         if !self.state_handle.road_state.snapping {
             return None;
         }
 
+        if self.is_bidirectional() {
+            return self.check_super_snapping();
+        }
+
         // Get available snaps
         let node_snap_configs = self
             .world
@@ -298,8 +573,18 @@ impl<W: WorldManipulator> Tool<Construct, W> {
 
         if let Some(snap_config) = &self.instance.curve_tool.get_selected_node() {
             snap_configs.retain(|s| s.side() != snap_config.side());
+            // Both ends may be transitions, so the road must be able to go directly between them.
+            snap_configs.retain(|s| {
+                SegmentType::new(snap_config.node_type(), s.node_type()).is_buildable()
+            });
         }
 
+        // The configs for transition segments have the type of the node instead of the selected
+        // type.
+        let sel_node_type = self.get_sel_node_type();
+        let transition = self.is_transition();
+        snap_configs.retain(|s| (s.node_type() != sel_node_type) == transition);
+
         if snap_configs.is_empty() {
             return None;
         }
@@ -307,6 +592,20 @@ impl<W: WorldManipulator> Tool<Construct, W> {
         Some(snap_configs[0].clone())
     }
 
+    /// Bidirectional roads can only snap to super nodes that match the selected super node type
+    /// exactly. The returned snap config is located at the center of the super node.
+    fn check_super_snapping(&self) -> Option<SnapConfig> {
+        let (_, mut super_snaps) = self
+            .world
+            .get_super_snap_configs_closest_node(self.ground_pos, self.get_sel_super_node_type())?;
+
+        if let Some(snap_config) = &self.instance.curve_tool.get_selected_node() {
+            super_snaps.retain(|s| s.side() != snap_config.side());
+        }
+
+        super_snaps.first().map(|s| s.center_snap())
+    }
+
     // #############################################################################################
     // Gfx handling
     // #############################################################################################
@@ -320,11 +619,22 @@ impl<W: WorldManipulator> Tool<Construct, W> {
         } else {
             None
         };
-        let mut possible_snaps: Vec<([f32; 3], [f32; 3])> = self
-            .world
-            .get_possible_snap_nodes(side, self.get_sel_road_type().node_type)
+        let possible_locs: Vec<Loc> = if self.is_bidirectional() {
+            self.world
+                .get_possible_super_snap_nodes(side, self.get_sel_super_node_type())
+                .into_iter()
+                .map(|(_id, loc)| loc)
+                .collect()
+        } else {
+            self.world
+                .get_possible_snap_nodes(side, self.get_sel_road_type().node_type)
+                .into_iter()
+                .map(|(_id, loc)| loc)
+                .collect()
+        };
+        let mut possible_snaps: Vec<([f32; 3], [f32; 3])> = possible_locs
             .iter()
-            .map(|(_id, loc)| (<[f32; 3]>::from(loc.pos), <[f32; 3]>::from(*loc.dir)))
+            .map(|loc| (<[f32; 3]>::from(loc.pos), <[f32; 3]>::from(*loc.dir)))
             .collect();
 
         if let Some(loc) = self.instance.dir_marker {
@@ -334,34 +644,86 @@ impl<W: WorldManipulator> Tool<Construct, W> {
         gfx_handle.set_node_markers(possible_snaps);
     }
 
+    /// Shows the road that is being built going from a node of type `first` to a node of type
+    /// `last`. When building bidirectional roads the curve is the center of the road, and both
+    /// directions are shown.
     fn set_road_tool_mesh<G: GfxWorldData>(
         &self,
         gfx_handle: &mut G,
         curve: CompositeCurveSum,
-        node_type: NodeType,
+        first: NodeType,
+        last: NodeType,
     ) {
-        let mesh = match curve {
-            CompositeCurveSum::Single(curve) => {
-                segment_gen::gen_road_mesh_with_lanes(curve.get_spine(), node_type)
-            }
+        if self.is_bidirectional() {
+            let offset = self.get_sel_super_node_type().direction_offset();
+            let offset_curve = |distance: f32| match &curve {
+                CompositeCurveSum::Single(curve) => {
+                    CompositeCurveSum::Single(curve.offset(distance))
+                }
+                CompositeCurveSum::Double(curve1, curve2) => {
+                    CompositeCurveSum::Double(curve1.offset(distance), curve2.offset(distance))
+                }
+            };
+            let mesh = segment_gen::combine_road_meshes_bad(vec![
+                self.gen_road_tool_mesh(offset_curve(offset), first, last),
+                self.gen_road_tool_mesh(offset_curve(-offset), first, last),
+            ]);
+            gfx_handle.set_road_tool_mesh(Some(mesh));
+            return;
+        }
+
+        let mesh = self.gen_road_tool_mesh(curve, first, last);
+        gfx_handle.set_road_tool_mesh(Some(mesh));
+    }
+
+    /// Marks the existing segments that the road being built would collide with.
+    fn show_collisions<G: GfxWorldData>(&self, gfx_handle: &mut G, curve: CompositeCurveSum) {
+        let mut collisions = if self.is_bidirectional() {
+            let (forward, backward) = self.gen_super_road_builder(curve).gen_road_builders();
+            let mut collisions = self.world.get_road_collisions(&forward);
+            collisions.extend(self.world.get_road_collisions(&backward));
+            collisions
+        } else {
+            self.world
+                .get_road_collisions(&self.gen_road_builder(curve))
+        };
+        collisions.sort_unstable();
+        collisions.dedup();
+        gfx_handle.mark_road_segments(collisions);
+    }
+
+    fn gen_road_tool_mesh(
+        &self,
+        curve: CompositeCurveSum,
+        first: NodeType,
+        last: NodeType,
+    ) -> RoadMesh {
+        let bridge = self.is_bridge();
+        match curve {
+            CompositeCurveSum::Single(curve) => segment_gen::gen_segment_mesh(
+                curve.get_spine(),
+                SegmentType::new(first, last),
+                bridge,
+            ),
             CompositeCurveSum::Double(curve1, curve2) => {
-                let mesh1 = segment_gen::gen_road_mesh_with_lanes(curve1.get_spine(), node_type);
-                let mesh2 = segment_gen::gen_road_mesh_with_lanes(curve2.get_spine(), node_type);
+                let segment_types = self.compute_segment_types(first, last, 2);
+                let mesh1 =
+                    segment_gen::gen_segment_mesh(curve1.get_spine(), segment_types[0], bridge);
+                let mesh2 =
+                    segment_gen::gen_segment_mesh(curve2.get_spine(), segment_types[1], bridge);
                 segment_gen::combine_road_meshes_bad(vec![mesh1, mesh2])
             }
-        };
-        gfx_handle.set_road_tool_mesh(Some(mesh));
+        }
     }
+}
 
-    fn gen_road_mesh_from_builder(
-        &self,
-        road_builder: &LRoadBuilder,
-        node_type: NodeType,
-    ) -> Vec<RoadMesh> {
-        road_builder
-            .get_segments()
-            .iter()
-            .map(|s| segment_gen::gen_road_mesh_with_lanes(s.get_spine(), node_type))
-            .collect::<Vec<RoadMesh>>()
+/// Returns the steepest grade along the curve.
+fn max_grade(curve: &CompositeCurveSum) -> f32 {
+    match curve {
+        CompositeCurveSum::Single(curve) => curve.get_profile().max_grade(),
+        CompositeCurveSum::Double(curve1, curve2) => curve1
+            .get_profile()
+            .max_grade()
+            .max(curve2.get_profile().max_grade()),
     }
 }
@@ -23,8 +23,10 @@ impl From<EndPoint> for PosOrLoc {
     fn from(value: EndPoint) -> Self {
         match value {
             EndPoint::New(pos) => PosOrLoc::Pos(pos),
+            // The curves are built in the ground plane, and are lifted up to the height of the
+            // node afterwards.
             EndPoint::Old(snap_config) => PosOrLoc::Loc(Loc::new(
-                snap_config.pos(),
+                snap_config.pos() * Vec3::new(1.0, 0.0, 1.0),
                 snap_config.dir().flip(snap_config.is_reverse()).into(),
             )),
         }
@@ -87,6 +89,9 @@ pub trait CurveToolSpec {
 
     fn get_snapped_node(&self) -> Option<SnapConfig>;
 
+    /// Returns true if the first point of the curve has been selected.
+    fn has_first_point(&self) -> bool;
+
     fn is_building_reverse(&self, state_reverse: bool) -> bool;
 }
 
@@ -220,6 +225,10 @@ where
         self.snapped_node.clone()
     }
 
+    fn has_first_point(&self) -> bool {
+        self.first_point.is_some()
+    }
+
     fn is_building_reverse(&self, state_reverse: bool) -> bool {
         match (&self.first_point, &self.snapped_node) {
             (Some(EndPoint::Old(first_snap)), None) => first_snap.is_reverse(),
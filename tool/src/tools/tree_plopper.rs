@@ -23,11 +23,9 @@ impl<G: GfxWorldData, W: WorldManipulator> ToolUnique<G> for Tool<TreePlopper, W
 
     fn process_keyboard(&mut self, _gfx_handle: &mut G, _key: utils::input::KeyAction) {}
 
-    fn left_click(&mut self, gfx_handle: &mut G) {
+    fn left_click(&mut self, _gfx_handle: &mut G) {
         if let Some(tree) = self.instance.tree_builder {
-            let id = self.world.add_tree(tree, utils::consts::TREE_MODEL_ID);
-            let raw_trees = vec![(id, tree.pos().into(), tree.yrot())];
-            gfx_handle.add_trees(utils::consts::TREE_MODEL_ID, raw_trees);
+            self.world.add_tree(tree, utils::consts::TREE_MODEL_ID);
         }
     }
 
@@ -1,15 +1,17 @@
 mod circular;
+mod composite;
 mod cubic;
 mod quadratic;
 mod straight;
 
 pub use circular::Circular;
+pub use composite::Composite;
 pub use cubic::Cubic;
 pub use quadratic::Quadratic;
 pub use straight::Straight;
 use utils::math::Loc;
 
-use crate::Spine;
+use crate::{GuidePoints, Spine, VerticalProfile};
 
 use thiserror::Error;
 
@@ -41,12 +43,25 @@ pub trait CurveShared {
 
     /// Reverses this curve
     fn reverse(&mut self);
+
+    /// Returns the heights of this curve.
+    fn get_profile(&self) -> &VerticalProfile;
+
+    /// Gives this curve the heights of the given profile. The profile is stretched to the
+    /// horizontal length of this curve.
+    fn set_profile(&mut self, profile: VerticalProfile);
 }
 
 trait CurveUnique {
     fn compute_spine(&self) -> Spine;
     fn reverse(&mut self);
     fn contains_pos(&self, pos: Vec3, width: f32) -> bool;
+    fn offset(&self, distance: f32) -> Self;
+    fn split(&self, length: f32) -> (Self, Self)
+    where
+        Self: Sized;
+    /// Returns the guide points of the bezier curves that make up this curve, in order.
+    fn pieces(&self) -> Vec<GuidePoints>;
 }
 
 #[derive(Debug, Clone, Copy)]
@@ -103,6 +118,16 @@ pub enum CurveError {
 
 pub type CurveResult<C> = std::result::Result<C, CurveError>;
 
+/// The kinds of curves that a {`CurveSum`} can be.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CurveKind {
+    Straight,
+    Circular,
+    Quadratic,
+    Cubic,
+    Composite,
+}
+
 #[enum_dispatch(CurveShared)]
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum CurveSum {
@@ -110,6 +135,95 @@ pub enum CurveSum {
     Circular(Curve<Circular>),
     Quadratic(Curve<Quadratic>),
     Cubic(Curve<Cubic>),
+    Composite(Curve<Composite>),
+}
+
+impl CurveSum {
+    pub fn kind(&self) -> CurveKind {
+        match self {
+            CurveSum::Straight(_) => CurveKind::Straight,
+            CurveSum::Circular(_) => CurveKind::Circular,
+            CurveSum::Quadratic(_) => CurveKind::Quadratic,
+            CurveSum::Cubic(_) => CurveKind::Cubic,
+            CurveSum::Composite(_) => CurveKind::Composite,
+        }
+    }
+
+    /// Returns a curve that is parallel to this one, at the given distance to the right hand side
+    /// of this curve. Negative distances are to the left hand side.
+    pub fn offset(&self, distance: f32) -> CurveSum {
+        let mut offset: CurveSum = match self {
+            CurveSum::Straight(curve) => Curve::from(curve.instance.offset(distance)).into(),
+            CurveSum::Circular(curve) => Curve::from(curve.instance.offset(distance)).into(),
+            CurveSum::Quadratic(curve) => Curve::from(curve.instance.offset(distance)).into(),
+            CurveSum::Cubic(curve) => Curve::from(curve.instance.offset(distance)).into(),
+            CurveSum::Composite(curve) => Curve::from(curve.instance.offset(distance)).into(),
+        };
+        offset.set_profile(self.get_profile().clone());
+        offset
+    }
+
+    /// Splits this curve in two at the point the given number of meters along it. The first
+    /// returned curve ends where the second one starts.
+    pub fn split(&self, length: f32) -> (CurveSum, CurveSum) {
+        fn halves<C: CurveUnique>(curve: &Curve<C>, length: f32) -> (CurveSum, CurveSum)
+        where
+            Curve<C>: Into<CurveSum>,
+        {
+            let (first, last) = curve.instance.split(length);
+            (Curve::from(first).into(), Curve::from(last).into())
+        }
+        let (mut first, mut last) = match self {
+            CurveSum::Straight(curve) => halves(curve, length),
+            CurveSum::Circular(curve) => halves(curve, length),
+            CurveSum::Quadratic(curve) => halves(curve, length),
+            CurveSum::Cubic(curve) => halves(curve, length),
+            CurveSum::Composite(curve) => halves(curve, length),
+        };
+        let (first_profile, last_profile) = self.get_profile().split(length);
+        first.set_profile(first_profile);
+        last.set_profile(last_profile);
+        (first, last)
+    }
+
+    /// Joins this curve with the given curve, which must start where this curve ends. The result
+    /// is of the same kind as the two curves if they are the two parts of such a curve, and a
+    /// {`Composite`} curve otherwise. The pieces that meet where the curves are joined are merged
+    /// into one piece if they are the parts of a single bezier curve.
+    pub fn join(&self, other: &CurveSum) -> CurveSum {
+        let mut pieces = self.pieces();
+        let mut last = other.pieces();
+        if let (Some(a), Some(b)) = (pieces.last(), last.first()) {
+            if let Some(joined) = GuidePoints::join(a, b) {
+                pieces.pop();
+                last[0] = joined;
+            }
+        }
+        pieces.extend(last);
+
+        let mut joined: CurveSum = match (self, other, pieces.len()) {
+            (CurveSum::Straight(_), CurveSum::Straight(_), 1) => {
+                Curve::from(Straight::from_guide_points(pieces.remove(0))).into()
+            }
+            (CurveSum::Circular(_), CurveSum::Circular(_), 1) => {
+                Curve::from(Circular::from_guide_points(pieces.remove(0))).into()
+            }
+            _ => Curve::from(Composite::from_guide_points(pieces)).into(),
+        };
+        joined.set_profile(self.get_profile().join(other.get_profile()));
+        joined
+    }
+
+    /// Returns the guide points of the bezier curves that make up this curve, in order.
+    pub fn pieces(&self) -> Vec<GuidePoints> {
+        match self {
+            CurveSum::Straight(curve) => curve.instance.pieces(),
+            CurveSum::Circular(curve) => curve.instance.pieces(),
+            CurveSum::Quadratic(curve) => curve.instance.pieces(),
+            CurveSum::Cubic(curve) => curve.instance.pieces(),
+            CurveSum::Composite(curve) => curve.instance.pieces(),
+        }
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -117,18 +231,34 @@ pub struct Curve<C> {
     instance: C,
     length: f32,
     spine: Spine,
+    profile: VerticalProfile,
 }
 
 impl<C: CurveUnique> CurveSpec for Curve<C> {}
 
 impl<C: CurveUnique> From<C> for Curve<C> {
+    /// The heights of the curve go with a constant grade from the height of its first guide point
+    /// to the height of its last.
     fn from(value: C) -> Self {
-        let spine = value.compute_spine();
+        let mut spine = value.compute_spine();
+        let heights = spine.first().zip(spine.last());
+        let profile = heights.map_or(VerticalProfile::flat(0.0, 0.0), |(first, last)| {
+            VerticalProfile::fit(
+                first.pos.y,
+                None,
+                last.pos.y,
+                None,
+                spine.compute_horizontal_length(),
+            )
+        });
+        spine.apply_profile(&profile);
+        let length = spine.compute_length();
 
         Self {
             instance: value,
-            length: 0.0,
+            length,
             spine,
+            profile,
         }
     }
 }
@@ -157,5 +287,21 @@ impl<C: CurveUnique> CurveShared for Curve<C> {
     fn reverse(&mut self) {
         self.instance.reverse();
         self.spine = self.instance.compute_spine();
+        self.set_profile(self.profile.reverse());
+    }
+
+    fn get_profile(&self) -> &VerticalProfile {
+        &self.profile
+    }
+
+    fn set_profile(&mut self, profile: VerticalProfile) {
+        let horizontal_length = self.spine.compute_horizontal_length();
+        self.profile = if profile.length() > 0.0 {
+            profile.scaled(horizontal_length / profile.length())
+        } else {
+            profile
+        };
+        self.spine.apply_profile(&self.profile);
+        self.length = self.spine.compute_length();
     }
 }
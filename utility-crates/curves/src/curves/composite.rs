@@ -0,0 +1,70 @@
+use glam::Vec3;
+use serde::{Deserialize, Serialize};
+
+use crate::{GuidePoints, Spine};
+
+use super::CurveUnique;
+
+/// Several bezier curves joined end to end. Used for curves that cannot be described by a single
+/// curve of the other kinds, such as two curves that have been merged.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Composite {
+    guide_points: Vec<GuidePoints>,
+}
+
+impl Composite {
+    pub(crate) fn from_guide_points(guide_points: Vec<GuidePoints>) -> Self {
+        Self { guide_points }
+    }
+}
+
+impl CurveUnique for Composite {
+    fn compute_spine(&self) -> Spine {
+        Spine::from_guide_points_vec(&self.guide_points)
+    }
+
+    fn reverse(&mut self) {
+        GuidePoints::reverse_vec(&mut self.guide_points)
+    }
+
+    fn contains_pos(&self, pos: Vec3, width: f32) -> bool {
+        self.guide_points.iter().any(|g| g.is_inside(pos, width))
+    }
+
+    fn offset(&self, distance: f32) -> Self {
+        Self {
+            guide_points: self
+                .guide_points
+                .iter()
+                .map(|g| g.offset(distance))
+                .collect(),
+        }
+    }
+
+    fn split(&self, length: f32) -> (Self, Self) {
+        let mut first = vec![];
+        let mut last = vec![];
+        let mut remaining = length;
+        for guide_points in self.guide_points.iter() {
+            let piece_length = guide_points.length();
+            if remaining <= 0.0 {
+                last.push(guide_points.clone());
+            } else if remaining >= piece_length {
+                first.push(guide_points.clone());
+            } else {
+                let (a, b) = guide_points.split(guide_points.t_at_length(remaining));
+                first.push(a);
+                last.push(b);
+            }
+            remaining -= piece_length;
+        }
+        (
+            Self::from_guide_points(first),
+            Self::from_guide_points(last),
+        )
+    }
+
+    fn pieces(&self) -> Vec<GuidePoints> {
+        self.guide_points.clone()
+    }
+}
@@ -1,7 +1,7 @@
 use glam::Vec3;
 use serde::{Deserialize, Serialize};
 
-use crate::Spine;
+use crate::{GuidePoints, Spine};
 
 use super::CurveUnique;
 
@@ -19,4 +19,16 @@ impl CurveUnique for Quadratic {
     fn contains_pos(&self, _pos: Vec3, _width: f32) -> bool {
         true
     }
+
+    fn offset(&self, _distance: f32) -> Self {
+        self.clone()
+    }
+
+    fn split(&self, _length: f32) -> (Self, Self) {
+        (self.clone(), self.clone())
+    }
+
+    fn pieces(&self) -> Vec<GuidePoints> {
+        vec![]
+    }
 }
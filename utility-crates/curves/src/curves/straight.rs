@@ -18,6 +18,10 @@ impl Straight {
         let guide_points = GuidePoints::from_two_points(first_pos, last_pos);
         Self { guide_points }
     }
+
+    pub(crate) fn from_guide_points(guide_points: GuidePoints) -> Self {
+        Self { guide_points }
+    }
 }
 
 impl CurveUnique for Straight {
@@ -32,6 +36,27 @@ impl CurveUnique for Straight {
     fn contains_pos(&self, pos: Vec3, width: f32) -> bool {
         self.guide_points.is_inside(pos, width)
     }
+
+    fn offset(&self, distance: f32) -> Self {
+        Self {
+            guide_points: self.guide_points.offset(distance),
+        }
+    }
+
+    fn split(&self, length: f32) -> (Self, Self) {
+        let t = self.guide_points.t_at_length(length);
+        let (first, last) = self.guide_points.split(t);
+        (
+            Self {
+                guide_points: first,
+            },
+            Self { guide_points: last },
+        )
+    }
+
+    fn pieces(&self) -> Vec<GuidePoints> {
+        vec![self.guide_points.clone()]
+    }
 }
 
 impl Curve<Straight> {
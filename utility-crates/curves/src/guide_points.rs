@@ -1,6 +1,15 @@
+use utils::math::VecUtils;
+
 use glam::Vec3;
 use serde::{Deserialize, Serialize};
 
+/// The number of points sampled along a curve when measuring lengths along it.
+const LENGTH_SAMPLES: usize = 256;
+
+/// Two sets of guide points are joined if they are the parts of a single curve within this many
+/// meters.
+const JOIN_TOLERANCE: f32 = 0.05;
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct GuidePoints(Vec<Vec3>);
 
@@ -88,6 +97,153 @@ impl GuidePoints {
         v.normalize()
     }
 
+    /// Returns the guide points of a curve that is parallel to this one, at the given distance to
+    /// the right hand side of this curve. Negative distances are to the left hand side. When these
+    /// guide points approximate a circle, the returned guide points approximate the circle with
+    /// the same center.
+    pub(crate) fn offset(&self, distance: f32) -> Self {
+        let first_dir = self.calc_bezier_dir(0.0);
+        let last_dir = self.calc_bezier_dir(1.0);
+        let first_normal = first_dir.right_hand();
+        let last_normal = last_dir.right_hand();
+
+        let first = self[0];
+        let last = self[self.len() - 1];
+        let new_first = first + first_normal * distance;
+        let new_last = last + last_normal * distance;
+
+        let inner = match self.len() {
+            3 => {
+                let mid = if first_dir.intersects_in_xz(last_dir) {
+                    new_first.intersection_in_xz(first_dir, new_last, last_dir)
+                } else {
+                    self[1] + first_normal * distance
+                };
+                vec![mid]
+            }
+            4 => {
+                // The center of the circle is where the normals of the end points intersect. The
+                // handles are scaled by how much the radius changes.
+                let scale = if first_normal.intersects_in_xz(last_normal) {
+                    let center = first.intersection_in_xz(first_normal, last, last_normal);
+                    (center - new_first).length() / (center - first).length()
+                } else {
+                    1.0
+                };
+                vec![
+                    new_first + (self[1] - first) * scale,
+                    new_last + (self[2] - last) * scale,
+                ]
+            }
+            _ => self[1..self.len() - 1]
+                .iter()
+                .map(|p| *p + first_normal * distance)
+                .collect(),
+        };
+
+        let mut points = vec![new_first];
+        points.extend(inner);
+        points.push(new_last);
+        GuidePoints::from_vec(points)
+    }
+
+    /// Splits the curve in two at `t` using de Casteljau's algorithm. Together the two returned
+    /// curves trace exactly the same path as this one.
+    pub(crate) fn split(&self, t: f32) -> (Self, Self) {
+        let mut first = vec![self[0]];
+        let mut last = vec![self[self.len() - 1]];
+        let mut points = self.0.clone();
+        while points.len() > 1 {
+            points = points.windows(2).map(|w| w[0].lerp(w[1], t)).collect();
+            first.push(points[0]);
+            last.push(points[points.len() - 1]);
+        }
+        last.reverse();
+        (GuidePoints::from_vec(first), GuidePoints::from_vec(last))
+    }
+
+    /// Returns the guide points of the curve that was split in two to get `first` and `last`, if
+    /// they are the two parts of a single curve. This is the inverse of {`split`}.
+    pub(crate) fn join(first: &Self, last: &Self) -> Option<Self> {
+        let n = first.len();
+        if n < 2 || last.len() != n || (first[n - 1] - last[0]).length() > JOIN_TOLERANCE {
+            return None;
+        }
+        // The split point divides the line between the two inner points of the last level of de
+        // Casteljau's algorithm in the ratio t : 1 - t.
+        let left = (first[n - 1] - first[n - 2]).length();
+        let right = (last[1] - last[0]).length();
+        if left + right == 0.0 {
+            return None;
+        }
+        let t = left / (left + right);
+
+        // Each guide point of `first` is a weighted sum of the guide points of the joined curve
+        // with the same or lower index, and each guide point of `last` of those with the same or
+        // higher index. The first half is recovered from `first` and the second from `last`, which
+        // keeps the divisions by t and 1 - t to a minimum.
+        let binomial =
+            |k: usize, j: usize| (0..j).fold(1.0, |acc, i| acc * (k - i) as f32 / (i + 1) as f32);
+        let mut points = vec![Vec3::ZERO; n];
+        let half = (n - 1) / 2;
+        for k in 0..=half {
+            let known: Vec3 = (0..k)
+                .map(|j| {
+                    points[j] * binomial(k, j) * (1.0 - t).powi((k - j) as i32) * t.powi(j as i32)
+                })
+                .sum();
+            points[k] = (first[k] - known) / t.powi(k as i32);
+        }
+        for k in (half + 1..n).rev() {
+            let m = n - 1 - k;
+            let known: Vec3 = (0..m)
+                .map(|j| {
+                    points[n - 1 - j]
+                        * binomial(m, j)
+                        * t.powi((m - j) as i32)
+                        * (1.0 - t).powi(j as i32)
+                })
+                .sum();
+            points[k] = (last[n - 1 - m] - known) / (1.0 - t).powi(m as i32);
+        }
+        let joined = GuidePoints::from_vec(points);
+
+        let (new_first, new_last) = joined.split(t);
+        let matches = |a: &Self, b: &Self| {
+            a.iter()
+                .zip(b.iter())
+                .all(|(p, q)| (*p - *q).length() < JOIN_TOLERANCE)
+        };
+        (matches(&new_first, first) && matches(&new_last, last)).then_some(joined)
+    }
+
+    /// Returns the `t` at which the curve has covered the given length, measured by sampling the
+    /// curve. Lengths beyond the end of the curve give 1.0.
+    pub(crate) fn t_at_length(&self, length: f32) -> f32 {
+        let mut covered = 0.0;
+        let mut prev = self.calc_bezier_pos(0.0);
+        for i in 1..=LENGTH_SAMPLES {
+            let pos = self.calc_bezier_pos(i as f32 / LENGTH_SAMPLES as f32);
+            let step = (pos - prev).length();
+            if step > 0.0 && covered + step >= length {
+                return (i as f32 - 1.0 + (length - covered) / step) / LENGTH_SAMPLES as f32;
+            }
+            covered += step;
+            prev = pos;
+        }
+        1.0
+    }
+
+    /// Returns the length of the curve, measured by sampling the curve.
+    pub(crate) fn length(&self) -> f32 {
+        (0..=LENGTH_SAMPLES)
+            .map(|i| self.calc_bezier_pos(i as f32 / LENGTH_SAMPLES as f32))
+            .collect::<Vec<Vec3>>()
+            .windows(2)
+            .map(|w| (w[1] - w[0]).length())
+            .sum()
+    }
+
     pub fn reverse_vec(vec: &mut Vec<Self>) {
         vec.reverse();
         for guide_points in vec.iter_mut() {
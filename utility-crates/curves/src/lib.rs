@@ -2,12 +2,14 @@ mod curves;
 mod guide_points;
 mod spine;
 mod spine_points;
+mod vertical_profile;
 
 pub use guide_points::GuidePoints;
 pub use spine::Spine;
 pub use spine_points::SpinePoints;
+pub use vertical_profile::VerticalProfile;
 
 pub use curves::{
-    Circular, CompositeCurveSum, Cubic, Curve, CurveError, CurveInfo, CurveResult, CurveShared,
-    CurveSpec, CurveSum, Quadratic, Straight,
+    Circular, Composite, CompositeCurveSum, Cubic, Curve, CurveError, CurveInfo, CurveKind,
+    CurveResult, CurveShared, CurveSpec, CurveSum, Quadratic, Straight,
 };
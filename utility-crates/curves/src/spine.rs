@@ -1,8 +1,8 @@
-use crate::{GuidePoints, SpinePoints};
+use crate::{GuidePoints, SpinePoints, VerticalProfile};
 
 use utils::math::{Loc, VecUtils};
 
-use glam::Vec3;
+use glam::{Vec3, Vec3Swizzles};
 use serde::{Deserialize, Serialize};
 
 /// Spines always have a uniform distribution of their points.
@@ -51,6 +51,19 @@ impl Spine {
         spine.make_uniform()
     }
 
+    /// Will make the returned spine uniform. The curves given by the guide points must be joined
+    /// end to end.
+    pub fn from_guide_points_vec(guide_points: &[GuidePoints]) -> Self {
+        let mut spine = Spine::empty();
+        for guide_points in guide_points.iter() {
+            let piece = Spine::from_guide_points(guide_points);
+            // The first point of a piece is the last point of the piece before it.
+            let skip = if spine.is_empty() { 0 } else { 1 };
+            spine.extend(piece.iter().skip(skip));
+        }
+        spine.make_uniform()
+    }
+
     fn make_uniform(self) -> Self {
         // Note that .0 is pos and .1 is dir. Probably not ideal.
 
@@ -91,14 +104,74 @@ impl Spine {
         uniform_spine
     }
 
-    /// Generates a set of parallel spine_points ordered from left to right.
-    pub fn gen_parallel(&self, path_width: f32, no_paths: u8) -> Vec<SpinePoints> {
+    /// Returns the length of the path going through all points of this spine.
+    pub fn compute_length(&self) -> f32 {
+        self.windows(2)
+            .map(|locs| (locs[1].pos - locs[0].pos).length())
+            .sum()
+    }
+
+    /// Returns the length of the path going through all points of this spine, as seen from above.
+    pub fn compute_horizontal_length(&self) -> f32 {
+        self.windows(2)
+            .map(|locs| (locs[1].pos - locs[0].pos).xz().length())
+            .sum()
+    }
+
+    /// Returns the smallest radius of the curvature of this spine as seen from above, estimated
+    /// from the circle through every three consecutive points. Returns {`f32::INFINITY`} if the
+    /// spine is straight.
+    pub fn compute_min_radius(&self) -> f32 {
+        self.windows(3)
+            .map(|locs| {
+                let (a, b, c) = (locs[0].pos.xz(), locs[1].pos.xz(), locs[2].pos.xz());
+                let double_area = (b - a).perp_dot(c - a).abs();
+                if double_area < f32::EPSILON {
+                    return f32::INFINITY;
+                }
+                (b - a).length() * (c - b).length() * (c - a).length() / (2.0 * double_area)
+            })
+            .fold(f32::INFINITY, f32::min)
+    }
+
+    /// Sets the height of every point to the height of the profile at that point. The profile is
+    /// stretched to cover the whole spine.
+    pub fn apply_profile(&mut self, profile: &VerticalProfile) {
+        let horizontal_length = self.compute_horizontal_length();
+        let scale = if horizontal_length > 0.0 {
+            profile.length() / horizontal_length
+        } else {
+            0.0
+        };
+        let mut covered = 0.0;
+        let mut prev = self.first().map(|loc| loc.pos);
+        for loc in self.iter_mut() {
+            if let Some(prev) = prev {
+                covered += (loc.pos - prev).xz().length();
+            }
+            prev = Some(loc.pos);
+            loc.pos.y = profile.height_at(covered * scale);
+        }
+    }
+
+    /// Generates a set of parallel spine_points ordered from left to right. The spacing between the
+    /// paths changes linearly from `first_width` at the start of the spine to `last_width` at the
+    /// end of the spine.
+    pub fn gen_parallel(
+        &self,
+        first_width: f32,
+        last_width: f32,
+        no_paths: u8,
+    ) -> Vec<SpinePoints> {
         let mut paths = Vec::with_capacity(no_paths.into());
         for _ in 0..no_paths {
             paths.push(SpinePoints::with_capacity(self.len()));
         }
 
-        for loc in self.iter() {
+        let last_index = (self.len() - 1).max(1) as f32;
+        for (j, loc) in self.iter().enumerate() {
+            let t = j as f32 / last_index;
+            let path_width = first_width + (last_width - first_width) * t;
             let space = Vec3::from(loc.dir).right_hand() * path_width;
             let left_most = loc.pos - (no_paths as f32 / 2.) * space;
             for (i, path) in paths.iter_mut().enumerate() {
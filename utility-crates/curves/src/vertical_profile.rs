@@ -0,0 +1,250 @@
+//! The heights of a curve. The curves themselves only describe the road in the ground plane, and
+//! the profile lifts it up. Following the usual practice for roads, the profile consists of
+//! parabolic vertical curves, such that the grade changes at a constant rate within each of them.
+use serde::{Deserialize, Serialize};
+
+/// A single parabolic vertical curve. At `x` horizontal meters into the vertical curve the height
+/// is `height + grade * x + rate * x * x`.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+struct VerticalCurve {
+    length: f32,
+    height: f32,
+    grade: f32,
+    rate: f32,
+}
+
+impl VerticalCurve {
+    fn height_at(&self, x: f32) -> f32 {
+        self.height + self.grade * x + self.rate * x * x
+    }
+
+    fn grade_at(&self, x: f32) -> f32 {
+        self.grade + 2.0 * self.rate * x
+    }
+
+    fn split(&self, x: f32) -> (Self, Self) {
+        let first = Self { length: x, ..*self };
+        let last = Self {
+            length: self.length - x,
+            height: self.height_at(x),
+            grade: self.grade_at(x),
+            rate: self.rate,
+        };
+        (first, last)
+    }
+
+    fn reverse(&self) -> Self {
+        Self {
+            length: self.length,
+            height: self.height_at(self.length),
+            grade: -self.grade_at(self.length),
+            rate: self.rate,
+        }
+    }
+}
+
+/// The heights along a curve, given by parabolic vertical curves joined end to end with matching
+/// heights and grades. Distances are measured horizontally along the curve, and grades are given
+/// as the rise per horizontal meter in the direction of the curve.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct VerticalProfile(Vec<VerticalCurve>);
+
+impl VerticalProfile {
+    /// A profile of the given length at a constant height.
+    pub fn flat(height: f32, length: f32) -> Self {
+        Self::fit(height, None, height, None, length)
+    }
+
+    /// Returns the profile going from `first_height` to `last_height` over the given length. A
+    /// grade that is given at an end is kept there. Without any grades the profile has a constant
+    /// grade, with one grade it is a single parabola, and with both grades it consists of two
+    /// parabolas that meet halfway.
+    pub fn fit(
+        first_height: f32,
+        first_grade: Option<f32>,
+        last_height: f32,
+        last_grade: Option<f32>,
+        length: f32,
+    ) -> Self {
+        if length <= 0.0 {
+            return Self(vec![VerticalCurve {
+                length: 0.0,
+                height: first_height,
+                grade: 0.0,
+                rate: 0.0,
+            }]);
+        }
+        let rise = last_height - first_height;
+        match (first_grade, last_grade) {
+            (None, None) => Self(vec![VerticalCurve {
+                length,
+                height: first_height,
+                grade: rise / length,
+                rate: 0.0,
+            }]),
+            (Some(grade), None) => Self(vec![VerticalCurve {
+                length,
+                height: first_height,
+                grade,
+                rate: (rise - grade * length) / (length * length),
+            }]),
+            (None, Some(grade)) => {
+                Self::fit(last_height, Some(-grade), first_height, None, length).reverse()
+            }
+            (Some(first_grade), Some(last_grade)) => {
+                // The parabolas meet at the middle m with the same height and grade, which gives
+                // 3 * first_rate + last_rate = (rise - 2 * first_grade * m) / m^2 and
+                // first_rate + last_rate = (last_grade - first_grade) / (2 * m).
+                let m = length / 2.0;
+                let rate_sum = (last_grade - first_grade) / (2.0 * m);
+                let first_rate = ((rise - 2.0 * first_grade * m) / (m * m) - rate_sum) / 2.0;
+                let first = VerticalCurve {
+                    length: m,
+                    height: first_height,
+                    grade: first_grade,
+                    rate: first_rate,
+                };
+                let last = VerticalCurve {
+                    length: m,
+                    height: first.height_at(m),
+                    grade: first.grade_at(m),
+                    rate: rate_sum - first_rate,
+                };
+                Self(vec![first, last])
+            }
+        }
+    }
+
+    /// The horizontal length covered by this profile.
+    pub fn length(&self) -> f32 {
+        self.0.iter().map(|c| c.length).sum()
+    }
+
+    /// Returns the vertical curve at `x` horizontal meters along the profile, and how far into that
+    /// vertical curve `x` is.
+    fn locate(&self, x: f32) -> (&VerticalCurve, f32) {
+        let mut x = x.max(0.0);
+        for curve in self.0.iter() {
+            if x <= curve.length {
+                return (curve, x);
+            }
+            x -= curve.length;
+        }
+        let last = &self.0[self.0.len() - 1];
+        (last, last.length)
+    }
+
+    /// The height at `x` horizontal meters along the profile.
+    pub fn height_at(&self, x: f32) -> f32 {
+        let (curve, x) = self.locate(x);
+        curve.height_at(x)
+    }
+
+    /// The grade at `x` horizontal meters along the profile.
+    pub fn grade_at(&self, x: f32) -> f32 {
+        let (curve, x) = self.locate(x);
+        curve.grade_at(x)
+    }
+
+    pub fn first_height(&self) -> f32 {
+        self.height_at(0.0)
+    }
+
+    pub fn last_height(&self) -> f32 {
+        self.height_at(self.length())
+    }
+
+    pub fn first_grade(&self) -> f32 {
+        self.grade_at(0.0)
+    }
+
+    pub fn last_grade(&self) -> f32 {
+        self.grade_at(self.length())
+    }
+
+    /// Returns the steepest grade of the profile, going either up or down. Since the grade changes
+    /// linearly within each vertical curve, it is the steepest at the end of one of them.
+    pub fn max_grade(&self) -> f32 {
+        self.0
+            .iter()
+            .map(|c| c.grade_at(0.0).abs().max(c.grade_at(c.length).abs()))
+            .fold(0.0, f32::max)
+    }
+
+    /// Returns every vertical curve of the profile as the horizontal distance along the profile
+    /// where it starts, together with its height, grade and rate at that point. The height `x`
+    /// meters into a vertical curve is `height + grade * x + rate * x * x`.
+    pub fn pieces(&self) -> Vec<(f32, [f32; 3])> {
+        let mut start = 0.0;
+        self.0
+            .iter()
+            .map(|c| {
+                let piece = (start, [c.height, c.grade, c.rate]);
+                start += c.length;
+                piece
+            })
+            .collect()
+    }
+
+    /// Splits the profile in two at `x` horizontal meters along it.
+    pub fn split(&self, x: f32) -> (Self, Self) {
+        let mut first = vec![];
+        let mut last = vec![];
+        let mut remaining = x;
+        for curve in self.0.iter() {
+            if remaining <= 0.0 {
+                last.push(*curve);
+            } else if remaining >= curve.length {
+                first.push(*curve);
+            } else {
+                let (a, b) = curve.split(remaining);
+                first.push(a);
+                last.push(b);
+            }
+            remaining -= curve.length;
+        }
+        // A split at either end leaves an empty half, which still needs a height.
+        let at_split = VerticalCurve {
+            length: 0.0,
+            height: self.height_at(x),
+            grade: self.grade_at(x),
+            rate: 0.0,
+        };
+        if first.is_empty() {
+            first.push(at_split);
+        }
+        if last.is_empty() {
+            last.push(at_split);
+        }
+        (Self(first), Self(last))
+    }
+
+    /// Returns the profile that continues with `other` where this one ends.
+    pub fn join(&self, other: &VerticalProfile) -> Self {
+        Self(self.0.iter().chain(other.0.iter()).copied().collect())
+    }
+
+    /// Returns the same profile seen from the other end.
+    pub fn reverse(&self) -> Self {
+        Self(self.0.iter().rev().map(|c| c.reverse()).collect())
+    }
+
+    /// Returns this profile stretched horizontally by the given factor, such that the heights
+    /// stay the same at the same fraction of the length.
+    pub fn scaled(&self, factor: f32) -> Self {
+        if factor <= 0.0 {
+            return self.clone();
+        }
+        Self(
+            self.0
+                .iter()
+                .map(|c| VerticalCurve {
+                    length: c.length * factor,
+                    height: c.height,
+                    grade: c.grade / factor,
+                    rate: c.rate / (factor * factor),
+                })
+                .collect(),
+        )
+    }
+}
@@ -4,6 +4,12 @@ pub const LANE_MARKINGS_WIDTH: f32 = 0.2;
 pub const LANE_MARKINGS_LENGTH: f32 = 5.0;
 pub const ROAD_HEIGHT: f32 = 0.2;
 pub const ROAD_MIN_LENGTH: f32 = 10.0;
+/// The steepest grade that roads are allowed to have by default, as rise per horizontal meter.
+pub const MAX_GRADE: f32 = 0.06;
+/// The smallest vertical distance between two roads that cross each other by default.
+pub const MIN_CLEARANCE: f32 = 5.0;
+/// The height above the ground that bridges are built at by default.
+pub const BRIDGE_DECK_HEIGHT: f32 = 7.0;
 pub const DEFAULT_DIR: Vec3 = Vec3::new(1.0, 0.0, 0.0);
 
 // Figure out what these two do
@@ -10,5 +10,6 @@ pub use id_set::IdSet;
 
 pub type NodeId = Id<id_manager::NodeMarker, u16>;
 pub type SegmentId = Id<id_manager::SegmentMarker, u16>;
+pub type SuperNodeId = Id<id_manager::SuperNodeMarker, u16>;
 pub type TreeId = Id<id_manager::TreeMarker, u16>;
 pub type VehicleId = Id<id_manager::VehicleMarker, u32>;
@@ -1,6 +1,7 @@
 use num::{Bounded, FromPrimitive, Integer, ToPrimitive};
 use serde::{Deserialize, Serialize};
 
+use std::fmt;
 use std::marker::PhantomData;
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, PartialOrd, Ord)]
@@ -8,6 +9,8 @@ pub struct NodeMarker;
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, PartialOrd, Ord)]
 pub struct SegmentMarker;
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, PartialOrd, Ord)]
+pub struct SuperNodeMarker;
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, PartialOrd, Ord)]
 pub struct TreeMarker;
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, PartialOrd, Ord)]
 pub struct VehicleMarker;
@@ -25,6 +28,11 @@ pub trait IdBehaviour {
     /// TODO benchmark how much this casting affects performance. If counter in IdManager was
     /// generic as well, there would probaly be no performance hit because no casting.
     fn from_usize(val: usize) -> Self;
+    /// Returns {`None`} if the value does not fit in the integer type of the id. Use this for
+    /// values that come from outside of the program, such as save games.
+    fn try_from_usize(val: usize) -> Option<Self>
+    where
+        Self: Sized;
     fn to_usize(&self) -> usize;
 }
 
@@ -36,11 +44,28 @@ impl<A, Int: Integer + Bounded + FromPrimitive + ToPrimitive> IdBehaviour for Id
         }
     }
 
+    fn try_from_usize(val: usize) -> Option<Self> {
+        Some(Self {
+            id: Int::from_usize(val)?,
+            marker: PhantomData,
+        })
+    }
+
     fn to_usize(&self) -> usize {
         self.id.to_usize().unwrap()
     }
 }
 
+/// Shows the number of the id, such that it can be written to files that refer to it. An id can
+/// not be created from its number outside of this module.
+impl<A, Int: Integer + Bounded + FromPrimitive + ToPrimitive + fmt::Display> fmt::Display
+    for Id<A, Int>
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.id.fmt(f)
+    }
+}
+
 #[derive(Serialize, Deserialize)]
 // pub struct IdManager<A = Id<M, Int>> {
 pub struct IdManager<A: IdBehaviour> {
@@ -167,7 +167,7 @@ impl<K: IdBehaviour, V, S> IdMap<K, V, S> {
         self.vec.shrink_to_fit();
     }
 
-    pub fn contains_key(&mut self, k: K) -> bool {
+    pub fn contains_key(&self, k: K) -> bool {
         let k_num = k.to_usize();
         if k_num >= self.capacity() {
             return false;
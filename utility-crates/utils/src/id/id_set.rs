@@ -1,7 +1,7 @@
 use super::IdBehaviour;
 
 use fixedbitset::FixedBitSet;
-use serde::{Deserialize, Serialize};
+use serde::{de::Error, Deserialize, Deserializer, Serialize, Serializer};
 
 use rand::{thread_rng, Rng};
 
@@ -9,7 +9,7 @@ use std::marker::PhantomData;
 
 const RESERVE_CHUNKS: usize = 8192;
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone)]
 pub struct IdSet<V: IdBehaviour> {
     set: FixedBitSet,
     marker: PhantomData<V>,
@@ -45,7 +45,7 @@ impl<V: IdBehaviour> IdSet<V> {
     pub fn reserve(&mut self, additional: usize) {
         // round up to nearest multiple of RESERVE_CHUNKS
         let additional = additional + (RESERVE_CHUNKS - 1) & !(RESERVE_CHUNKS - 1);
-        let bits = self.len() + additional;
+        let bits = self.capacity() + additional;
         self.set.grow(bits);
     }
 
@@ -101,8 +101,10 @@ impl<V: IdBehaviour> IdSet<V> {
         let capacity = self.capacity();
 
         if v_num >= capacity {
+            // The set must hold at least v_num + 1 bits for v_num to be put into it.
             let additional = v_num - capacity + 1;
             self.reserve(additional);
+            self.set.grow(v_num + 1);
         }
 
         self.set.put(v_num)
@@ -130,3 +132,23 @@ impl<V: IdBehaviour> IdSet<V> {
         })
     }
 }
+
+/// An `IdSet` is serialized as the list of ids it contains, such that the encoding does not depend
+/// on the capacity of the set and can be read back by any deserializer.
+impl<V: IdBehaviour> Serialize for IdSet<V> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_seq(self.set.ones().collect::<Vec<usize>>())
+    }
+}
+
+impl<'de, V: IdBehaviour> Deserialize<'de> for IdSet<V> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let mut result = Self::new();
+        for v_num in Vec::<usize>::deserialize(deserializer)? {
+            let v = V::try_from_usize(v_num)
+                .ok_or_else(|| D::Error::custom(format!("id {} is out of range", v_num)))?;
+            result.insert(v);
+        }
+        Ok(result)
+    }
+}
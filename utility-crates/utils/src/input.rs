@@ -36,13 +36,21 @@ pub enum Action {
 
     ToggleSnapping,
     ToggleReverse,
+    ToggleTransition,
+    ToggleBidirectional,
+    ToggleBridge,
 
     CycleCurveType,
     CycleLaneWidth,
     CycleNoLanes,
+    CycleMedianWidth,
+
+    RaiseElevation,
+    LowerElevation,
 
     ToggleBulldozeRoads,
     ToggleBulldozeTrees,
+    ToggleBulldozeNodes,
 
     EnterBulldoze,
     EnterConstruct,
@@ -50,6 +58,13 @@ pub enum Action {
 
     ToggleCurvatureGfx,
 
+    SaveGame,
+    LoadGame,
+    PrintStats,
+
+    Undo,
+    Redo,
+
     Exit,
     Esc,
 }
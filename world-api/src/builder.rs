@@ -1,4 +1,4 @@
-use super::NodeType;
+use super::{LaneWidth, NodeType, SegmentType};
 
 use curves::{CurveShared, CurveSum, Spine};
 use utils::id::SegmentId;
@@ -62,27 +62,113 @@ pub enum LaneMapConfig {
 pub enum LSegmentBuilderType {
     /// Find a better naming convention for these types.
     Same(NodeType),
-    // SameWidth
-    // SameNoLanes
+    /// A transition segment where the lane width is the same at both ends, but where lanes are
+    /// added or dropped.
+    SameWidth {
+        lane_width: LaneWidth,
+        from_no_lanes: u8,
+        to_no_lanes: u8,
+    },
+    /// A transition segment where the number of lanes is the same at both ends, but where the
+    /// width of the lanes changes.
+    SameNoLanes {
+        no_lanes: u8,
+        from_lane_width: LaneWidth,
+        to_lane_width: LaneWidth,
+    },
+}
+
+impl LSegmentBuilderType {
+    /// Returns the types of the two ends of the segment.
+    pub fn segment_type(&self) -> SegmentType {
+        match self {
+            Self::Same(node_type) => (*node_type).into(),
+            Self::SameWidth {
+                lane_width,
+                from_no_lanes,
+                to_no_lanes,
+            } => SegmentType::new(
+                NodeType::new(*lane_width, *from_no_lanes),
+                NodeType::new(*lane_width, *to_no_lanes),
+            ),
+            Self::SameNoLanes {
+                no_lanes,
+                from_lane_width,
+                to_lane_width,
+            } => SegmentType::new(
+                NodeType::new(*from_lane_width, *no_lanes),
+                NodeType::new(*to_lane_width, *no_lanes),
+            ),
+        }
+    }
 }
 
 #[derive(Debug, Clone)]
 pub struct LSegmentBuilder {
     node_config: LSegmentBuilderType,
     curve: CurveSum,
+    /// The segment is carried by a bridge deck on piers instead of lying on the ground.
+    bridge: bool,
 }
 
 impl LSegmentBuilder {
     pub fn new(node_type: NodeType, curve: CurveSum) -> Self {
         let node_config = LSegmentBuilderType::Same(node_type);
 
-        Self { node_config, curve }
+        Self {
+            node_config,
+            curve,
+            bridge: false,
+        }
+    }
+
+    /// Creates a segment that goes from a node of type `from` to a node of type `to`. If the types
+    /// differ, the segment is a transition segment.
+    ///
+    /// Panics if no kind of transition segment exists between the two types, see
+    /// {`SegmentType::is_buildable`}.
+    pub fn transition(from: NodeType, to: NodeType, curve: CurveSum) -> Self {
+        let node_config = if from == to {
+            LSegmentBuilderType::Same(from)
+        } else if from.lane_width() == to.lane_width() {
+            LSegmentBuilderType::SameWidth {
+                lane_width: from.lane_width(),
+                from_no_lanes: from.no_lanes(),
+                to_no_lanes: to.no_lanes(),
+            }
+        } else if from.no_lanes() == to.no_lanes() {
+            LSegmentBuilderType::SameNoLanes {
+                no_lanes: from.no_lanes(),
+                from_lane_width: from.lane_width(),
+                to_lane_width: to.lane_width(),
+            }
+        } else {
+            panic!("No transition segment exists from {:?} to {:?}", from, to)
+        };
+
+        Self {
+            node_config,
+            curve,
+            bridge: false,
+        }
+    }
+
+    pub fn set_bridge(&mut self, bridge: bool) {
+        self.bridge = bridge;
+    }
+
+    pub fn is_bridge(&self) -> bool {
+        self.bridge
     }
 
     pub fn consume(self) -> (LSegmentBuilderType, CurveSum) {
         (self.node_config, self.curve)
     }
 
+    pub fn segment_type(&self) -> SegmentType {
+        self.node_config.segment_type()
+    }
+
     pub fn get_curve(&self) -> &CurveSum {
         &self.curve
     }
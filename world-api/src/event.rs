@@ -0,0 +1,24 @@
+use super::Tree;
+
+use utils::id::{NodeId, SegmentId, TreeId};
+
+/// A single change of the world. Every change is reported, whether it was made directly, or by
+/// undoing or redoing an edit.
+#[derive(Debug, Clone)]
+pub enum WorldEvent {
+    /// A segment has been added. A segment that changed is removed and then added again.
+    SegmentAdded(SegmentId),
+    SegmentRemoved(SegmentId),
+    NodeAdded(NodeId),
+    /// The segments attached to a node or the configuration of its lanes has changed.
+    NodeChanged(NodeId),
+    NodeRemoved(NodeId),
+    /// A tree has been added together with its model_id.
+    TreeAdded(TreeId, Tree, u128),
+    /// A tree has been removed together with its model_id.
+    TreeRemoved(TreeId, u128),
+}
+
+/// Identifies a subscriber of the events of a world.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SubscriberId(pub usize);
@@ -0,0 +1,24 @@
+use serde::{Deserialize, Serialize};
+use utils::id::SegmentId;
+
+/// Identifies a single lane of a segment. Lanes are indexed from left to right as seen in the
+/// driving direction, by their index at the start of the segment.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+pub struct LaneId {
+    segment: SegmentId,
+    lane: u8,
+}
+
+impl LaneId {
+    pub fn new(segment: SegmentId, lane: u8) -> Self {
+        Self { segment, lane }
+    }
+
+    pub fn segment(&self) -> SegmentId {
+        self.segment
+    }
+
+    pub fn lane(&self) -> u8 {
+        self.lane
+    }
+}
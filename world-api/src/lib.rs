@@ -1,38 +1,109 @@
 mod builder;
+mod event;
+mod lane;
+mod query;
 mod road_builder;
 mod road_type;
+mod route;
 mod snap;
+mod stats;
 mod tree;
 
 use std::time::Duration;
 
 pub use builder::*;
+pub use event::*;
+pub use lane::*;
+pub use query::*;
 pub use road_builder::*;
 pub use road_type::*;
+pub use route::*;
 pub use snap::*;
+pub use stats::*;
 pub use tree::*;
 
+use curves::CurveSum;
 use glam::Vec3;
-use utils::id::{NodeId, SegmentId, TreeId};
+use utils::id::{NodeId, SegmentId, SuperNodeId, TreeId};
 use utils::math::Loc;
 
 pub trait WorldManipulator:
-    RoadManipulator + TreeManipulator + IdGetter + SimController + SimData
+    RoadManipulator
+    + RoadQuery
+    + SuperNodeManipulator
+    + TreeManipulator
+    + HistoryManipulator
+    + EventStream
+    + RouteQuery
+    + IdGetter
+    + SimController
+    + SimData
 {
     fn update(&mut self, dt: Duration);
 }
 
 pub trait RoadManipulator {
     /// The node_type parameter is temporary until implementation of transition segments.
+    /// If the road overlaps any existing segments nothing is added, and the overlapped segments
+    /// are returned in {`RoadGenErr::Collision`}.
     fn add_road(
         &mut self,
         road: LRoadBuilder,
         sel_node_type: NodeType,
-    ) -> (Option<SnapConfig>, Vec<SegmentId>);
+    ) -> Result<(Option<SnapConfig>, Vec<SegmentId>), RoadGenErr>;
+
+    /// Returns the existing segments that the given road would overlap if it was added. Segments
+    /// attached to the nodes that the road snaps to are allowed to touch the road close to those
+    /// nodes.
+    fn get_road_collisions(&self, road: &LRoadBuilder) -> Vec<SegmentId>;
 
     /// The return bool signals whether the segment was allowed to be removed or not.
     fn remove_segment(&mut self, segment_id: SegmentId) -> bool;
 
+    /// Removes the node together with every segment attached to it. The nodes at the other end
+    /// of the segments are updated, and are removed as well if they have no segments left.
+    /// Returns the ids of the removed segments, or {`None`} if the node does not exist or if a
+    /// segment cannot be removed without splitting the node at its other end. In that case
+    /// nothing is removed.
+    fn remove_node(&mut self, node_id: NodeId) -> Option<Vec<SegmentId>>;
+
+    /// Returns the ids of every segment attached to the given node.
+    fn get_node_segments(&self, node_id: NodeId) -> Vec<SegmentId>;
+
+    /// Returns the smallest vertical distance that two segments must have where they cross each
+    /// other. Roads that would cross an existing segment closer than this are not built.
+    fn get_min_clearance(&self) -> f32;
+
+    /// Sets the smallest vertical distance that two segments must have where they cross each
+    /// other. Returns false without changing it if the clearance is negative or not finite, as
+    /// such a clearance would let roads be built through each other.
+    fn set_min_clearance(&mut self, clearance: f32) -> bool;
+
+    /// Returns true if the given segment is carried by a bridge.
+    fn is_segment_bridge(&self, id: SegmentId) -> bool;
+
+    /// Returns the grade of the road at the given node, as the rise per horizontal meter in the
+    /// direction of the node. Returns {`None`} if the node does not exist.
+    fn get_node_grade(&self, node_id: NodeId) -> Option<f32>;
+
+    /// Splits the segment in two at the point the given number of meters along its curve, by
+    /// inserting a new symmetric node there. The original segment is removed. Returns the new
+    /// node and the two new segments in driving order, or {`None`} if the segment does not exist,
+    /// is a transition segment, or if the length is not finite or either part would be shorter than
+    /// the minimum road length.
+    fn split_segment(
+        &mut self,
+        segment_id: SegmentId,
+        length: f32,
+    ) -> Option<(NodeId, [SegmentId; 2])>;
+
+    /// Removes a symmetric node and joins its incoming and outgoing segment into a single new
+    /// segment, which is returned. This is the reverse of {`split_segment`}. Returns {`None`}
+    /// without changing anything if the node does not exist, is not symmetric, or if the two
+    /// segments are transition segments, connect the same pair of nodes, or only one of them is a
+    /// bridge.
+    fn merge_segments(&mut self, node_id: NodeId) -> Option<SegmentId>;
+
     /// Returns a list of node id's that have an open slot for the selected road type to snap to
     /// together with that nodes pos and dir.
     /// If side parameter is set to {`None`}, then no direction is checked when matching nodes.
@@ -50,15 +121,136 @@ pub trait RoadManipulator {
         node_type: NodeType,
     ) -> Option<(NodeId, Vec<SnapConfig>)>;
 
+    /// Returns the ids of all segments in the world.
+    fn get_segment_ids(&self) -> Vec<SegmentId>;
+
+    /// Returns every lane of every segment in the world. Together with
+    /// {`get_lane_successors`} this defines a directed graph of the lanes, which can be used for
+    /// simulation and analysis of the road network.
+    fn get_lanes(&self) -> Vec<LaneId>;
+
+    /// Returns the lanes that a car in the given lane can continue into at the end of the lane's
+    /// segment without changing lanes. Where a road splits each lane continues into exactly one of
+    /// the new roads, and where lanes are added the rightmost lane can continue into all of them.
+    /// A lane that ends at a dead end has no successors.
+    fn get_lane_successors(&self, lane: LaneId) -> &[LaneId];
+
+    /// Returns the curve and segment type of the given segment. This is what is needed to generate
+    /// the graphics of a segment that was not built by a tool, such as one from a save game.
+    fn get_segment_curve(&self, id: SegmentId) -> (&CurveSum, SegmentType);
+
     fn debug_node(&self, id: NodeId);
     fn debug_segment(&self, id: SegmentId);
 }
 
+/// Read-only access to the road network, for everything that needs to look at all of the roads
+/// without changing them, such as exporters, statistics and inspectors.
+pub trait RoadQuery {
+    /// Returns every node in the world, in no particular order.
+    fn nodes(&self) -> impl Iterator<Item = NodeView> + '_;
+
+    /// Returns every segment in the world, in no particular order.
+    fn segments(&self) -> impl Iterator<Item = SegmentView<'_>>;
+
+    /// Returns the node, or {`None`} if it does not exist.
+    fn get_node(&self, id: NodeId) -> Option<NodeView>;
+
+    /// Returns the segment, or {`None`} if it does not exist.
+    fn get_segment(&self, id: SegmentId) -> Option<SegmentView<'_>>;
+
+    /// Returns every segment attached to the node together with the lanes of the node that it
+    /// occupies. The segments on each side are ordered from left to right, and a segment with the
+    /// type of the node occupies all of its lanes. Returns {`None`} if the node does not exist.
+    fn get_node_lane_map(&self, id: NodeId) -> Option<Vec<LaneMapEntry>>;
+
+    /// Counts and measures the whole road network. This goes through every node and segment, so
+    /// it should not be called every frame.
+    fn compute_stats(&self) -> NetworkStats;
+}
+
+/// Super nodes group the nodes of the two directions of a bidirectional road. They are kept on top
+/// of the road graph, which itself is not aware of them.
+pub trait SuperNodeManipulator {
+    /// Adds both directions of the bidirectional road as a single edit. Returns the super node
+    /// that the tool can continue building from, if any, together with the ids of the new
+    /// segments of both directions. If either direction overlaps existing segments, neither is
+    /// added.
+    fn add_bidirectional_road(
+        &mut self,
+        road: LSuperRoadBuilder,
+    ) -> Result<(Option<SuperSnapConfig>, Vec<SegmentId>), RoadGenErr>;
+
+    /// Returns a list of super nodes that have an open slot for the selected super node type to
+    /// snap to together with the location of their center.
+    /// If side parameter is set to {`None`}, then no direction is checked when matching super
+    /// nodes.
+    fn get_possible_super_snap_nodes(
+        &self,
+        side: Option<Side>,
+        super_type: SuperNodeType,
+    ) -> Vec<(SuperNodeId, Loc)>;
+
+    /// If no super node is within range of pos, then this function returns {`None`}. Otherwise it
+    /// returns the closest super node to pos, and all its possible {`SuperSnapConfig`}'s. Only
+    /// super nodes of exactly the selected super node type can be snapped to.
+    fn get_super_snap_configs_closest_node(
+        &self,
+        ground_pos: Vec3,
+        super_type: SuperNodeType,
+    ) -> Option<(SuperNodeId, Vec<SuperSnapConfig>)>;
+}
+
+/// Finds routes through the road network. Routes always follow the direction of the segments, and
+/// the length of the segments is used as the cost of driving through them.
+pub trait RouteQuery {
+    /// Returns the shortest route that starts at the node `from` and ends at the node `to`, or
+    /// {`None`} if `to` cannot be reached from `from`. The route from a node to itself is empty.
+    fn find_route(&self, from: NodeId, to: NodeId) -> Option<Route>;
+
+    /// Returns the shortest route between the segments or nodes located at the two positions, or
+    /// {`None`} if there is no road at either position or if no route exists. A route starting on
+    /// a segment drives through the rest of that segment, and a route ending on a segment drives
+    /// through that segment as its last step. If both positions are on the same segment and `to`
+    /// lies behind `from`, the route leaves the segment and has to come back around to it.
+    fn find_route_between_positions(&self, from: Vec3, to: Vec3) -> Option<Route>;
+}
+
 pub trait TreeManipulator {
     fn add_tree(&mut self, tree: Tree, model_id: u128) -> TreeId;
     /// Returns the model_id of the tree that has been removed.
     fn remove_tree(&mut self, tree_id: TreeId) -> u128;
     fn get_tree_pos(&self, id: TreeId) -> Vec3;
+    /// Returns all trees in the world grouped by their model_id.
+    fn get_trees(&self) -> Vec<(u128, Vec<(TreeId, Tree)>)>;
+}
+
+/// Every edit of roads and trees is recorded, such that it can be undone and redone. Undoing an
+/// edit restores the exact ids and configurations of the nodes, segments and trees it touched.
+pub trait HistoryManipulator {
+    /// Reverts the most recent edit that has not been undone. Returns {`false`} if there is
+    /// nothing to undo. The changes are reported as events.
+    fn undo(&mut self) -> bool;
+
+    /// Reapplies the most recently undone edit. Returns {`false`} if there is nothing to redo.
+    /// The changes are reported as events. Making a new edit discards all edits that could be
+    /// redone.
+    fn redo(&mut self) -> bool;
+}
+
+/// Tells observers, such as the graphics or the simulation, how the world changes, such that they
+/// do not depend on whoever made the change to tell them. Every subscriber receives every event
+/// that happens after it subscribed, in the order that they happened.
+pub trait EventStream {
+    /// Adds a new subscriber, which receives every event from now on.
+    fn subscribe(&mut self) -> SubscriberId;
+
+    /// Removes the subscriber, and discards the events that it has not drained. Its id is never
+    /// handed out again.
+    fn unsubscribe(&mut self, subscriber: SubscriberId);
+
+    /// Returns the events that happened since the subscriber last drained its events. This is
+    /// meant to be called once every frame.
+    fn drain_events(&mut self, subscriber: SubscriberId) -> Vec<WorldEvent>;
 }
 
 pub trait IdGetter {
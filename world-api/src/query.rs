@@ -0,0 +1,171 @@
+use super::{NodeType, SegmentType, Side, SnapRange};
+
+use curves::{CurveShared, CurveSum};
+use utils::id::{NodeId, SegmentId};
+use utils::math::Loc;
+
+/// Describes how the segments of a node are attached to it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NodeMode {
+    /// Only one segment is attached, and it has the type of the node.
+    Basic,
+    /// An incoming and an outgoing segment are attached, and both have the type of the node.
+    Sym,
+    /// A segment with the type of the node is attached on one side, and any number of smaller
+    /// segments are attached on the other side.
+    Asym,
+    /// No segment with the type of the node is attached. The smaller segments are attached on the
+    /// side opposite of the open side.
+    Open,
+}
+
+/// A single segment in the lane map of a node, together with the lanes of the node that it
+/// occupies.
+#[derive(Debug, Clone, PartialEq)]
+pub struct LaneMapEntry {
+    segment_id: SegmentId,
+    side: Side,
+    node_type: NodeType,
+    snap_range: SnapRange,
+}
+
+/// A read-only view of a node in the road graph.
+#[derive(Debug, Clone)]
+pub struct NodeView {
+    id: NodeId,
+    loc: Loc,
+    node_type: NodeType,
+    mode: NodeMode,
+}
+
+/// A read-only view of a segment in the road graph.
+#[derive(Debug, Clone, Copy)]
+pub struct SegmentView<'a> {
+    id: SegmentId,
+    curve: &'a CurveSum,
+    segment_type: SegmentType,
+    from_node: NodeId,
+    to_node: NodeId,
+    bridge: bool,
+}
+
+// #################################################################################################
+// Implementation of LaneMapEntry
+// #################################################################################################
+impl LaneMapEntry {
+    pub fn new(
+        segment_id: SegmentId,
+        side: Side,
+        node_type: NodeType,
+        snap_range: SnapRange,
+    ) -> Self {
+        Self {
+            segment_id,
+            side,
+            node_type,
+            snap_range,
+        }
+    }
+
+    pub fn segment_id(&self) -> SegmentId {
+        self.segment_id
+    }
+
+    /// The side of the node that the segment is attached to.
+    pub fn side(&self) -> Side {
+        self.side
+    }
+
+    /// The type of the segment at the node.
+    pub fn node_type(&self) -> NodeType {
+        self.node_type
+    }
+
+    /// The lanes of the node that the segment occupies, indexed from the left of the node.
+    pub fn snap_range(&self) -> &SnapRange {
+        &self.snap_range
+    }
+}
+
+// #################################################################################################
+// Implementation of NodeView
+// #################################################################################################
+impl NodeView {
+    pub fn new(id: NodeId, loc: Loc, node_type: NodeType, mode: NodeMode) -> Self {
+        Self {
+            id,
+            loc,
+            node_type,
+            mode,
+        }
+    }
+
+    pub fn id(&self) -> NodeId {
+        self.id
+    }
+
+    pub fn loc(&self) -> Loc {
+        self.loc
+    }
+
+    pub fn node_type(&self) -> NodeType {
+        self.node_type
+    }
+
+    pub fn mode(&self) -> NodeMode {
+        self.mode
+    }
+}
+
+// #################################################################################################
+// Implementation of SegmentView
+// #################################################################################################
+impl<'a> SegmentView<'a> {
+    pub fn new(
+        id: SegmentId,
+        curve: &'a CurveSum,
+        segment_type: SegmentType,
+        [from_node, to_node]: [NodeId; 2],
+        bridge: bool,
+    ) -> Self {
+        Self {
+            id,
+            curve,
+            segment_type,
+            from_node,
+            to_node,
+            bridge,
+        }
+    }
+
+    pub fn id(&self) -> SegmentId {
+        self.id
+    }
+
+    pub fn curve(&self) -> &'a CurveSum {
+        self.curve
+    }
+
+    pub fn segment_type(&self) -> SegmentType {
+        self.segment_type
+    }
+
+    /// The node at the start of the segment in the driving direction.
+    pub fn from_node(&self) -> NodeId {
+        self.from_node
+    }
+
+    /// The node at the end of the segment in the driving direction.
+    pub fn to_node(&self) -> NodeId {
+        self.to_node
+    }
+
+    pub fn is_bridge(&self) -> bool {
+        self.bridge
+    }
+
+    /// The length of the segment in meters.
+    pub fn length(&self) -> f32 {
+        self.curve.get_length()
+    }
+}
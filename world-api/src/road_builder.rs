@@ -1,16 +1,21 @@
-use super::{LNodeBuilder, LSegmentBuilder, NodeType, SnapConfig};
+use super::{LNodeBuilder, LSegmentBuilder, NodeType, SnapConfig, SuperNodeType, SuperSnapConfig};
 
-use curves::CurveShared;
+use curves::{CurveShared, CurveSum};
+use utils::id::SegmentId;
 use utils::math::{DirXZ, Loc};
 
 use glam::Vec3;
 
 /// TODO add better error types.
+#[derive(Debug, Clone)]
 pub enum RoadGenErr {
     Placeholder,
     CCSFailed,
     DoubleSnapFailed,
-    Collision,
+    /// The road overlaps the given existing segments.
+    Collision(Vec<SegmentId>),
+    /// No curves could be fitted to the path of the road.
+    CurveFitFailed,
 }
 
 #[derive(Debug, Clone)]
@@ -38,7 +43,7 @@ impl LNodeBuilderType {
         }
     }
 
-    fn node_type(&self) -> NodeType {
+    fn _node_type(&self) -> NodeType {
         match self {
             New(b) => b.node_type(),
             Old(s) => s.node_type(),
@@ -99,12 +104,125 @@ impl LRoadBuilder {
         (self.nodes, self.segments, self.reverse)
     }
 
-    /// NOTE: temporary should be removed once transition segments
-    pub fn get_first_node_type(&self) -> NodeType {
-        self.nodes[0].node_type()
+    pub fn get_nodes(&self) -> &Vec<LNodeBuilderType> {
+        &self.nodes
     }
 
     pub fn get_segments(&self) -> &Vec<LSegmentBuilder> {
         &self.segments
     }
+
+    /// Builds every segment of the road as a bridge, or none of them.
+    pub fn set_bridge(&mut self, bridge: bool) {
+        self.segments.iter_mut().for_each(|s| s.set_bridge(bridge));
+    }
+}
+
+/// Defines a bidirectional road, which consists of two roads going in opposite directions that
+/// are separated by the median of the super node type. The curves define the center of the road,
+/// and are given in the driving direction of the forward road. The forward road is offset to the
+/// right of the curves, and the backward road to the left.
+#[derive(Debug, Clone)]
+pub struct LSuperRoadBuilder {
+    curves: Vec<CurveSum>,
+    super_type: SuperNodeType,
+    first: Option<SuperSnapConfig>,
+    last: Option<SuperSnapConfig>,
+    reverse: bool,
+    bridge: bool,
+}
+
+impl LSuperRoadBuilder {
+    pub fn new(
+        curves: Vec<CurveSum>,
+        super_type: SuperNodeType,
+        first: Option<SuperSnapConfig>,
+        last: Option<SuperSnapConfig>,
+        reverse: bool,
+    ) -> Self {
+        Self {
+            curves,
+            super_type,
+            first,
+            last,
+            reverse,
+            bridge: false,
+        }
+    }
+
+    /// Builds both directions of the road as bridges, or none of them.
+    pub fn set_bridge(&mut self, bridge: bool) {
+        self.bridge = bridge;
+    }
+
+    pub fn super_type(&self) -> SuperNodeType {
+        self.super_type
+    }
+
+    pub fn is_reverse(&self) -> bool {
+        self.reverse
+    }
+
+    /// Generates the forward and the backward road. The nodes of the backward road are ordered
+    /// opposite of those of the forward road, such that the first node of the forward road and the
+    /// last node of the backward road belong to the same super node.
+    pub fn gen_road_builders(self) -> (LRoadBuilder, LRoadBuilder) {
+        let node_type = self.super_type.node_type();
+        let offset = self.super_type.direction_offset();
+
+        let forward_curves: Vec<CurveSum> = self.curves.iter().map(|c| c.offset(offset)).collect();
+        let backward_curves: Vec<CurveSum> = self
+            .curves
+            .iter()
+            .rev()
+            .map(|c| {
+                let mut curve = c.offset(-offset);
+                curve.reverse();
+                curve
+            })
+            .collect();
+
+        let mut forward = Self::gen_road_builder(
+            forward_curves,
+            node_type,
+            self.first.as_ref().map(|s| s.forward().clone()),
+            self.last.as_ref().map(|s| s.forward().clone()),
+            self.reverse,
+        );
+        let mut backward = Self::gen_road_builder(
+            backward_curves,
+            node_type,
+            self.last.as_ref().map(|s| s.backward().clone()),
+            self.first.as_ref().map(|s| s.backward().clone()),
+            !self.reverse,
+        );
+        forward.set_bridge(self.bridge);
+        backward.set_bridge(self.bridge);
+        (forward, backward)
+    }
+
+    fn gen_road_builder(
+        curves: Vec<CurveSum>,
+        node_type: NodeType,
+        first: Option<SnapConfig>,
+        last: Option<SnapConfig>,
+        reverse: bool,
+    ) -> LRoadBuilder {
+        let map_end_point = |snap: Option<SnapConfig>, loc: Loc| match snap {
+            Some(snap) => Old(snap),
+            None => LNodeBuilderType::new(loc, node_type),
+        };
+
+        let mut nodes = vec![map_end_point(first, curves[0].first())];
+        for curve in curves[..curves.len() - 1].iter() {
+            nodes.push(LNodeBuilderType::new(curve.last(), node_type));
+        }
+        nodes.push(map_end_point(last, curves[curves.len() - 1].last()));
+
+        let segments = curves
+            .into_iter()
+            .map(|curve| LSegmentBuilder::new(node_type, curve))
+            .collect();
+        LRoadBuilder::new(nodes, segments, reverse)
+    }
 }
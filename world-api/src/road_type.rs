@@ -75,6 +75,159 @@ impl NodeType {
     }
 }
 
+/// Defines the discrete set of widths of the median that separates the two directions of a
+/// bidirectional road.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum MedianWidth {
+    /// The two directions are right next to each other.
+    #[default]
+    None,
+    Narrow,
+    Wide,
+}
+
+impl MedianWidth {
+    /// Returns the width of this median as an f32.
+    pub fn getf32(&self) -> f32 {
+        use MedianWidth::*;
+        match self {
+            None => 0.0,
+            Narrow => 2.0,
+            Wide => 6.0,
+        }
+    }
+}
+
+/// Defines the types of super nodes that are possible. A super node groups the nodes of a
+/// bidirectional road, where both directions have the same node type and are separated by a
+/// median.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct SuperNodeType {
+    node_type: NodeType,
+    median: MedianWidth,
+}
+
+impl SuperNodeType {
+    pub fn new(node_type: NodeType, median: MedianWidth) -> Self {
+        Self { node_type, median }
+    }
+
+    /// The node type of each of the two directions.
+    pub fn node_type(&self) -> NodeType {
+        self.node_type
+    }
+
+    pub fn median(&self) -> MedianWidth {
+        self.median
+    }
+
+    /// Returns the total width of both directions and the median.
+    pub fn compute_width(&self) -> f32 {
+        2.0 * self.node_type.compute_width() + self.median.getf32()
+    }
+
+    /// Returns the distance from the center of the road to the center of each direction.
+    pub fn direction_offset(&self) -> f32 {
+        (self.node_type.compute_width() + self.median.getf32()) / 2.0
+    }
+}
+
+/// Defines the types of the two ends of a segment. A segment whose ends have different types is a
+/// transition segment, along which the road gradually changes from one type to the other.
+///
+/// The lanes at each end of a segment are indexed from left to right according to the type at that
+/// end, such that the nodes never see the transition. When the number of lanes changes, lanes are
+/// added or dropped on the right side of the road. When the lane width changes, every lane changes
+/// width gradually along the segment.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct SegmentType {
+    from: NodeType,
+    to: NodeType,
+}
+
+impl From<NodeType> for SegmentType {
+    fn from(node_type: NodeType) -> Self {
+        Self::new(node_type, node_type)
+    }
+}
+
+impl SegmentType {
+    pub fn new(from: NodeType, to: NodeType) -> Self {
+        Self { from, to }
+    }
+
+    /// The type of the end of the segment at its from node.
+    pub fn from_type(&self) -> NodeType {
+        self.from
+    }
+
+    /// The type of the end of the segment at its to node.
+    pub fn to_type(&self) -> NodeType {
+        self.to
+    }
+
+    pub fn is_transition(&self) -> bool {
+        self.from != self.to
+    }
+
+    /// Returns true if a segment can be built between the two types. A transition segment can
+    /// change either the lane width or the number of lanes, but not both.
+    pub fn is_buildable(&self) -> bool {
+        self.from.lane_width() == self.to.lane_width() || self.from.no_lanes() == self.to.no_lanes()
+    }
+
+    /// Returns the number of lanes of whichever end of the segment has the most lanes.
+    pub fn max_no_lanes(&self) -> u8 {
+        self.from.no_lanes().max(self.to.no_lanes())
+    }
+
+    /// Returns the width of each lane at the fraction `t` of the way along the segment, ordered
+    /// from left to right. There is always {`max_no_lanes`} entries, where lanes that are added or
+    /// dropped along the segment have zero width at the end where they do not exist.
+    pub fn lane_widths_at(&self, t: f32) -> Vec<f32> {
+        let end_width = |node_type: NodeType, lane: u8| {
+            if lane < node_type.no_lanes() {
+                node_type.lane_width_f32()
+            } else {
+                0.0
+            }
+        };
+        (0..self.max_no_lanes())
+            .map(|lane| {
+                let from_width = end_width(self.from, lane);
+                let to_width = end_width(self.to, lane);
+                from_width + (to_width - from_width) * t
+            })
+            .collect()
+    }
+
+    /// Returns the total width of the road at the fraction `t` of the way along the segment.
+    pub fn width_at(&self, t: f32) -> f32 {
+        self.lane_widths_at(t).iter().sum()
+    }
+
+    /// Returns the lane at the end of the segment that a car in the given lane at the start of the
+    /// segment ends up in, if it does not change lanes. Cars in a lane that is dropped merge into
+    /// the rightmost remaining lane.
+    pub fn get_exit_lane(&self, entry_lane: u8) -> u8 {
+        entry_lane.min(self.to.no_lanes() - 1)
+    }
+
+    /// Returns every lane at the end of the segment that a car in the given lane at the start of
+    /// the segment can end up in without changing lanes. This is the lane given by
+    /// {`get_exit_lane`}, except for the rightmost lane when lanes are added, which can continue
+    /// into all of the added lanes.
+    pub fn get_exit_lanes(&self, entry_lane: u8) -> Vec<u8> {
+        let from_no_lanes = self.from.no_lanes();
+        let to_no_lanes = self.to.no_lanes();
+        if entry_lane == from_no_lanes - 1 && to_no_lanes > from_no_lanes {
+            (entry_lane..to_no_lanes).collect()
+        } else {
+            vec![self.get_exit_lane(entry_lane)]
+        }
+    }
+}
+
 /// Defines the two sides of a node.
 #[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
 pub enum Side {
@@ -0,0 +1,52 @@
+use serde::{Deserialize, Serialize};
+use utils::id::SegmentId;
+
+/// A single segment of a {`Route`}, together with the lanes of the segment that lead to the next
+/// segment of the route without changing lanes. For the last segment of a route every lane is
+/// included.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RouteStep {
+    segment: SegmentId,
+    lanes: Vec<u8>,
+}
+
+impl RouteStep {
+    pub fn new(segment: SegmentId, lanes: Vec<u8>) -> Self {
+        Self { segment, lanes }
+    }
+
+    pub fn segment(&self) -> SegmentId {
+        self.segment
+    }
+
+    /// The lanes are indexed by their index at the start of the segment.
+    pub fn lanes(&self) -> &[u8] {
+        &self.lanes
+    }
+}
+
+/// A route through the road network, given by the segments that are driven through in order.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Route {
+    steps: Vec<RouteStep>,
+    length: f32,
+}
+
+impl Route {
+    pub fn new(steps: Vec<RouteStep>, length: f32) -> Self {
+        Self { steps, length }
+    }
+
+    pub fn steps(&self) -> &[RouteStep] {
+        &self.steps
+    }
+
+    pub fn segments(&self) -> Vec<SegmentId> {
+        self.steps.iter().map(|s| s.segment).collect()
+    }
+
+    /// The total length in meters of the segments of the route.
+    pub fn length(&self) -> f32 {
+        self.length
+    }
+}
@@ -1,8 +1,9 @@
 use super::NodeType;
 use super::Side;
+use super::SuperNodeType;
 use glam::Vec3;
 use serde::{Deserialize, Serialize};
-use utils::id::NodeId;
+use utils::id::{NodeId, SuperNodeId};
 use utils::math::{DirXZ, Loc};
 
 /// Represents a continuous range of lane indexes. As an example, SnapRange might contain 2,3,4
@@ -24,6 +25,20 @@ pub struct SnapConfig {
     side: Side,
 }
 
+/// Represents a legal way of snapping a bidirectional road to a super node. The super node is
+/// located at the center between its two nodes, and its direction is the driving direction of
+/// the forward node.
+#[derive(Debug, Clone)]
+pub struct SuperSnapConfig {
+    super_node_id: SuperNodeId,
+    super_type: SuperNodeType,
+    loc: Loc,
+    /// The snap config of the node whose driving direction is the direction of the super node.
+    forward: SnapConfig,
+    /// The snap config of the node whose driving direction is opposite of the super node.
+    backward: SnapConfig,
+}
+
 // #################################################################################################
 // Implementation of SnapRange
 // #################################################################################################
@@ -156,3 +171,68 @@ impl SnapConfig {
         self.side == Side::In
     }
 }
+
+// #################################################################################################
+// Implementation of SuperSnapConfig
+// #################################################################################################
+impl SuperSnapConfig {
+    /// Should only be called by world.
+    pub fn new(
+        super_node_id: SuperNodeId,
+        super_type: SuperNodeType,
+        loc: Loc,
+        forward: SnapConfig,
+        backward: SnapConfig,
+    ) -> Self {
+        Self {
+            super_node_id,
+            super_type,
+            loc,
+            forward,
+            backward,
+        }
+    }
+
+    pub fn id(&self) -> SuperNodeId {
+        self.super_node_id
+    }
+
+    pub fn super_type(&self) -> SuperNodeType {
+        self.super_type
+    }
+
+    pub fn pos(&self) -> Vec3 {
+        self.loc.pos
+    }
+
+    pub fn dir(&self) -> DirXZ {
+        self.loc.dir
+    }
+
+    pub fn forward(&self) -> &SnapConfig {
+        &self.forward
+    }
+
+    pub fn backward(&self) -> &SnapConfig {
+        &self.backward
+    }
+
+    /// The side of the super node that a new road would be attached to, as seen from the forward
+    /// node.
+    pub fn side(&self) -> Side {
+        self.forward.side
+    }
+
+    /// Returns a {`SnapConfig`} located at the center of the super node, which behaves like the
+    /// forward snap config. This lets tools that only understand regular nodes, such as the curve
+    /// tools, snap to super nodes.
+    pub fn center_snap(&self) -> SnapConfig {
+        SnapConfig::new(
+            self.forward.node_id,
+            self.super_type.node_type(),
+            self.loc,
+            self.forward.snap_range.clone(),
+            self.forward.side,
+        )
+    }
+}
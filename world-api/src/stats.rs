@@ -0,0 +1,68 @@
+use super::{LaneWidth, NodeMode};
+
+use curves::CurveKind;
+
+use std::fmt;
+
+/// Summarizes the size and shape of the road network.
+#[derive(Debug, Clone, Default)]
+pub struct NetworkStats {
+    /// The total length in kilometres of all lanes of each lane width. Lane widths that no lane
+    /// has are left out. Transition segments count half of their length towards the type at each
+    /// end.
+    pub lane_km: Vec<(LaneWidth, f32)>,
+    /// The number of segments of each kind of curve. Kinds that no segment has are left out.
+    pub segments_by_curve: Vec<(CurveKind, usize)>,
+    /// The number of nodes in each mode. Modes that no node has are left out.
+    pub nodes_by_mode: Vec<(NodeMode, usize)>,
+    /// The smallest radius in meters of any segment as seen from above, or {`None`} if every
+    /// segment is straight.
+    pub min_curve_radius: Option<f32>,
+    /// The number of nodes where traffic enters the network, because nothing leads into them.
+    pub starting_dead_ends: usize,
+    /// The number of nodes where traffic leaves the network, because nothing leads out of them.
+    pub ending_dead_ends: usize,
+    /// The number of groups of nodes that are connected by segments, regardless of direction.
+    pub connected_components: usize,
+}
+
+impl NetworkStats {
+    pub fn total_lane_km(&self) -> f32 {
+        self.lane_km.iter().map(|(_, km)| km).sum()
+    }
+
+    pub fn no_segments(&self) -> usize {
+        self.segments_by_curve.iter().map(|(_, count)| count).sum()
+    }
+
+    pub fn no_nodes(&self) -> usize {
+        self.nodes_by_mode.iter().map(|(_, count)| count).sum()
+    }
+}
+
+impl fmt::Display for NetworkStats {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "Lane kilometres: {:.2}", self.total_lane_km())?;
+        for (lane_width, km) in self.lane_km.iter() {
+            writeln!(f, "  {:?}: {:.2}", lane_width, km)?;
+        }
+        writeln!(f, "Segments: {}", self.no_segments())?;
+        for (kind, count) in self.segments_by_curve.iter() {
+            writeln!(f, "  {:?}: {}", kind, count)?;
+        }
+        writeln!(f, "Nodes: {}", self.no_nodes())?;
+        for (mode, count) in self.nodes_by_mode.iter() {
+            writeln!(f, "  {:?}: {}", mode, count)?;
+        }
+        match self.min_curve_radius {
+            Some(radius) => writeln!(f, "Minimum curve radius: {:.1} m", radius)?,
+            None => writeln!(f, "Minimum curve radius: none")?,
+        }
+        writeln!(
+            f,
+            "Dead ends: {} starting, {} ending",
+            self.starting_dead_ends, self.ending_dead_ends
+        )?;
+        write!(f, "Connected components: {}", self.connected_components)
+    }
+}
@@ -0,0 +1,37 @@
+use glam::Vec3;
+
+/// The mean radius of the earth in meters.
+const EARTH_RADIUS: f64 = 6_371_000.0;
+
+/// Places the origin of the world on the earth, such that positions can be given as longitude and
+/// latitude. The world is treated as flat, which is accurate for areas up to a few tens of
+/// kilometres across.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct GeoOrigin {
+    /// The longitude of the origin of the world in degrees.
+    pub lon: f64,
+    /// The latitude of the origin of the world in degrees.
+    pub lat: f64,
+}
+
+impl GeoOrigin {
+    pub fn new(lon: f64, lat: f64) -> Self {
+        Self { lon, lat }
+    }
+
+    /// Returns the longitude and latitude in degrees of the given position in the world.
+    pub fn to_lon_lat(&self, pos: Vec3) -> [f64; 2] {
+        let [east, north] = crate::plan_pos(pos);
+        let lat = self.lat + (north / EARTH_RADIUS).to_degrees();
+        let lon = self.lon + (east / (EARTH_RADIUS * self.lat.to_radians().cos())).to_degrees();
+        [lon, lat]
+    }
+
+    /// Returns the position on the ground in the world of the given longitude and latitude in
+    /// degrees.
+    pub fn from_lon_lat(&self, [lon, lat]: [f64; 2]) -> Vec3 {
+        let north = (lat - self.lat).to_radians() * EARTH_RADIUS;
+        let east = (lon - self.lon).to_radians() * EARTH_RADIUS * self.lat.to_radians().cos();
+        Vec3::new(east as f32, 0.0, -north as f32)
+    }
+}
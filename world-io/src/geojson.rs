@@ -0,0 +1,123 @@
+//! Writes the road network as a GeoJSON feature collection, such that it can be overlaid on maps
+//! in GIS tools such as QGIS. Every segment is written as a LineString, or as a MultiLineString of
+//! its lanes, and every node is written as a Point. The height of every position is written as its
+//! altitude.
+use super::{lane_paths, plan_pos, ExportResult, GeoOrigin};
+
+use world_api::{NodeView, RoadQuery, SegmentView};
+
+use curves::CurveShared;
+
+use glam::Vec3;
+use serde_json::{json, Value};
+
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::Path;
+
+/// Configures how the road network is written.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct GeoJsonOptions {
+    /// If set, positions are written as longitude and latitude, as the GeoJSON standard requires.
+    /// Otherwise they are written in meters east and north of the origin of the world, and the
+    /// coordinate reference system has to be assigned by whoever reads the file.
+    pub origin: Option<GeoOrigin>,
+    /// If true, each segment is written as the center lines of its lanes instead of its spine.
+    pub lanes: bool,
+}
+
+/// Writes the road network of the world to the file at the given path, replacing the file if it
+/// already exists.
+pub fn export_geojson<W: RoadQuery>(
+    world: &W,
+    path: &Path,
+    options: &GeoJsonOptions,
+) -> ExportResult {
+    let mut writer = BufWriter::new(File::create(path)?);
+    write_geojson(world, &mut writer, options)?;
+    writer.flush()?;
+    Ok(())
+}
+
+/// Writes the road network of the world as a GeoJSON feature collection.
+pub fn write_geojson<W: RoadQuery>(
+    world: &W,
+    writer: impl Write,
+    options: &GeoJsonOptions,
+) -> ExportResult {
+    let mut features: Vec<Value> = world
+        .segments()
+        .map(|segment| segment_feature(&segment, options))
+        .collect();
+    features.extend(world.nodes().map(|node| node_feature(&node, options)));
+    let collection = json!({
+        "type": "FeatureCollection",
+        "features": features,
+    });
+    serde_json::to_writer_pretty(writer, &collection)?;
+    Ok(())
+}
+
+fn position(pos: Vec3, options: &GeoJsonOptions) -> Value {
+    let [x, y] = match options.origin {
+        Some(origin) => origin.to_lon_lat(pos),
+        None => plan_pos(pos),
+    };
+    json!([x, y, pos.y as f64])
+}
+
+fn line(points: impl Iterator<Item = Vec3>, options: &GeoJsonOptions) -> Value {
+    Value::Array(points.map(|pos| position(pos, options)).collect())
+}
+
+fn segment_feature(segment: &SegmentView, options: &GeoJsonOptions) -> Value {
+    let geometry = if options.lanes {
+        let lines: Vec<Value> = lane_paths(segment)
+            .into_iter()
+            .map(|path| line(path.into_iter(), options))
+            .collect();
+        json!({ "type": "MultiLineString", "coordinates": lines })
+    } else {
+        let spine = segment.curve().get_spine();
+        json!({
+            "type": "LineString",
+            "coordinates": line(spine.iter().map(|loc| loc.pos), options),
+        })
+    };
+    let segment_type = segment.segment_type();
+    let (from_type, to_type) = (segment_type.from_type(), segment_type.to_type());
+    json!({
+        "type": "Feature",
+        "geometry": geometry,
+        "properties": {
+            "kind": "segment",
+            "id": segment.id().to_string(),
+            "from_node": segment.from_node().to_string(),
+            "to_node": segment.to_node().to_string(),
+            "lanes_from": from_type.no_lanes(),
+            "lanes_to": to_type.no_lanes(),
+            "lane_width_from": from_type.lane_width_f32(),
+            "lane_width_to": to_type.lane_width_f32(),
+            "length": segment.length(),
+            "bridge": segment.is_bridge(),
+        },
+    })
+}
+
+fn node_feature(node: &NodeView, options: &GeoJsonOptions) -> Value {
+    let node_type = node.node_type();
+    json!({
+        "type": "Feature",
+        "geometry": {
+            "type": "Point",
+            "coordinates": position(node.loc().pos, options),
+        },
+        "properties": {
+            "kind": "node",
+            "id": node.id().to_string(),
+            "mode": format!("{:?}", node.mode()),
+            "lanes": node_type.no_lanes(),
+            "lane_width": node_type.lane_width_f32(),
+        },
+    })
+}
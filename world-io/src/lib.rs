@@ -0,0 +1,79 @@
+//! This crate reads and writes the road network in the formats of other tools, such that designs
+//! can be shared with people who do not run the app, and real road networks can be used as a
+//! starting point. It only goes through the traits of `world-api`, so it works with any world that
+//! implements them.
+//!
+//! The world is laid out with x pointing east, z pointing south and y pointing up. Every format
+//! that is written in plan coordinates uses x as east and -z as north.
+mod geo;
+mod geojson;
+mod opendrive;
+mod osm;
+mod sumo;
+#[cfg(test)]
+mod test_utils;
+
+pub use geo::GeoOrigin;
+pub use geojson::{export_geojson, write_geojson, GeoJsonOptions};
+pub use opendrive::{export_opendrive, write_opendrive, OpenDriveOptions};
+pub use osm::{import_osm, read_osm, OsmOptions, OsmReport};
+pub use sumo::{
+    export_sumo, write_sumo_connections, write_sumo_edges, write_sumo_nodes, SumoOptions,
+};
+
+use world_api::SegmentView;
+
+use curves::CurveShared;
+
+use glam::Vec3;
+use thiserror::Error;
+
+#[derive(Error, Debug)]
+pub enum ExportError {
+    /// The exported file could not be written.
+    #[error("Could not write the exported file")]
+    Io(#[from] std::io::Error),
+
+    /// The road network could not be encoded as JSON.
+    #[error("The JSON encoding failed: {0}")]
+    Json(#[from] serde_json::Error),
+}
+
+pub type ExportResult = Result<(), ExportError>;
+
+#[derive(Error, Debug)]
+pub enum ImportError {
+    /// The imported file could not be read.
+    #[error("Could not read the imported file")]
+    Io(#[from] std::io::Error),
+
+    /// The imported file is not valid XML.
+    #[error("The XML decoding failed: {0}")]
+    Xml(#[from] roxmltree::Error),
+}
+
+/// Returns the position in plan coordinates, east and north in meters.
+pub fn plan_pos(pos: Vec3) -> [f64; 2] {
+    [pos.x as f64, -pos.z as f64]
+}
+
+/// Returns the paths along the center of each lane of the segment, ordered from left to right.
+/// For transition segments there is always one path for each lane of whichever end has the most
+/// lanes, where lanes that are added or dropped narrow down to nothing at the right edge of the
+/// road.
+pub fn lane_paths(segment: &SegmentView) -> Vec<Vec<Vec3>> {
+    let spine = segment.curve().get_spine();
+    let segment_type = segment.segment_type();
+    let mut paths = vec![Vec::with_capacity(spine.len()); segment_type.max_no_lanes() as usize];
+    let last_index = (spine.len() - 1).max(1) as f32;
+    for (i, loc) in spine.iter().enumerate() {
+        let lane_widths = segment_type.lane_widths_at(i as f32 / last_index);
+        let right = Vec3::from(loc.dir.right_hand());
+        let mut offset = -lane_widths.iter().sum::<f32>() / 2.0;
+        for (path, width) in paths.iter_mut().zip(lane_widths) {
+            path.push(loc.pos + right * (offset + width / 2.0));
+            offset += width;
+        }
+    }
+    paths
+}
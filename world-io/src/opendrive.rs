@@ -0,0 +1,682 @@
+//! Writes the road network as an OpenDRIVE 1.7 file, such that it can be driven in simulators.
+//! Every segment becomes a road whose reference line is the spine of the segment, with all of its
+//! lanes on the right hand side in the driving direction. Straight curves are written as lines,
+//! circular curves as arcs and every other bezier curve as parametric cubic polynomials.
+//!
+//! A node that connects exactly one incoming and one outgoing segment links the two roads
+//! directly. A node where roads split or merge becomes a direct junction, which connects the roads
+//! without any connecting roads in between. In both cases the lanes are linked where they occupy
+//! the same lanes of the node.
+use super::{plan_pos, ExportResult, GeoOrigin};
+
+use world_api::{LaneMapEntry, RoadQuery, SegmentView, Side};
+
+use curves::{CurveKind, CurveShared, CurveSum};
+use utils::id::{NodeId, SegmentId};
+
+use glam::DVec2;
+
+use std::collections::BTreeMap;
+use std::f64::consts::PI;
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::Path;
+
+/// The number of points sampled along a parametric cubic polynomial when measuring its length.
+const POLY_SAMPLES: usize = 64;
+
+/// The width of the lines that mark the lanes.
+const ROAD_MARK_WIDTH: f64 = 0.2;
+
+/// Configures how the road network is written.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct OpenDriveOptions {
+    /// If set, the file is georeferenced with a transverse mercator projection centered on the
+    /// origin of the world.
+    pub origin: Option<GeoOrigin>,
+}
+
+/// The shape of a single geometry of the plan view of a road.
+enum Shape {
+    Line,
+    /// The curvature of the arc, which is positive for arcs that turn to the left.
+    Arc(f64),
+    /// The coefficients of the u and v polynomials in the local frame of the geometry, over the
+    /// normalized parameter range.
+    ParamPoly3([f64; 4], [f64; 4]),
+}
+
+struct Geometry {
+    start: DVec2,
+    hdg: f64,
+    length: f64,
+    shape: Shape,
+}
+
+/// How the lanes of an incoming segment continue into an outgoing segment at a node, given as the
+/// pairs of lane ids that are linked.
+struct Connection {
+    incoming: SegmentId,
+    outgoing: SegmentId,
+    lanes: Vec<(i32, i32)>,
+}
+
+/// How the roads are linked at a node.
+enum NodeLink {
+    /// Nothing continues through the node.
+    End,
+    /// The node connects exactly one incoming and one outgoing road.
+    Road(Connection),
+    /// Roads split or merge at the node.
+    Junction(Vec<Connection>),
+}
+
+/// Writes the road network of the world to the file at the given path, replacing the file if it
+/// already exists.
+pub fn export_opendrive<W: RoadQuery>(
+    world: &W,
+    path: &Path,
+    options: &OpenDriveOptions,
+) -> ExportResult {
+    let mut writer = BufWriter::new(File::create(path)?);
+    write_opendrive(world, &mut writer, options)?;
+    writer.flush()?;
+    Ok(())
+}
+
+/// Writes the road network of the world as an OpenDRIVE file.
+pub fn write_opendrive<W: RoadQuery>(
+    world: &W,
+    mut writer: impl Write,
+    options: &OpenDriveOptions,
+) -> ExportResult {
+    let links: BTreeMap<NodeId, NodeLink> = world
+        .nodes()
+        .map(|node| (node.id(), node_link(world, node.id())))
+        .collect();
+
+    writeln!(writer, r#"<?xml version="1.0" encoding="UTF-8"?>"#)?;
+    writeln!(writer, "<OpenDRIVE>")?;
+    write!(
+        writer,
+        r#"  <header revMajor="1" revMinor="7" name="hw-architect" version="1.0""#
+    )?;
+    match options.origin {
+        Some(origin) => {
+            writeln!(writer, ">")?;
+            writeln!(
+                writer,
+                "    <geoReference><![CDATA[+proj=tmerc +lat_0={} +lon_0={} +k=1 +x_0=0 +y_0=0 \
+                 +datum=WGS84 +units=m +no_defs]]></geoReference>",
+                origin.lat, origin.lon
+            )?;
+            writeln!(writer, "  </header>")?;
+        }
+        None => writeln!(writer, "/>")?,
+    }
+    for segment in world.segments() {
+        write_road(&mut writer, &segment, &links)?;
+    }
+    for (node, link) in links.iter() {
+        if let NodeLink::Junction(connections) = link {
+            write_junction(&mut writer, *node, connections)?;
+        }
+    }
+    writeln!(writer, "</OpenDRIVE>")?;
+    Ok(())
+}
+
+/// Returns the id of the lane with the given index from the left. Every lane is on the right hand
+/// side of the reference line.
+fn lane_id(index: usize) -> i32 {
+    -(index as i32 + 1)
+}
+
+fn node_link<W: RoadQuery>(world: &W, node: NodeId) -> NodeLink {
+    let lane_map = world.get_node_lane_map(node).unwrap_or_default();
+    let (incoming, outgoing): (Vec<&LaneMapEntry>, Vec<&LaneMapEntry>) =
+        lane_map.iter().partition(|entry| entry.side() == Side::In);
+    let mut connections = vec![];
+    for a in incoming.iter() {
+        for b in outgoing.iter() {
+            let lanes: Vec<(i32, i32)> = a
+                .snap_range()
+                .iter()
+                .enumerate()
+                .filter_map(|(i, lane)| {
+                    let j = b.snap_range().iter().position(|other| other == lane)?;
+                    Some((lane_id(i), lane_id(j)))
+                })
+                .collect();
+            if !lanes.is_empty() {
+                connections.push(Connection {
+                    incoming: a.segment_id(),
+                    outgoing: b.segment_id(),
+                    lanes,
+                });
+            }
+        }
+    }
+    match (incoming.len(), outgoing.len(), connections.pop()) {
+        (_, _, None) => NodeLink::End,
+        (1, 1, Some(connection)) => NodeLink::Road(connection),
+        (_, _, Some(connection)) => {
+            connections.push(connection);
+            NodeLink::Junction(connections)
+        }
+    }
+}
+
+fn write_road(
+    writer: &mut impl Write,
+    segment: &SegmentView,
+    links: &BTreeMap<NodeId, NodeLink>,
+) -> ExportResult {
+    let geometries = plan_view(segment.curve());
+    let length: f64 = geometries.iter().map(|g| g.length).sum();
+    let id = segment.id();
+
+    writeln!(
+        writer,
+        r#"  <road name="" length="{}" id="{}" junction="-1">"#,
+        length, id
+    )?;
+
+    // The lanes of a road can only be linked directly when the road itself is linked to another
+    // road. Otherwise they are linked by the junction.
+    let predecessor = links.get(&segment.from_node());
+    let successor = links.get(&segment.to_node());
+    writeln!(writer, "    <link>")?;
+    match predecessor {
+        Some(NodeLink::Road(c)) => writeln!(
+            writer,
+            r#"      <predecessor elementType="road" elementId="{}" contactPoint="end"/>"#,
+            c.incoming
+        )?,
+        Some(NodeLink::Junction(_)) => writeln!(
+            writer,
+            r#"      <predecessor elementType="junction" elementId="{}"/>"#,
+            segment.from_node()
+        )?,
+        _ => {}
+    }
+    match successor {
+        Some(NodeLink::Road(c)) => writeln!(
+            writer,
+            r#"      <successor elementType="road" elementId="{}" contactPoint="start"/>"#,
+            c.outgoing
+        )?,
+        Some(NodeLink::Junction(_)) => writeln!(
+            writer,
+            r#"      <successor elementType="junction" elementId="{}"/>"#,
+            segment.to_node()
+        )?,
+        _ => {}
+    }
+    writeln!(writer, "    </link>")?;
+    writeln!(writer, r#"    <type s="0" type="motorway"/>"#)?;
+
+    writeln!(writer, "    <planView>")?;
+    let mut s = 0.0;
+    for g in geometries.iter() {
+        writeln!(
+            writer,
+            r#"      <geometry s="{}" x="{}" y="{}" hdg="{}" length="{}">"#,
+            s, g.start.x, g.start.y, g.hdg, g.length
+        )?;
+        match &g.shape {
+            Shape::Line => writeln!(writer, "        <line/>")?,
+            Shape::Arc(curvature) => {
+                writeln!(writer, r#"        <arc curvature="{}"/>"#, curvature)?
+            }
+            Shape::ParamPoly3(u, v) => writeln!(
+                writer,
+                r#"        <paramPoly3 aU="{}" bU="{}" cU="{}" dU="{}" aV="{}" bV="{}" cV="{}" dV="{}" pRange="normalized"/>"#,
+                u[0], u[1], u[2], u[3], v[0], v[1], v[2], v[3]
+            )?,
+        }
+        writeln!(writer, "      </geometry>")?;
+        s += g.length;
+    }
+    writeln!(writer, "    </planView>")?;
+
+    // The profile is measured along the spine, which is stretched to the length of the plan view.
+    let profile = segment.curve().get_profile();
+    let profile = if profile.length() > 0.0 {
+        profile.scaled(length as f32 / profile.length())
+    } else {
+        profile.clone()
+    };
+    writeln!(writer, "    <elevationProfile>")?;
+    for (s, [height, grade, rate]) in profile.pieces() {
+        writeln!(
+            writer,
+            r#"      <elevation s="{}" a="{}" b="{}" c="{}" d="0"/>"#,
+            s, height, grade, rate
+        )?;
+    }
+    writeln!(writer, "    </elevationProfile>")?;
+
+    let segment_type = segment.segment_type();
+    let first_widths = segment_type.lane_widths_at(0.0);
+    let last_widths = segment_type.lane_widths_at(1.0);
+    let slope = |first: f64, last: f64| {
+        if length > 0.0 {
+            (last - first) / length
+        } else {
+            0.0
+        }
+    };
+    let first_width: f64 = first_widths.iter().map(|w| *w as f64).sum();
+    let last_width: f64 = last_widths.iter().map(|w| *w as f64).sum();
+
+    writeln!(writer, "    <lanes>")?;
+    // Moves the center lane to the left edge of the road, such that the road is centered on the
+    // reference line.
+    writeln!(
+        writer,
+        r#"      <laneOffset s="0" a="{}" b="{}" c="0" d="0"/>"#,
+        first_width / 2.0,
+        slope(first_width / 2.0, last_width / 2.0)
+    )?;
+    writeln!(writer, r#"      <laneSection s="0">"#)?;
+    writeln!(writer, "        <center>")?;
+    writeln!(
+        writer,
+        r#"          <lane id="0" type="none" level="false">"#
+    )?;
+    write_road_mark(writer, "solid")?;
+    writeln!(writer, "          </lane>")?;
+    writeln!(writer, "        </center>")?;
+    writeln!(writer, "        <right>")?;
+    let no_lanes = first_widths.len();
+    for (i, (first, last)) in first_widths.iter().zip(last_widths.iter()).enumerate() {
+        let lane = lane_id(i);
+        writeln!(
+            writer,
+            r#"          <lane id="{}" type="driving" level="false">"#,
+            lane
+        )?;
+        let lane_predecessor = match predecessor {
+            Some(NodeLink::Road(c)) => c.lanes.iter().find(|(_, to)| *to == lane),
+            _ => None,
+        };
+        let lane_successor = match successor {
+            Some(NodeLink::Road(c)) => c.lanes.iter().find(|(from, _)| *from == lane),
+            _ => None,
+        };
+        if lane_predecessor.is_some() || lane_successor.is_some() {
+            writeln!(writer, "            <link>")?;
+            if let Some((from, _)) = lane_predecessor {
+                writeln!(writer, r#"              <predecessor id="{}"/>"#, from)?;
+            }
+            if let Some((_, to)) = lane_successor {
+                writeln!(writer, r#"              <successor id="{}"/>"#, to)?;
+            }
+            writeln!(writer, "            </link>")?;
+        }
+        writeln!(
+            writer,
+            r#"            <width sOffset="0" a="{}" b="{}" c="0" d="0"/>"#,
+            first,
+            slope(*first as f64, *last as f64)
+        )?;
+        write_road_mark(writer, if i + 1 == no_lanes { "solid" } else { "broken" })?;
+        writeln!(writer, "          </lane>")?;
+    }
+    writeln!(writer, "        </right>")?;
+    writeln!(writer, "      </laneSection>")?;
+    writeln!(writer, "    </lanes>")?;
+
+    if segment.is_bridge() {
+        writeln!(writer, "    <objects>")?;
+        writeln!(
+            writer,
+            r#"      <bridge s="0" length="{}" id="{}" name="" type="concrete"/>"#,
+            length, id
+        )?;
+        writeln!(writer, "    </objects>")?;
+    }
+    writeln!(writer, "  </road>")?;
+    Ok(())
+}
+
+/// Writes the marking at the outer edge of a lane.
+fn write_road_mark(writer: &mut impl Write, mark_type: &str) -> ExportResult {
+    writeln!(
+        writer,
+        r#"            <roadMark sOffset="0" type="{}" weight="standard" color="standard" width="{}"/>"#,
+        mark_type, ROAD_MARK_WIDTH
+    )?;
+    Ok(())
+}
+
+fn write_junction(
+    writer: &mut impl Write,
+    node: NodeId,
+    connections: &[Connection],
+) -> ExportResult {
+    writeln!(
+        writer,
+        r#"  <junction id="{}" name="" type="direct">"#,
+        node
+    )?;
+    for (i, c) in connections.iter().enumerate() {
+        writeln!(
+            writer,
+            r#"    <connection id="{}" incomingRoad="{}" linkedRoad="{}" contactPoint="start">"#,
+            i, c.incoming, c.outgoing
+        )?;
+        for (from, to) in c.lanes.iter() {
+            writeln!(writer, r#"      <laneLink from="{}" to="{}"/>"#, from, to)?;
+        }
+        writeln!(writer, "    </connection>")?;
+    }
+    writeln!(writer, "  </junction>")?;
+    Ok(())
+}
+
+/// Returns the geometries that make up the plan view of the curve, one for each of its pieces.
+fn plan_view(curve: &CurveSum) -> Vec<Geometry> {
+    curve
+        .pieces()
+        .iter()
+        .map(|piece| {
+            let points: Vec<DVec2> = piece.iter().map(|p| DVec2::from(plan_pos(*p))).collect();
+            match curve.kind() {
+                CurveKind::Straight => line(&points),
+                CurveKind::Circular => arc(&points),
+                _ => param_poly3(&points),
+            }
+        })
+        .collect()
+}
+
+/// Returns the direction of the curve given by the guide points at its first point.
+fn first_dir(points: &[DVec2]) -> DVec2 {
+    points[1..]
+        .iter()
+        .map(|p| *p - points[0])
+        .find(|d| d.length_squared() > 0.0)
+        .unwrap_or(DVec2::X)
+}
+
+/// Returns the direction of the curve given by the guide points at its last point.
+fn last_dir(points: &[DVec2]) -> DVec2 {
+    let last = points[points.len() - 1];
+    points[..points.len() - 1]
+        .iter()
+        .rev()
+        .map(|p| last - *p)
+        .find(|d| d.length_squared() > 0.0)
+        .unwrap_or(DVec2::X)
+}
+
+fn heading(dir: DVec2) -> f64 {
+    dir.y.atan2(dir.x)
+}
+
+fn line(points: &[DVec2]) -> Geometry {
+    let (first, last) = (points[0], points[points.len() - 1]);
+    Geometry {
+        start: first,
+        hdg: heading(last - first),
+        length: (last - first).length(),
+        shape: Shape::Line,
+    }
+}
+
+/// The guide points must describe an arc of a circle.
+fn arc(points: &[DVec2]) -> Geometry {
+    let (first, last) = (points[0], points[points.len() - 1]);
+    let hdg = heading(first_dir(points));
+    let mut turn = heading(last_dir(points)) - hdg;
+    if turn > PI {
+        turn -= 2.0 * PI;
+    } else if turn <= -PI {
+        turn += 2.0 * PI;
+    }
+    let chord = (last - first).length();
+    if turn.abs() < 1e-6 || chord == 0.0 {
+        return line(points);
+    }
+    let curvature = 2.0 * (turn / 2.0).sin() / chord;
+    Geometry {
+        start: first,
+        hdg,
+        length: turn / curvature,
+        shape: Shape::Arc(curvature),
+    }
+}
+
+/// Bezier curves of a degree higher than three are reduced to the cubic bezier curve with the same
+/// directions and speeds at its ends, which is exact for bezier curves of a lower degree.
+fn param_poly3(points: &[DVec2]) -> Geometry {
+    let n = (points.len() - 1) as f64;
+    let (p0, p3) = (points[0], points[points.len() - 1]);
+    let p1 = p0 + (points[1] - p0) * n / 3.0;
+    let p2 = p3 - (p3 - points[points.len() - 2]) * n / 3.0;
+
+    let b = 3.0 * (p1 - p0);
+    let c = 3.0 * (p0 - 2.0 * p1 + p2);
+    let d = p3 - 3.0 * p2 + 3.0 * p1 - p0;
+    let hdg = heading(first_dir(points));
+    let (sin, cos) = hdg.sin_cos();
+    let local = |v: DVec2| DVec2::new(v.x * cos + v.y * sin, -v.x * sin + v.y * cos);
+    let (b, c, d) = (local(b), local(c), local(d));
+
+    let at = |p: f64| b * p + c * p * p + d * p * p * p;
+    let length = (1..=POLY_SAMPLES)
+        .map(|i| {
+            let (p, q) = ((i - 1) as f64, i as f64);
+            (at(q / POLY_SAMPLES as f64) - at(p / POLY_SAMPLES as f64)).length()
+        })
+        .sum();
+    Geometry {
+        start: p0,
+        hdg,
+        length,
+        shape: Shape::ParamPoly3([0.0, b.x, c.x, d.x], [0.0, b.y, c.y, d.y]),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{write_opendrive, OpenDriveOptions};
+    use crate::test_utils::{add_branch, add_road, arc, lanes, straight};
+
+    use world::World;
+    use world_api::RoadQuery;
+
+    use utils::id::{NodeId, SegmentId};
+    use utils::math::{DirXZ, Loc};
+
+    use glam::Vec3;
+
+    use std::f64::consts::PI;
+
+    /// The incoming road, the linked road and the lane links of a connection of a junction.
+    type JunctionConnection = (String, String, Vec<(i32, i32)>);
+
+    fn write(world: &World) -> String {
+        let mut bytes = vec![];
+        write_opendrive(world, &mut bytes, &OpenDriveOptions::default()).unwrap();
+        String::from_utf8(bytes).unwrap()
+    }
+
+    fn road<'a>(document: &'a roxmltree::Document, id: SegmentId) -> roxmltree::Node<'a, 'a> {
+        document
+            .descendants()
+            .find(|n| n.has_tag_name("road") && n.attribute("id") == Some(&id.to_string()))
+            .unwrap()
+    }
+
+    /// Returns the element type, id and contact point of the predecessor or successor of the
+    /// road.
+    fn road_link(road: roxmltree::Node, name: &str) -> Option<(String, String, Option<String>)> {
+        let link = road.children().find(|n| n.has_tag_name("link"))?;
+        let element = link.children().find(|n| n.has_tag_name(name))?;
+        Some((
+            element.attribute("elementType")?.to_string(),
+            element.attribute("elementId")?.to_string(),
+            element.attribute("contactPoint").map(str::to_string),
+        ))
+    }
+
+    /// Returns the lane ids of the road, each with the id of its predecessor and successor lane.
+    fn lane_links(road: roxmltree::Node) -> Vec<(i32, Option<i32>, Option<i32>)> {
+        road.descendants()
+            .filter(|n| n.has_tag_name("lane") && n.attribute("id") != Some("0"))
+            .map(|lane| {
+                let linked = |name| {
+                    lane.descendants()
+                        .find(|n| n.has_tag_name(name))
+                        .and_then(|n| n.attribute("id")?.parse().ok())
+                };
+                let id = lane.attribute("id").unwrap().parse().unwrap();
+                (id, linked("predecessor"), linked("successor"))
+            })
+            .collect()
+    }
+
+    /// Returns the curvature and length of every arc in the plan view of the road.
+    fn arcs(road: roxmltree::Node) -> Vec<(f64, f64)> {
+        road.descendants()
+            .filter(|n| n.has_tag_name("geometry"))
+            .filter_map(|geometry| {
+                let arc = geometry.children().find(|n| n.has_tag_name("arc"))?;
+                let curvature = arc.attribute("curvature")?.parse().ok()?;
+                let length = geometry.attribute("length")?.parse().ok()?;
+                Some((curvature, length))
+            })
+            .collect()
+    }
+
+    #[test]
+    fn chain_links_roads_and_lanes() {
+        // A straight segment followed by a quarter circle of radius 50 m, turning either way.
+        for (turn, sign) in [(-50.0, 1.0), (50.0, -1.0)] {
+            let mut world = World::new();
+            let (a, b) = (Vec3::ZERO, Vec3::new(100.0, 0.0, 0.0));
+            let mut curves = vec![straight(a, b)];
+            curves.extend(arc(
+                Loc::new(b, DirXZ::from(Vec3::X)),
+                Vec3::new(150.0, 0.0, turn),
+            ));
+            let node_types = vec![lanes(2); curves.len() + 1];
+            let segments = add_road(&mut world, curves, &node_types);
+
+            let text = write(&world);
+            let document = roxmltree::Document::parse(&text).unwrap();
+            let (first, second) = (road(&document, segments[0]), road(&document, segments[1]));
+            assert!(document.descendants().all(|n| !n.has_tag_name("junction")));
+
+            assert_eq!(road_link(first, "predecessor"), None);
+            assert_eq!(
+                road_link(first, "successor"),
+                Some((
+                    "road".to_string(),
+                    segments[1].to_string(),
+                    Some("start".to_string())
+                ))
+            );
+            assert_eq!(
+                road_link(second, "predecessor"),
+                Some((
+                    "road".to_string(),
+                    segments[0].to_string(),
+                    Some("end".to_string())
+                ))
+            );
+            assert_eq!(
+                lane_links(first),
+                vec![(-1, None, Some(-1)), (-2, None, Some(-2))]
+            );
+            let predecessors: Vec<(i32, Option<i32>)> = lane_links(second)
+                .into_iter()
+                .map(|(id, predecessor, _)| (id, predecessor))
+                .collect();
+            assert_eq!(predecessors, vec![(-1, Some(-1)), (-2, Some(-2))]);
+
+            // A left turn seen from above has a positive curvature.
+            let arcs: Vec<(f64, f64)> = segments[1..]
+                .iter()
+                .flat_map(|s| arcs(road(&document, *s)))
+                .collect();
+            assert!(!arcs.is_empty());
+            for (curvature, _) in arcs.iter() {
+                assert!((curvature - sign / 50.0).abs() < 1e-4, "{}", curvature);
+            }
+            let length: f64 = arcs.iter().map(|(_, length)| length).sum();
+            assert!((length - 25.0 * PI).abs() < 0.01, "{}", length);
+            let curve_length: f32 = segments[1..]
+                .iter()
+                .map(|s| world.get_segment(*s).unwrap().length())
+                .sum();
+            assert!((length - curve_length as f64).abs() < 0.5);
+        }
+    }
+
+    #[test]
+    fn split_becomes_junction() {
+        let mut world = World::new();
+        let end = Vec3::new(100.0, 0.0, 0.0);
+        let main = add_road(&mut world, vec![straight(Vec3::ZERO, end)], &[lanes(2); 2])[0];
+        let node: NodeId = world.get_segment(main).unwrap().to_node();
+        let left = add_branch(&mut world, end, 0);
+        let right = add_branch(&mut world, end, 1);
+
+        let text = write(&world);
+        let document = roxmltree::Document::parse(&text).unwrap();
+        let junction = ("junction".to_string(), node.to_string(), None);
+        assert_eq!(
+            road_link(road(&document, main), "successor"),
+            Some(junction.clone())
+        );
+        assert_eq!(
+            road_link(road(&document, left), "predecessor"),
+            Some(junction.clone())
+        );
+        assert_eq!(
+            road_link(road(&document, right), "predecessor"),
+            Some(junction)
+        );
+        // Lanes are only linked directly between roads, and here the junction links them.
+        assert!(lane_links(road(&document, main))
+            .iter()
+            .all(|(_, predecessor, successor)| predecessor.is_none() && successor.is_none()));
+
+        let junction = document
+            .descendants()
+            .find(|n| n.has_tag_name("junction"))
+            .unwrap();
+        assert_eq!(junction.attribute("id"), Some(node.to_string().as_str()));
+        assert_eq!(junction.attribute("type"), Some("direct"));
+        let mut connections: Vec<JunctionConnection> = junction
+            .children()
+            .filter(|n| n.has_tag_name("connection"))
+            .map(|c| {
+                let lane_links = c
+                    .children()
+                    .filter(|n| n.has_tag_name("laneLink"))
+                    .map(|l| {
+                        let id = |name| l.attribute(name).unwrap().parse().unwrap();
+                        (id("from"), id("to"))
+                    })
+                    .collect();
+                (
+                    c.attribute("incomingRoad").unwrap().to_string(),
+                    c.attribute("linkedRoad").unwrap().to_string(),
+                    lane_links,
+                )
+            })
+            .collect();
+        connections.sort();
+        let mut expected = vec![
+            (main.to_string(), left.to_string(), vec![(-1, -1)]),
+            (main.to_string(), right.to_string(), vec![(-2, -1)]),
+        ];
+        expected.sort();
+        assert_eq!(connections, expected);
+    }
+}
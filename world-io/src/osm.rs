@@ -0,0 +1,723 @@
+//! Reads the roads of an OpenStreetMap XML file. Every way that is tagged as a drivable highway is
+//! split where it meets other ways, and each piece is built as a one-way road, or as two one-way
+//! roads next to each other if the way can be driven in both directions. The polyline of each road
+//! is fitted with straight and circular curves that join tangentially, by rounding every corner of
+//! the polyline with the largest circle that fits.
+//!
+//! Roads are built one at a time through {`RoadManipulator::add_road`}, so the world checks them
+//! like any road built with the tools. Roads that meet at the same OSM node are snapped together
+//! where the world allows it, and roads that cannot be built are left out and reported. This
+//! includes roads whose polyline cannot be fitted with curves of at least the shortest length of a
+//! road, such as ways that turn back on themselves within a few meters.
+use super::{GeoOrigin, ImportError};
+
+use world_api::{
+    LNodeBuilderType, LRoadBuilder, LSegmentBuilder, LaneWidth, NodeType, RoadGenErr,
+    RoadManipulator, RoadQuery, Side, SnapConfig,
+};
+
+use curves::{
+    Circular, CompositeCurveSum, Curve, CurveShared, CurveSum, Straight, VerticalProfile,
+};
+use utils::consts::{BRIDGE_DECK_HEIGHT, MAX_NO_LANES, ROAD_MIN_LENGTH};
+use utils::id::NodeId;
+use utils::math::{DirXZ, Loc};
+
+use glam::{Vec3, Vec3Swizzles};
+
+use std::collections::BTreeMap;
+use std::path::Path;
+
+/// The highway types that are imported. Everything else, such as footways and service roads, is
+/// ignored.
+const DRIVABLE_HIGHWAYS: [&str; 13] = [
+    "motorway",
+    "motorway_link",
+    "trunk",
+    "trunk_link",
+    "primary",
+    "primary_link",
+    "secondary",
+    "secondary_link",
+    "tertiary",
+    "tertiary_link",
+    "unclassified",
+    "residential",
+    "living_street",
+];
+
+/// Corners that turn less than this, given as the cosine of the angle, are driven straight through.
+const COS_STRAIGHT: f32 = 0.9998;
+
+/// Roads are only snapped to nodes that are closer than this to where the road ends.
+const SNAP_DISTANCE: f32 = 0.5;
+
+/// Configures how an OpenStreetMap file is imported.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct OsmOptions {
+    /// The place on earth of the origin of the world. If not set, the center of the bounds of the
+    /// file is used, or the average position of its nodes if the file has no bounds.
+    pub origin: Option<GeoOrigin>,
+    /// The width of every lane of the imported roads.
+    pub lane_width: LaneWidth,
+}
+
+/// Describes the outcome of an import.
+#[derive(Debug, Clone)]
+pub struct OsmReport {
+    /// The place on earth of the origin of the world that the file was projected around. Exports
+    /// of the imported roads should use it to line up with the original map.
+    pub origin: GeoOrigin,
+    /// The number of one-way roads that were built.
+    pub roads: usize,
+    /// The ways of which some roads were not built, together with the reason for each road.
+    pub skipped: Vec<(i64, RoadGenErr)>,
+}
+
+/// A drivable way, with its nodes given in its forward direction.
+struct Way {
+    id: i64,
+    nodes: Vec<i64>,
+    forward_lanes: u8,
+    /// Zero if the way is one-way.
+    backward_lanes: u8,
+    height: f32,
+}
+
+/// Imports the drivable highways of the OpenStreetMap XML file at the given path into the world.
+pub fn import_osm<W: RoadManipulator + RoadQuery>(
+    world: &mut W,
+    path: &Path,
+    options: &OsmOptions,
+) -> Result<OsmReport, ImportError> {
+    let text = std::fs::read_to_string(path)?;
+    read_osm(world, &text, options)
+}
+
+/// Imports the drivable highways of the given OpenStreetMap XML document into the world.
+pub fn read_osm<W: RoadManipulator + RoadQuery>(
+    world: &mut W,
+    text: &str,
+    options: &OsmOptions,
+) -> Result<OsmReport, ImportError> {
+    let document = roxmltree::Document::parse(text)?;
+    let osm = document.root_element();
+
+    let mut bounds = None;
+    let mut lon_lats: BTreeMap<i64, [f64; 2]> = BTreeMap::new();
+    let mut ways = vec![];
+    for element in osm.children().filter(|n| n.is_element()) {
+        match element.tag_name().name() {
+            "bounds" => {
+                if let (Some(min_lat), Some(min_lon), Some(max_lat), Some(max_lon)) = (
+                    parse_degrees(element, "minlat", 90.0),
+                    parse_degrees(element, "minlon", 180.0),
+                    parse_degrees(element, "maxlat", 90.0),
+                    parse_degrees(element, "maxlon", 180.0),
+                ) {
+                    bounds = Some(GeoOrigin::new(
+                        (min_lon + max_lon) / 2.0,
+                        (min_lat + max_lat) / 2.0,
+                    ));
+                }
+            }
+            "node" => {
+                let id = element.attribute("id").and_then(|v| v.parse().ok());
+                let lat = parse_degrees(element, "lat", 90.0);
+                let lon = parse_degrees(element, "lon", 180.0);
+                if let (Some(id), Some(lat), Some(lon)) = (id, lat, lon) {
+                    lon_lats.insert(id, [lon, lat]);
+                }
+            }
+            "way" => {
+                if let Some(way) = parse_way(element) {
+                    ways.push(way);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    // Extracts of the map often cut ways at their edge, so nodes that are not in the file are
+    // left out of the ways.
+    ways.iter_mut()
+        .for_each(|way| way.nodes.retain(|id| lon_lats.contains_key(id)));
+    ways.retain(|way| way.nodes.len() >= 2);
+
+    let origin = options.origin.or(bounds).unwrap_or_else(|| {
+        let count = lon_lats.len().max(1) as f64;
+        let [lon, lat] = lon_lats.values().fold([0.0, 0.0], |[lon, lat], [x, y]| {
+            [lon + x / count, lat + y / count]
+        });
+        GeoOrigin::new(lon, lat)
+    });
+    let positions: BTreeMap<i64, Vec3> = lon_lats
+        .iter()
+        .map(|(id, lon_lat)| (*id, origin.from_lon_lat(*lon_lat)))
+        .collect();
+
+    // Ways are split at every node that is used more than once, such that roads only meet at
+    // their ends.
+    let mut uses: BTreeMap<i64, usize> = BTreeMap::new();
+    for way in ways.iter() {
+        way.nodes
+            .iter()
+            .for_each(|id| *uses.entry(*id).or_default() += 1);
+    }
+    let mut pieces = vec![];
+    for way in ways.iter() {
+        let mut start = 0;
+        for i in 1..way.nodes.len() {
+            if i < way.nodes.len() - 1 && uses[&way.nodes[i]] < 2 {
+                continue;
+            }
+            let nodes = &way.nodes[start..=i];
+            start = i;
+            // A road cannot start and end at the same node, so closed pieces such as roundabouts
+            // are split in two.
+            let halves = if nodes[0] == nodes[nodes.len() - 1] && nodes.len() > 3 {
+                let (first, last) = (&nodes[..=nodes.len() / 2], &nodes[nodes.len() / 2..]);
+                vec![first, last]
+            } else {
+                vec![nodes]
+            };
+            for nodes in halves {
+                let mut points: Vec<Vec3> = nodes.iter().map(|id| positions[id]).collect();
+                points.dedup_by(|a, b| a.distance(*b) < 0.01);
+                if points.len() >= 2 {
+                    let ends = [nodes[0], nodes[nodes.len() - 1]];
+                    pieces.push(Piece { way, ends, points });
+                }
+            }
+        }
+    }
+
+    // Where exactly two pieces meet, both get the direction halfway between their last pieces of
+    // polyline, such that the roads continue smoothly into each other.
+    let mut outwards: BTreeMap<i64, Vec<Vec3>> = BTreeMap::new();
+    for piece in pieces.iter() {
+        let [first, last] = piece.ends;
+        let points = &piece.points;
+        let last_index = points.len() - 1;
+        let outward = |from: usize, to: usize| Vec3::from(DirXZ::from(points[to] - points[from]));
+        outwards.entry(first).or_default().push(outward(0, 1));
+        outwards
+            .entry(last)
+            .or_default()
+            .push(outward(last_index, last_index - 1));
+    }
+    let tangents: BTreeMap<i64, Vec3> = outwards
+        .into_iter()
+        .filter_map(|(id, outwards)| match outwards[..] {
+            [a, b] => Some((id, (a - b).try_normalize()?)),
+            _ => None,
+        })
+        .collect();
+
+    let mut importer = Importer {
+        world,
+        ends: BTreeMap::new(),
+        report: OsmReport {
+            origin,
+            roads: 0,
+            skipped: vec![],
+        },
+    };
+    for piece in pieces.iter() {
+        let [first, last] = piece.ends;
+        let points = &piece.points;
+        let last_index = points.len() - 1;
+        let end_dir = |id: i64, outward: Vec3| -> DirXZ {
+            let outward = DirXZ::from(outward);
+            match tangents.get(&id) {
+                Some(tangent) if tangent.dot(*outward) >= 0.0 => (*tangent).into(),
+                Some(tangent) => (-*tangent).into(),
+                None => outward,
+            }
+        };
+        let first = Loc::new(points[0], end_dir(first, points[1] - points[0]));
+        let last = Loc::new(
+            points[last_index],
+            end_dir(last, points[last_index - 1] - points[last_index]).flip(true),
+        );
+        let way = piece.way;
+        let mut curves = fit_curves(first, &fillet_targets(points));
+        let fitted = join_curves(&mut curves, first, last)
+            && curves.iter().all(|c| c.get_length() >= ROAD_MIN_LENGTH);
+        if !fitted {
+            // Each direction of the way would have been built as a road of its own.
+            let no_roads = if way.backward_lanes == 0 { 1 } else { 2 };
+            for _ in 0..no_roads {
+                importer
+                    .report
+                    .skipped
+                    .push((way.id, RoadGenErr::CurveFitFailed));
+            }
+            continue;
+        }
+
+        let forward_type = NodeType::new(options.lane_width, way.forward_lanes);
+        if way.backward_lanes == 0 {
+            importer.add_road(way, piece.ends, curves, forward_type);
+            continue;
+        }
+        // The two directions are placed on either side of the center line of the way.
+        let backward_type = NodeType::new(options.lane_width, way.backward_lanes);
+        let forward_offset = forward_type.compute_width() / 2.0;
+        let backward_offset = backward_type.compute_width() / 2.0;
+        let forward_curves = curves.iter().map(|c| c.offset(forward_offset)).collect();
+        let backward_curves = curves
+            .iter()
+            .rev()
+            .map(|c| {
+                let mut curve = c.offset(-backward_offset);
+                curve.reverse();
+                curve
+            })
+            .collect();
+        importer.add_road(way, piece.ends, forward_curves, forward_type);
+        let [first, last] = piece.ends;
+        importer.add_road(way, [last, first], backward_curves, backward_type);
+    }
+
+    Ok(importer.report)
+}
+
+/// Returns the angle in degrees of the given attribute, if it is a number between -`max` and `max`.
+/// Values that are not finite, which parse from text such as `NaN` and `1e400`, are rejected too.
+fn parse_degrees(element: roxmltree::Node, name: &str, max: f64) -> Option<f64> {
+    let degrees: f64 = element.attribute(name)?.parse().ok()?;
+    (degrees.abs() <= max).then_some(degrees)
+}
+
+/// Returns the way if it is a drivable highway.
+fn parse_way(element: roxmltree::Node) -> Option<Way> {
+    let id = element.attribute("id")?.parse().ok()?;
+    let tags: BTreeMap<&str, &str> = element
+        .children()
+        .filter(|n| n.has_tag_name("tag"))
+        .filter_map(|n| Some((n.attribute("k")?, n.attribute("v")?)))
+        .collect();
+    let highway = *tags.get("highway")?;
+    if !DRIVABLE_HIGHWAYS.contains(&highway) {
+        return None;
+    }
+    let mut nodes: Vec<i64> = element
+        .children()
+        .filter(|n| n.has_tag_name("nd"))
+        .filter_map(|n| n.attribute("ref")?.parse().ok())
+        .collect();
+
+    let oneway = match tags.get("oneway").copied() {
+        Some("yes" | "true" | "1") => true,
+        Some("-1" | "reverse") => {
+            nodes.reverse();
+            true
+        }
+        Some(_) => false,
+        None => highway == "motorway" || tags.get("junction") == Some(&"roundabout"),
+    };
+    let lanes = |key| tags.get(key).and_then(|v| v.parse::<u8>().ok());
+    let default_lanes = if highway == "motorway" || highway == "trunk" {
+        2
+    } else {
+        1
+    };
+    let clamp = |lanes: u8| lanes.clamp(1, MAX_NO_LANES);
+    let (forward_lanes, backward_lanes) = if oneway {
+        (clamp(lanes("lanes").unwrap_or(default_lanes)), 0)
+    } else {
+        let half = lanes("lanes").map(|lanes| lanes / 2);
+        (
+            clamp(lanes("lanes:forward").or(half).unwrap_or(default_lanes)),
+            clamp(lanes("lanes:backward").or(half).unwrap_or(default_lanes)),
+        )
+    };
+
+    let bridge = tags.get("bridge").is_some_and(|v| *v != "no");
+    let layer = tags
+        .get("layer")
+        .and_then(|v| v.parse::<i8>().ok())
+        .unwrap_or(0);
+    let height = if bridge {
+        layer.max(1) as f32 * BRIDGE_DECK_HEIGHT
+    } else {
+        0.0
+    };
+
+    Some(Way {
+        id,
+        nodes,
+        forward_lanes,
+        backward_lanes,
+        height,
+    })
+}
+
+/// A stretch of a way between the places where it meets other ways.
+struct Piece<'a> {
+    way: &'a Way,
+    /// The OSM nodes at the start and end of the piece.
+    ends: [i64; 2],
+    points: Vec<Vec3>,
+}
+
+struct Importer<'a, W> {
+    world: &'a mut W,
+    /// The nodes of the world that were built at the end of a road, for each OSM node.
+    ends: BTreeMap<i64, Vec<NodeId>>,
+    report: OsmReport,
+}
+
+impl<W: RoadManipulator + RoadQuery> Importer<'_, W> {
+    /// Builds a one-way road from the given curves, which are in the driving direction and start
+    /// and end at the given OSM nodes. The ends of the road are snapped to nodes that were built
+    /// at the same OSM nodes, if the nodes are in the same place and direction.
+    fn add_road(
+        &mut self,
+        way: &Way,
+        ends: [i64; 2],
+        mut curves: Vec<CurveSum>,
+        node_type: NodeType,
+    ) {
+        let no_curves = curves.len();
+        let first_snap = self.find_snap(ends[0], curves[0].first(), node_type, Side::Out);
+        let last_snap = self.find_snap(ends[1], curves[no_curves - 1].last(), node_type, Side::In);
+
+        let first_height = first_snap.as_ref().map_or(way.height, |s| s.pos().y);
+        let last_height = last_snap.as_ref().map_or(way.height, |s| s.pos().y);
+        for (i, curve) in curves.iter_mut().enumerate() {
+            let from = if i == 0 { first_height } else { way.height };
+            let to = if i == no_curves - 1 {
+                last_height
+            } else {
+                way.height
+            };
+            let length = curve.get_profile().length();
+            curve.set_profile(VerticalProfile::fit(from, None, to, None, length));
+        }
+
+        let first_type = first_snap.as_ref().map_or(node_type, |s| s.node_type());
+        let last_type = last_snap.as_ref().map_or(node_type, |s| s.node_type());
+        let mut nodes = vec![match first_snap {
+            Some(snap) => LNodeBuilderType::Old(snap),
+            None => LNodeBuilderType::new(curves[0].first(), node_type),
+        }];
+        for curve in curves[..no_curves - 1].iter() {
+            nodes.push(LNodeBuilderType::new(curve.last(), node_type));
+        }
+        nodes.push(match last_snap {
+            Some(snap) => LNodeBuilderType::Old(snap),
+            None => LNodeBuilderType::new(curves[no_curves - 1].last(), node_type),
+        });
+        let segments = curves
+            .into_iter()
+            .enumerate()
+            .map(|(i, curve)| {
+                let from = if i == 0 { first_type } else { node_type };
+                let to = if i == no_curves - 1 {
+                    last_type
+                } else {
+                    node_type
+                };
+                LSegmentBuilder::transition(from, to, curve)
+            })
+            .collect();
+        let mut road_builder = LRoadBuilder::new(nodes, segments, false);
+        road_builder.set_bridge(way.height > 0.0);
+
+        let segment_ids = match self.world.add_road(road_builder, node_type) {
+            Ok((_, segment_ids)) => segment_ids,
+            Err(err) => {
+                self.report.skipped.push((way.id, err));
+                return;
+            }
+        };
+        self.report.roads += 1;
+        let first_node = segment_ids
+            .first()
+            .and_then(|id| self.world.get_segment(*id))
+            .map(|s| s.from_node());
+        let last_node = segment_ids
+            .last()
+            .and_then(|id| self.world.get_segment(*id))
+            .map(|s| s.to_node());
+        for (osm_id, node_id) in ends.into_iter().zip([first_node, last_node]) {
+            let Some(node_id) = node_id else { continue };
+            let nodes = self.ends.entry(osm_id).or_default();
+            if !nodes.contains(&node_id) {
+                nodes.push(node_id);
+            }
+        }
+    }
+
+    /// Returns the snap config on the given side of the nodes that were built at the given OSM
+    /// node, which is at the given location. Only nodes with the same lane width are snapped to,
+    /// such that any transition segment that is needed only changes the number of lanes.
+    fn find_snap(
+        &self,
+        osm_id: i64,
+        loc: Loc,
+        node_type: NodeType,
+        side: Side,
+    ) -> Option<SnapConfig> {
+        let candidates = self.ends.get(&osm_id)?;
+        candidates
+            .iter()
+            .filter_map(|id| self.world.get_node(*id))
+            .filter_map(|node| {
+                self.world
+                    .get_snap_configs_closest_node(node.loc().pos, node_type)
+                    .filter(|(id, _)| *id == node.id())
+            })
+            .flat_map(|(_, configs)| configs)
+            .find(|snap| {
+                snap.side() == side
+                    && snap.node_type().lane_width() == node_type.lane_width()
+                    && snap.dir() == loc.dir
+                    && (snap.pos() - loc.pos).xz().length() < SNAP_DISTANCE
+            })
+    }
+}
+
+/// Returns the points that the curves of the polyline should pass through between its ends, when
+/// every corner of the polyline is rounded by the largest circle that fits without overlapping the
+/// rounding of the neighbouring corners.
+fn fillet_targets(points: &[Vec3]) -> Vec<Vec3> {
+    let last = points.len() - 1;
+    let mut targets = vec![];
+    for i in 1..last {
+        let (diff_in, diff_out) = (points[i] - points[i - 1], points[i + 1] - points[i]);
+        let (length_in, length_out) = (diff_in.length(), diff_out.length());
+        let (dir_in, dir_out) = (diff_in / length_in, diff_out / length_out);
+        if dir_in.dot(dir_out) > COS_STRAIGHT {
+            targets.push(points[i]);
+            continue;
+        }
+        // The ends of the polyline are not rounded, so the corners next to them can use the
+        // whole of the piece towards the end.
+        let share_in = if i == 1 { 1.0 } else { 0.5 };
+        let share_out = if i == last - 1 { 1.0 } else { 0.5 };
+        let tangent = (length_in * share_in).min(length_out * share_out);
+        targets.push(points[i] - dir_in * tangent);
+        targets.push(points[i] + dir_out * tangent);
+    }
+    targets.dedup_by(|a, b| a.distance(*b) < 0.01);
+    targets
+        .retain(|target| target.distance(points[0]) > 0.01 && target.distance(points[last]) > 0.01);
+    targets
+}
+
+/// Fits tangentially joined curves from the given start through the given targets. A target that
+/// lies straight ahead is reached with a straight curve and any other with a circular curve.
+/// Targets closer than the shortest possible road to where the previous curve ended are skipped,
+/// unless no curve would be built at all.
+fn fit_curves(first: Loc, targets: &[Vec3]) -> Vec<CurveSum> {
+    let mut curves = vec![];
+    let mut loc = first;
+    for (i, target) in targets.iter().enumerate() {
+        let diff = *target - loc.pos;
+        let is_last = i == targets.len() - 1;
+        if diff.length() < ROAD_MIN_LENGTH && !(is_last && curves.is_empty()) {
+            continue;
+        }
+        if diff.normalize_or_zero().dot(*loc.dir) > COS_STRAIGHT {
+            let (curve, _) = Curve::<Straight>::from_first_locked(loc, *target);
+            curves.push(curve.into());
+        } else {
+            let (curve, _) = Curve::<Circular>::from_first_locked(loc, *target);
+            match CompositeCurveSum::from(curve) {
+                CompositeCurveSum::Single(curve) => curves.push(curve),
+                CompositeCurveSum::Double(curve1, curve2) => {
+                    curves.push(curve1);
+                    curves.push(curve2);
+                }
+            }
+        }
+        loc = curves[curves.len() - 1].last();
+    }
+    curves
+}
+
+/// Tries to end the curves exactly at the given location, by adding a curve from the end of the
+/// curves. If the end of the curves is too close or cannot be joined, the last curve is replaced
+/// instead. Returns false without changing the curves if neither works.
+fn join_curves(curves: &mut Vec<CurveSum>, first: Loc, last: Loc) -> bool {
+    let join = |from: Loc| -> Option<Vec<CurveSum>> {
+        if from.dir == last.dir {
+            if let Ok(curve) = Curve::<Straight>::from_both_locked(from, last) {
+                return Some(vec![curve.into()]);
+            }
+        }
+        match CompositeCurveSum::from(Curve::<Circular>::from_both_locked(from, last).ok()?) {
+            CompositeCurveSum::Single(curve) => Some(vec![curve]),
+            CompositeCurveSum::Double(curve1, curve2) => Some(vec![curve1, curve2]),
+        }
+    };
+
+    let from = curves.last().map_or(first, |c| c.last());
+    if from.pos.distance(last.pos) >= ROAD_MIN_LENGTH || curves.is_empty() {
+        if let Some(mut joined) = join(from) {
+            curves.append(&mut joined);
+            return true;
+        }
+    }
+    let Some(removed) = curves.pop() else {
+        return false;
+    };
+    if let Some(mut joined) = join(removed.first()) {
+        curves.append(&mut joined);
+        return true;
+    }
+    curves.push(removed);
+    false
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{read_osm, OsmOptions};
+    use crate::{GeoOrigin, ImportError};
+
+    use world::World;
+    use world_api::{RoadGenErr, RoadQuery};
+
+    use glam::Vec3;
+
+    fn options() -> OsmOptions {
+        OsmOptions {
+            origin: Some(GeoOrigin::new(0.0, 0.0)),
+            ..Default::default()
+        }
+    }
+
+    /// Returns an OSM document with the given nodes, given as id, lat and lon, and a single way
+    /// through the given nodes.
+    fn document(nodes: &[(&str, &str, &str)], way: &[&str], tags: &str) -> String {
+        let nodes: String = nodes
+            .iter()
+            .map(|(id, lat, lon)| format!(r#"<node id="{}" lat="{}" lon="{}"/>"#, id, lat, lon))
+            .collect();
+        let refs: String = way
+            .iter()
+            .map(|id| format!(r#"<nd ref="{}"/>"#, id))
+            .collect();
+        format!(
+            r#"<osm>{}<way id="7">{}<tag k="highway" v="primary"/>{}</way></osm>"#,
+            nodes, refs, tags
+        )
+    }
+
+    #[test]
+    fn imports_a_straight_way() {
+        let mut world = World::new();
+        let text = document(
+            &[("1", "0", "0"), ("2", "0.001", "0")],
+            &["1", "2"],
+            r#"<tag k="oneway" v="yes"/>"#,
+        );
+        let report = read_osm(&mut world, &text, &options()).unwrap();
+        assert_eq!(report.roads, 1);
+        assert!(report.skipped.is_empty());
+        assert_eq!(world.segments().count(), 1);
+        assert!(world.validate().is_empty());
+    }
+
+    #[test]
+    fn rejects_invalid_xml() {
+        let mut world = World::new();
+        let result = read_osm(&mut world, "<osm><node id=\"1\"></osm>", &options());
+        assert!(matches!(result, Err(ImportError::Xml(_))));
+    }
+
+    #[test]
+    fn skips_bad_coordinates() {
+        for (lat, lon) in [
+            ("NaN", "0"),
+            ("0", "NaN"),
+            ("1e400", "0"),
+            ("-inf", "0"),
+            ("90.5", "0"),
+            ("0", "-180.5"),
+            ("north", "0"),
+        ] {
+            let mut world = World::new();
+            let text = document(
+                &[("1", "0", "0"), ("2", lat, lon), ("3", "0.001", "0")],
+                &["1", "2", "3"],
+                r#"<tag k="oneway" v="yes"/>"#,
+            );
+            let report = read_osm(&mut world, &text, &options()).unwrap();
+            // The bad node is left out of the way, which still runs between the other two.
+            assert_eq!(report.roads, 1, "{} {}", lat, lon);
+            assert!(world.validate().is_empty());
+        }
+    }
+
+    #[test]
+    fn skips_bad_bounds() {
+        let mut world = World::new();
+        let text = document(
+            &[("1", "0", "0"), ("2", "0.001", "0")],
+            &["1", "2"],
+            r#"<tag k="oneway" v="yes"/>"#,
+        )
+        .replace(
+            "<osm>",
+            r#"<osm><bounds minlat="NaN" minlon="0" maxlat="0.001" maxlon="0"/>"#,
+        );
+        let report = read_osm(&mut world, &text, &OsmOptions::default()).unwrap();
+        let [lon, lat] = report.origin.to_lon_lat(Vec3::ZERO);
+        assert!(lon.is_finite() && lat.is_finite());
+        assert_eq!(report.roads, 1);
+    }
+
+    #[test]
+    fn skips_bad_ids_and_ways() {
+        let mut world = World::new();
+        let text = r#"<osm>
+            <node id="1" lat="0" lon="0"/>
+            <node id="x" lat="0.0005" lon="0"/>
+            <node lat="0.0005" lon="0"/>
+            <node id="2" lat="0.001" lon="0"/>
+            <way id="7"><nd ref="1"/><nd ref="x"/><nd ref="3"/><nd/><nd ref="2"/>
+                <tag k="highway" v="primary"/><tag k="oneway" v="yes"/></way>
+            <way><nd ref="1"/><nd ref="2"/><tag k="highway" v="primary"/></way>
+            <way id="8"><nd ref="1"/><nd ref="2"/><tag k="highway" v="footway"/></way>
+            <way id="9"><nd ref="1"/><tag k="highway" v="primary"/></way>
+            <way id="10"><nd ref="1"/><nd ref="2"/><tag k="highway"/></way>
+        </osm>"#;
+        let report = read_osm(&mut world, text, &options()).unwrap();
+        assert_eq!(report.roads, 1);
+        assert!(report.skipped.is_empty());
+        assert_eq!(world.segments().count(), 1);
+    }
+
+    #[test]
+    fn skips_hairpins_that_cannot_be_fitted() {
+        let mut world = World::new();
+        // The way goes 111 meters north and comes back 3 meters to the east.
+        let text = document(
+            &[("1", "0", "0"), ("2", "0.001", "0"), ("3", "0", "0.000027")],
+            &["1", "2", "3"],
+            r#"<tag k="oneway" v="yes"/>"#,
+        );
+        let report = read_osm(&mut world, &text, &options()).unwrap();
+        assert_eq!(report.roads, 0);
+        assert!(matches!(
+            report.skipped[..],
+            [(7, RoadGenErr::CurveFitFailed)]
+        ));
+        assert_eq!(world.segments().count(), 0);
+
+        // Both directions of a two-way road are reported.
+        let mut world = World::new();
+        let text = document(
+            &[("1", "0", "0"), ("2", "0.001", "0"), ("3", "0", "0.000027")],
+            &["1", "2", "3"],
+            "",
+        );
+        let report = read_osm(&mut world, &text, &options()).unwrap();
+        assert_eq!(report.roads, 0);
+        assert_eq!(report.skipped.len(), 2);
+    }
+}
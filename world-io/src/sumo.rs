@@ -0,0 +1,227 @@
+//! Writes the road network as the plain XML files of the SUMO traffic simulator: a node file, an
+//! edge file and a connection file. They are turned into a SUMO network with
+//!
+//! ```text
+//! netconvert --node-files=design.nod.xml --edge-files=design.edg.xml \
+//!     --connection-files=design.con.xml --no-turnarounds -o design.net.xml
+//! ```
+//!
+//! Every node becomes a SUMO node and every segment an edge, whose shape is the spine of the
+//! segment. The lanes of an incoming and an outgoing segment are connected where they occupy the
+//! same lanes of the node. SUMO indexes lanes from the right, whereas the world indexes them from
+//! the left.
+use super::{plan_pos, ExportResult};
+
+use world_api::{LaneMapEntry, RoadQuery, SegmentView, Side};
+
+use curves::CurveShared;
+
+use glam::Vec3;
+
+use std::ffi::OsString;
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::{Path, PathBuf};
+
+/// Configures how the road network is written.
+#[derive(Debug, Clone, Copy)]
+pub struct SumoOptions {
+    /// The speed limit in meters per second of every edge.
+    pub speed: f32,
+}
+
+impl Default for SumoOptions {
+    fn default() -> Self {
+        // 100 km/h.
+        Self { speed: 27.78 }
+    }
+}
+
+/// Writes the road network of the world to the node, edge and connection files of SUMO. The file
+/// names are the given path followed by `.nod.xml`, `.edg.xml` and `.con.xml`, and existing files
+/// are replaced.
+pub fn export_sumo<W: RoadQuery>(world: &W, path: &Path, options: &SumoOptions) -> ExportResult {
+    let create = |suffix: &str| -> std::io::Result<BufWriter<File>> {
+        let mut name = OsString::from(path.as_os_str());
+        name.push(suffix);
+        Ok(BufWriter::new(File::create(PathBuf::from(name))?))
+    };
+
+    let mut writer = create(".nod.xml")?;
+    write_sumo_nodes(world, &mut writer)?;
+    writer.flush()?;
+    let mut writer = create(".edg.xml")?;
+    write_sumo_edges(world, &mut writer, options)?;
+    writer.flush()?;
+    let mut writer = create(".con.xml")?;
+    write_sumo_connections(world, &mut writer)?;
+    writer.flush()?;
+    Ok(())
+}
+
+/// Writes every node of the world as a SUMO node file.
+pub fn write_sumo_nodes<W: RoadQuery>(world: &W, mut writer: impl Write) -> ExportResult {
+    writeln!(writer, r#"<?xml version="1.0" encoding="UTF-8"?>"#)?;
+    writeln!(writer, "<nodes>")?;
+    for node in world.nodes() {
+        let pos = node.loc().pos;
+        let [x, y] = plan_pos(pos);
+        writeln!(
+            writer,
+            r#"  <node id="{}" x="{:.3}" y="{:.3}" z="{:.3}"/>"#,
+            node.id(),
+            x,
+            y,
+            pos.y
+        )?;
+    }
+    writeln!(writer, "</nodes>")?;
+    Ok(())
+}
+
+/// Writes every segment of the world as a SUMO edge file.
+pub fn write_sumo_edges<W: RoadQuery>(
+    world: &W,
+    mut writer: impl Write,
+    options: &SumoOptions,
+) -> ExportResult {
+    writeln!(writer, r#"<?xml version="1.0" encoding="UTF-8"?>"#)?;
+    writeln!(writer, "<edges>")?;
+    for segment in world.segments() {
+        write_edge(&mut writer, &segment, options)?;
+    }
+    writeln!(writer, "</edges>")?;
+    Ok(())
+}
+
+/// Writes the lanes that continue into each other at every node of the world as a SUMO
+/// connection file.
+pub fn write_sumo_connections<W: RoadQuery>(world: &W, mut writer: impl Write) -> ExportResult {
+    writeln!(writer, r#"<?xml version="1.0" encoding="UTF-8"?>"#)?;
+    writeln!(writer, "<connections>")?;
+    for node in world.nodes() {
+        let lane_map = world.get_node_lane_map(node.id()).unwrap_or_default();
+        let (incoming, outgoing): (Vec<&LaneMapEntry>, Vec<&LaneMapEntry>) =
+            lane_map.iter().partition(|entry| entry.side() == Side::In);
+        for a in incoming.iter() {
+            let Some(from) = world.get_segment(a.segment_id()) else {
+                continue;
+            };
+            for b in outgoing.iter() {
+                let Some(to) = world.get_segment(b.segment_id()) else {
+                    continue;
+                };
+                for (i, lane) in a.snap_range().iter().enumerate() {
+                    let Some(j) = b.snap_range().iter().position(|other| other == lane) else {
+                        continue;
+                    };
+                    writeln!(
+                        writer,
+                        r#"  <connection from="{}" to="{}" fromLane="{}" toLane="{}"/>"#,
+                        from.id(),
+                        to.id(),
+                        sumo_lane(&from, i),
+                        sumo_lane(&to, j)
+                    )?;
+                }
+            }
+        }
+    }
+    writeln!(writer, "</connections>")?;
+    Ok(())
+}
+
+/// Returns the SUMO index of the lane of the segment with the given index from the left. The edge
+/// of a transition segment has the lanes of whichever end has the most lanes, and since lanes are
+/// added and dropped on the right, the lanes at both ends are indexed from the same left edge.
+fn sumo_lane(segment: &SegmentView, index: usize) -> usize {
+    segment.segment_type().max_no_lanes() as usize - 1 - index
+}
+
+fn write_edge(
+    writer: &mut impl Write,
+    segment: &SegmentView,
+    options: &SumoOptions,
+) -> ExportResult {
+    let segment_type = segment.segment_type();
+    let (from_type, to_type) = (segment_type.from_type(), segment_type.to_type());
+    let lane_width = from_type.lane_width_f32().max(to_type.lane_width_f32());
+    let shape: Vec<String> = segment
+        .curve()
+        .get_spine()
+        .iter()
+        .map(|loc| shape_point(loc.pos))
+        .collect();
+    writeln!(
+        writer,
+        r#"  <edge id="{}" from="{}" to="{}" numLanes="{}" speed="{:.2}" width="{:.2}" spreadType="center" shape="{}"/>"#,
+        segment.id(),
+        segment.from_node(),
+        segment.to_node(),
+        segment_type.max_no_lanes(),
+        options.speed,
+        lane_width,
+        shape.join(" ")
+    )?;
+    Ok(())
+}
+
+fn shape_point(pos: Vec3) -> String {
+    let [x, y] = plan_pos(pos);
+    format!("{:.3},{:.3},{:.3}", x, y, pos.y)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::write_sumo_connections;
+    use crate::test_utils::{add_road, lanes, straight};
+
+    use world::World;
+
+    use glam::Vec3;
+
+    #[test]
+    fn connections_count_lanes_from_the_right() {
+        // Three lanes, then a transition that drops the rightmost lane, then two lanes.
+        let mut world = World::new();
+        let points = [0.0, 100.0, 200.0, 300.0].map(|x| Vec3::new(x, 0.0, 0.0));
+        let curves = points.windows(2).map(|p| straight(p[0], p[1])).collect();
+        let segments = add_road(
+            &mut world,
+            curves,
+            &[lanes(3), lanes(3), lanes(2), lanes(2)],
+        );
+
+        let mut bytes = vec![];
+        write_sumo_connections(&world, &mut bytes).unwrap();
+        let text = String::from_utf8(bytes).unwrap();
+        let document = roxmltree::Document::parse(&text).unwrap();
+        let mut connections: Vec<(String, String, usize, usize)> = document
+            .descendants()
+            .filter(|n| n.has_tag_name("connection"))
+            .map(|c| {
+                let attribute = |name| c.attribute(name).unwrap();
+                (
+                    attribute("from").to_string(),
+                    attribute("to").to_string(),
+                    attribute("fromLane").parse().unwrap(),
+                    attribute("toLane").parse().unwrap(),
+                )
+            })
+            .collect();
+        connections.sort();
+
+        // The edge of the transition has three lanes, of which the two leftmost continue into the
+        // two lanes of the last segment.
+        let [first, transition, last] = [0, 1, 2].map(|i| segments[i].to_string());
+        let mut expected = vec![
+            (first.clone(), transition.clone(), 0, 0),
+            (first.clone(), transition.clone(), 1, 1),
+            (first, transition.clone(), 2, 2),
+            (transition.clone(), last.clone(), 1, 0),
+            (transition, last, 2, 1),
+        ];
+        expected.sort();
+        assert_eq!(connections, expected);
+    }
+}
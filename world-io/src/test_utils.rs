@@ -0,0 +1,80 @@
+//! Helpers for building small worlds in tests.
+use world::World;
+use world_api::{
+    LNodeBuilderType, LRoadBuilder, LSegmentBuilder, LaneWidth, NodeType, RoadManipulator, Side,
+};
+
+use curves::{Circular, CompositeCurveSum, Curve, CurveShared, CurveSum, Straight};
+use utils::id::SegmentId;
+use utils::math::Loc;
+
+use glam::Vec3;
+
+/// Returns the node type with the given number of lanes of standard width.
+pub fn lanes(no_lanes: u8) -> NodeType {
+    NodeType::new(LaneWidth::Standard, no_lanes)
+}
+
+/// Returns the straight curve from `from` to `to`.
+pub fn straight(from: Vec3, to: Vec3) -> CurveSum {
+    Curve::<Straight>::from_free(from, to).0.into()
+}
+
+/// Returns the circular curves that start at `first` and end at `last_pos`.
+pub fn arc(first: Loc, last_pos: Vec3) -> Vec<CurveSum> {
+    let (curve, _) = Curve::<Circular>::from_first_locked(first, last_pos);
+    match CompositeCurveSum::from(curve) {
+        CompositeCurveSum::Single(curve) => vec![curve],
+        CompositeCurveSum::Double(curve1, curve2) => vec![curve1, curve2],
+    }
+}
+
+/// Adds a road going through the given curves, and returns its segments. The nodes of the road
+/// have the given types in order, and a segment between two types that differ is a transition.
+/// Panics if the road cannot be added.
+pub fn add_road(
+    world: &mut World,
+    curves: Vec<CurveSum>,
+    node_types: &[NodeType],
+) -> Vec<SegmentId> {
+    assert_eq!(curves.len() + 1, node_types.len());
+    let mut nodes = vec![LNodeBuilderType::new(curves[0].first(), node_types[0])];
+    for (curve, node_type) in curves.iter().zip(node_types[1..].iter()) {
+        nodes.push(LNodeBuilderType::new(curve.last(), *node_type));
+    }
+    let segments = curves
+        .into_iter()
+        .zip(node_types.windows(2))
+        .map(|(curve, types)| LSegmentBuilder::transition(types[0], types[1], curve))
+        .collect();
+    let road = LRoadBuilder::new(nodes, segments, false);
+    let (_, segments) = world.add_road(road, node_types[0]).unwrap();
+    segments
+}
+
+/// Adds a straight road of one lane that leaves the given lane, counted from the left, of the node
+/// at `pos` and continues for 100 meters. Returns its segment. Panics if the road cannot be added.
+pub fn add_branch(world: &mut World, pos: Vec3, lane: i8) -> SegmentId {
+    let (_, configs) = world.get_snap_configs_closest_node(pos, lanes(1)).unwrap();
+    let snap = configs
+        .into_iter()
+        .find(|snap| {
+            let range = snap.get_snap_range();
+            snap.side() == Side::Out && range.smallest() == lane && range.len() == 1
+        })
+        .unwrap();
+    let first = Loc::new(snap.pos(), snap.dir());
+    let curve: CurveSum =
+        Curve::<Straight>::from_first_locked(first, snap.pos() + *snap.dir() * 100.0)
+            .0
+            .into();
+    let last = LNodeBuilderType::new(curve.last(), lanes(1));
+    let segment = LSegmentBuilder::transition(snap.node_type(), lanes(1), curve);
+    let road = LRoadBuilder::new(
+        vec![LNodeBuilderType::Old(snap), last],
+        vec![segment],
+        false,
+    );
+    let (_, segments) = world.add_road(road, lanes(1)).unwrap();
+    segments[0]
+}
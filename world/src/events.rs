@@ -0,0 +1,61 @@
+//! Collects the changes of the world for each subscriber, until the subscriber drains them.
+use world_api::{SubscriberId, WorldEvent};
+
+#[derive(Default)]
+pub(crate) struct EventLog {
+    /// The events that each subscriber has not drained yet, or {`None`} for subscribers that have
+    /// unsubscribed. The slots of subscribers that have unsubscribed are never reused, such that a
+    /// stale id cannot drain the events of a newer subscriber.
+    queues: Vec<Option<Vec<WorldEvent>>>,
+}
+
+impl EventLog {
+    pub fn subscribe(&mut self) -> SubscriberId {
+        self.queues.push(Some(vec![]));
+        SubscriberId(self.queues.len() - 1)
+    }
+
+    pub fn unsubscribe(&mut self, subscriber: SubscriberId) {
+        if let Some(queue) = self.queues.get_mut(subscriber.0) {
+            *queue = None;
+        }
+    }
+
+    /// Hands the event to every subscriber. If there are no subscribers the event is dropped.
+    pub fn push(&mut self, event: WorldEvent) {
+        for queue in self.queues.iter_mut().flatten() {
+            queue.push(event.clone());
+        }
+    }
+
+    /// Returns the events of the subscriber, or nothing if it is not subscribed.
+    pub fn drain(&mut self, subscriber: SubscriberId) -> Vec<WorldEvent> {
+        match self.queues.get_mut(subscriber.0) {
+            Some(Some(queue)) => std::mem::take(queue),
+            _ => vec![],
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::test_utils::add_straight_road;
+    use crate::World;
+
+    use world_api::EventStream;
+
+    use glam::Vec3;
+
+    #[test]
+    fn unsubscribed_ids_are_not_reused() {
+        let mut world = World::new();
+        let old = world.subscribe();
+        world.unsubscribe(old);
+        let new = world.subscribe();
+        assert_ne!(old, new);
+
+        add_straight_road(&mut world, Vec3::ZERO, Vec3::new(100.0, 0.0, 0.0));
+        assert!(world.drain_events(old).is_empty());
+        assert!(!world.drain_events(new).is_empty());
+    }
+}
@@ -0,0 +1,62 @@
+//! Keeps track of the edits made to the world, such that they can be undone and redone.
+use crate::roads::GraphSnapshot;
+
+use utils::id::TreeId;
+use world_api::Tree;
+
+use std::collections::VecDeque;
+
+/// The maximum number of edits that can be undone. When more edits are made, the oldest ones are
+/// forgotten.
+const MAX_HISTORY: usize = 128;
+
+/// A single edit of the world, recorded with enough information to both revert and reapply it.
+#[derive(Clone)]
+pub(crate) enum Edit {
+    /// The nodes and segments touched by a road edit, before and after the edit was made.
+    Road {
+        before: GraphSnapshot,
+        after: GraphSnapshot,
+    },
+    AddTree {
+        id: TreeId,
+        tree: Tree,
+        model_id: u128,
+    },
+    RemoveTree {
+        id: TreeId,
+        tree: Tree,
+        model_id: u128,
+    },
+}
+
+#[derive(Default)]
+pub(crate) struct History {
+    undo_stack: VecDeque<Edit>,
+    redo_stack: Vec<Edit>,
+}
+
+impl History {
+    /// Records a new edit. All edits that could be redone are discarded.
+    pub fn record(&mut self, edit: Edit) {
+        self.redo_stack.clear();
+        if self.undo_stack.len() == MAX_HISTORY {
+            self.undo_stack.pop_front();
+        }
+        self.undo_stack.push_back(edit);
+    }
+
+    /// Returns the edit that should be undone, and marks it as redoable.
+    pub fn undo(&mut self) -> Option<Edit> {
+        let edit = self.undo_stack.pop_back()?;
+        self.redo_stack.push(edit.clone());
+        Some(edit)
+    }
+
+    /// Returns the edit that should be redone, and marks it as undoable.
+    pub fn redo(&mut self) -> Option<Edit> {
+        let edit = self.redo_stack.pop()?;
+        self.undo_stack.push_back(edit.clone());
+        Some(edit)
+    }
+}
@@ -3,38 +3,184 @@
 //! crate does not care about constraints such as road curvature, it only concerns itself with the
 //! logical state of the world. For stuff like road curvature the tool crate is intended to enforce
 //! it.
+mod events;
+mod history;
 mod nature;
 mod roads;
+mod save_game;
 mod simulation;
+mod spatial_grid;
+#[cfg(test)]
+mod test_utils;
+
+pub use roads::{GraphViolation, ModeViolation};
+pub use save_game::{SaveError, SaveFormat, SaveResult, SAVE_VERSION};
 
 use std::time::Duration;
 
 use utils::math::Loc;
 use world_api::{
-    IdGetter, RoadManipulator, SimController, SimData, TreeManipulator, WorldManipulator,
+    EventStream, HistoryManipulator, IdGetter, RoadManipulator, RoadQuery, RouteQuery,
+    SimController, SimData, SuperNodeManipulator, TreeManipulator, WorldManipulator,
+};
+use world_api::{
+    LNodeBuilderType, LRoadBuilder, LSuperRoadBuilder, LaneId, LaneMapEntry, NetworkStats,
+    NodeType, NodeView, RoadGenErr, Route, SegmentType, SegmentView, Side, SnapConfig,
+    SubscriberId, SuperNodeType, SuperSnapConfig, Tree, WorldEvent,
 };
-use world_api::{LRoadBuilder, NodeType, Side, SnapConfig, Tree};
+
+use events::EventLog;
+use history::{Edit, History};
 
 use nature::Trees;
-use roads::RoadGraph;
+use roads::{GraphSnapshot, RoadGraph, SuperNodes};
 // use simulation::SimHandler;
 
-use utils::id::{NodeId, SegmentId, TreeId};
+use curves::CurveSum;
+use utils::id::{NodeId, SegmentId, SuperNodeId, TreeId};
 
 use glam::Vec3;
 use serde::{Deserialize, Serialize};
 
+/// The snap config to continue building from and the new segments of a road added to the world.
+type AddedRoad = (Option<SnapConfig>, Vec<SegmentId>);
+
 #[derive(Serialize, Deserialize, Default)]
 pub struct World {
     road_graph: RoadGraph,
+    super_nodes: SuperNodes,
     // sim_handler: SimHandler,
     trees: Trees,
+    #[serde(skip)]
+    history: History,
+    #[serde(skip)]
+    events: EventLog,
 }
 
 impl World {
     pub fn new() -> Self {
         Self::default()
     }
+
+    /// Checks the invariants of the road graph, and returns every violation found. This is meant
+    /// for finding the cause of a corrupted world, such as one read from a damaged save game.
+    pub fn validate(&self) -> Vec<GraphViolation> {
+        self.road_graph.validate()
+    }
+
+    /// Rebuilds everything that is derived from the rest of the world and therefore not saved.
+    fn rebuild_derived(&mut self) {
+        self.road_graph.rebuild_lane_graph();
+        self.road_graph.rebuild_spatial_grids();
+        self.trees.rebuild_grid();
+    }
+
+    /// Adds the given roads to the road graph, and records them in the history as a single edit.
+    /// Returns the result of adding each road. If any of the roads collide with existing segments
+    /// none of them are added.
+    fn add_roads(
+        &mut self,
+        roads: Vec<LRoadBuilder>,
+        sel_node_type: NodeType,
+    ) -> Result<Vec<AddedRoad>, RoadGenErr> {
+        let mut collisions: Vec<SegmentId> = roads
+            .iter()
+            .flat_map(|road| self.road_graph.find_collisions(road))
+            .collect();
+        if !collisions.is_empty() {
+            collisions.sort_unstable();
+            collisions.dedup();
+            return Err(RoadGenErr::Collision(collisions));
+        }
+
+        let old_nodes: Vec<NodeId> = roads
+            .iter()
+            .flat_map(|road| road.get_nodes().iter())
+            .filter_map(|node| match node {
+                LNodeBuilderType::Old(snap) => Some(snap.id()),
+                LNodeBuilderType::New(_) => None,
+            })
+            .collect();
+        let mut before = self.road_graph.snapshot(&old_nodes, &[]);
+
+        let results: Vec<AddedRoad> = roads
+            .into_iter()
+            .map(|road| self.road_graph.add_road_unchecked(road, sel_node_type))
+            .collect();
+
+        let segments: Vec<SegmentId> = results
+            .iter()
+            .flat_map(|(_, segments)| segments.iter().copied())
+            .collect();
+        let mut nodes: Vec<NodeId> = Vec::with_capacity(segments.len() + 1);
+        for segment in segments.iter() {
+            for node in self.road_graph.get_segment_nodes(*segment) {
+                if !nodes.contains(&node) {
+                    nodes.push(node);
+                }
+            }
+        }
+        let after = self.road_graph.snapshot(&nodes, &segments);
+        before.cover(&after);
+        self.record_edit(Edit::Road { before, after });
+
+        Ok(results)
+    }
+
+    /// Returns the nodes of a road that was just added to the road graph, in the order that they
+    /// were built.
+    fn get_road_nodes(&self, segments: &[SegmentId]) -> Vec<NodeId> {
+        let mut nodes = vec![self.road_graph.get_segment_nodes(segments[0])[0]];
+        for segment in segments {
+            nodes.push(self.road_graph.get_segment_nodes(*segment)[1]);
+        }
+        nodes
+    }
+
+    /// Records the edit in the history, and tells the subscribers how the world changed.
+    fn record_edit(&mut self, edit: Edit) {
+        self.publish_edit(&edit, false);
+        self.history.record(edit);
+    }
+
+    /// Tells the subscribers how the world changed when the edit was made, or when it was undone.
+    fn publish_edit(&mut self, edit: &Edit, undo: bool) {
+        match (edit, undo) {
+            (Edit::Road { before, after }, false) => self.publish_road_change(before, after),
+            (Edit::Road { before, after }, true) => self.publish_road_change(after, before),
+            (Edit::AddTree { id, tree, model_id }, false)
+            | (Edit::RemoveTree { id, tree, model_id }, true) => {
+                self.events
+                    .push(WorldEvent::TreeAdded(*id, *tree, *model_id));
+            }
+            (Edit::AddTree { id, model_id, .. }, true)
+            | (Edit::RemoveTree { id, model_id, .. }, false) => {
+                self.events.push(WorldEvent::TreeRemoved(*id, *model_id));
+            }
+        }
+    }
+
+    /// Tells the subscribers how the roads changed going from the snapshot `from` to the snapshot
+    /// `to`, which cover the same nodes and segments.
+    fn publish_road_change(&mut self, from: &GraphSnapshot, to: &GraphSnapshot) {
+        for id in from.present_segments() {
+            self.events.push(WorldEvent::SegmentRemoved(id));
+        }
+        let (old_nodes, new_nodes) = (from.present_nodes(), to.present_nodes());
+        for id in old_nodes.iter().filter(|id| !new_nodes.contains(id)) {
+            self.events.push(WorldEvent::NodeRemoved(*id));
+        }
+        for id in new_nodes.iter() {
+            if old_nodes.contains(id) {
+                self.events.push(WorldEvent::NodeChanged(*id));
+            } else {
+                self.events.push(WorldEvent::NodeAdded(*id));
+            }
+        }
+        for id in to.present_segments() {
+            self.events.push(WorldEvent::SegmentAdded(id));
+        }
+    }
 }
 
 impl WorldManipulator for World {
@@ -50,36 +196,115 @@ impl RoadManipulator for World {
         &mut self,
         road: LRoadBuilder,
         sel_node_type: NodeType,
-    ) -> (Option<SnapConfig>, Vec<SegmentId>) {
+    ) -> Result<(Option<SnapConfig>, Vec<SegmentId>), RoadGenErr> {
         // capture the spine to create lane paths for sim_handler
-        let _lane_paths: Vec<_> = {
-            // temporary until transition segments
-            let node_type = road.get_first_node_type();
-            let width = node_type.lane_width_f32();
-            let no_paths = node_type.no_lanes();
-            road.get_segments()
-                .iter()
-                .map(|b| b.get_spine().gen_parallel(width, no_paths))
-                .collect()
-        };
+        let _lane_paths: Vec<_> = road
+            .get_segments()
+            .iter()
+            .map(|b| {
+                let segment_type = b.segment_type();
+                b.get_spine().gen_parallel(
+                    segment_type.from_type().lane_width_f32(),
+                    segment_type.to_type().lane_width_f32(),
+                    segment_type.max_no_lanes(),
+                )
+            })
+            .collect();
 
-        let (snap, segments) = self.road_graph.add_road(road, sel_node_type);
+        let (snap, segments) = self
+            .add_roads(vec![road], sel_node_type)?
+            .pop()
+            .expect("A result is returned for every road");
 
         // for (i, lane_path) in lane_paths.into_iter().enumerate() {
         //     self.sim_handler.add_segment(segments[i], lane_path);
         // }
 
-        (snap, segments)
+        Ok((snap, segments))
+    }
+
+    fn get_road_collisions(&self, road: &LRoadBuilder) -> Vec<SegmentId> {
+        self.road_graph.find_collisions(road)
     }
 
     fn remove_segment(&mut self, segment_id: SegmentId) -> bool {
+        let nodes = self.road_graph.get_segment_nodes(segment_id);
+        let before = self.road_graph.snapshot(&nodes, &[segment_id]);
         let result = self.road_graph.remove_segment(segment_id);
+        if result {
+            let after = self.road_graph.snapshot(&nodes, &[segment_id]);
+            self.record_edit(Edit::Road { before, after });
+        }
         // if result {
         //     self.sim_handler.remove_segment(segment_id);
         // }
         result
     }
 
+    fn remove_node(&mut self, node_id: NodeId) -> Option<Vec<SegmentId>> {
+        let segments = self.road_graph.get_node_segments(node_id);
+        let mut nodes = self.road_graph.get_neighbour_nodes(node_id);
+        nodes.push(node_id);
+        let before = self.road_graph.snapshot(&nodes, &segments);
+        let removed = self.road_graph.remove_node(node_id)?;
+        let after = self.road_graph.snapshot(&nodes, &segments);
+        self.record_edit(Edit::Road { before, after });
+        Some(removed)
+    }
+
+    fn get_node_segments(&self, node_id: NodeId) -> Vec<SegmentId> {
+        self.road_graph.get_node_segments(node_id)
+    }
+
+    fn get_min_clearance(&self) -> f32 {
+        self.road_graph.get_min_clearance()
+    }
+
+    fn set_min_clearance(&mut self, clearance: f32) -> bool {
+        self.road_graph.set_min_clearance(clearance)
+    }
+
+    fn is_segment_bridge(&self, id: SegmentId) -> bool {
+        self.road_graph.is_segment_bridge(id)
+    }
+
+    fn get_node_grade(&self, node_id: NodeId) -> Option<f32> {
+        self.road_graph.get_node_grade(node_id)
+    }
+
+    fn split_segment(
+        &mut self,
+        segment_id: SegmentId,
+        length: f32,
+    ) -> Option<(NodeId, [SegmentId; 2])> {
+        if !self.road_graph.contains_segment(segment_id) {
+            return None;
+        }
+        let nodes = self.road_graph.get_segment_nodes(segment_id);
+        let mut before = self.road_graph.snapshot(&nodes, &[segment_id]);
+        let (node_id, [first_id, last_id]) = self.road_graph.split_segment(segment_id, length)?;
+        let after = self.road_graph.snapshot(
+            &[nodes[0], node_id, nodes[1]],
+            &[segment_id, first_id, last_id],
+        );
+        before.cover(&after);
+        self.record_edit(Edit::Road { before, after });
+        Some((node_id, [first_id, last_id]))
+    }
+
+    fn merge_segments(&mut self, node_id: NodeId) -> Option<SegmentId> {
+        let mut segments = self.road_graph.get_node_segments(node_id);
+        let mut nodes = self.road_graph.get_neighbour_nodes(node_id);
+        nodes.push(node_id);
+        let mut before = self.road_graph.snapshot(&nodes, &segments);
+        let segment_id = self.road_graph.merge_segments(node_id)?;
+        segments.push(segment_id);
+        let after = self.road_graph.snapshot(&nodes, &segments);
+        before.cover(&after);
+        self.record_edit(Edit::Road { before, after });
+        Some(segment_id)
+    }
+
     fn get_possible_snap_nodes(
         &self,
         side: Option<Side>,
@@ -97,6 +322,22 @@ impl RoadManipulator for World {
             .get_snap_configs_closest_node(ground_pos, node_type)
     }
 
+    fn get_segment_ids(&self) -> Vec<SegmentId> {
+        self.road_graph.get_segment_ids()
+    }
+
+    fn get_lanes(&self) -> Vec<LaneId> {
+        self.road_graph.get_lanes()
+    }
+
+    fn get_lane_successors(&self, lane: LaneId) -> &[LaneId] {
+        self.road_graph.get_lane_successors(lane)
+    }
+
+    fn get_segment_curve(&self, id: SegmentId) -> (&CurveSum, SegmentType) {
+        self.road_graph.get_segment_curve(id)
+    }
+
     fn debug_node(&self, id: NodeId) {
         self.road_graph.debug_node(id)
     }
@@ -106,18 +347,170 @@ impl RoadManipulator for World {
     }
 }
 
+impl RoadQuery for World {
+    fn nodes(&self) -> impl Iterator<Item = NodeView> + '_ {
+        self.road_graph.nodes()
+    }
+
+    fn segments(&self) -> impl Iterator<Item = SegmentView<'_>> {
+        self.road_graph.segments()
+    }
+
+    fn get_node(&self, id: NodeId) -> Option<NodeView> {
+        self.road_graph.get_node(id)
+    }
+
+    fn get_segment(&self, id: SegmentId) -> Option<SegmentView<'_>> {
+        self.road_graph.get_segment(id)
+    }
+
+    fn get_node_lane_map(&self, id: NodeId) -> Option<Vec<LaneMapEntry>> {
+        self.road_graph.get_node_lane_map(id)
+    }
+
+    fn compute_stats(&self) -> NetworkStats {
+        self.road_graph.compute_stats()
+    }
+}
+
+impl SuperNodeManipulator for World {
+    fn add_bidirectional_road(
+        &mut self,
+        road: LSuperRoadBuilder,
+    ) -> Result<(Option<SuperSnapConfig>, Vec<SegmentId>), RoadGenErr> {
+        let super_type = road.super_type();
+        let reverse = road.is_reverse();
+        let (forward, backward) = road.gen_road_builders();
+
+        let mut results = self.add_roads(vec![forward, backward], super_type.node_type())?;
+        let (_, backward_segments) = results.pop().expect("The backward road has a result");
+        let (_, forward_segments) = results.pop().expect("The forward road has a result");
+
+        // The backward road is built in the opposite direction of the forward road.
+        let forward_nodes = self.get_road_nodes(&forward_segments);
+        let backward_nodes = self.get_road_nodes(&backward_segments);
+        let super_node_ids: Vec<SuperNodeId> = forward_nodes
+            .iter()
+            .zip(backward_nodes.iter().rev())
+            .map(|(forward, backward)| {
+                self.super_nodes
+                    .add_super_node(&self.road_graph, super_type, *forward, *backward)
+            })
+            .collect();
+
+        let new_snap_id = super_node_ids[if reverse { 0 } else { super_node_ids.len() - 1 }];
+        let new_snap =
+            self.super_nodes
+                .get_super_snap_config(&self.road_graph, new_snap_id, super_type);
+
+        let mut segments = forward_segments;
+        segments.extend(backward_segments);
+        Ok((new_snap, segments))
+    }
+
+    fn get_possible_super_snap_nodes(
+        &self,
+        side: Option<Side>,
+        super_type: SuperNodeType,
+    ) -> Vec<(SuperNodeId, Loc)> {
+        self.super_nodes
+            .get_possible_super_snap_nodes(&self.road_graph, side, super_type)
+    }
+
+    fn get_super_snap_configs_closest_node(
+        &self,
+        ground_pos: Vec3,
+        super_type: SuperNodeType,
+    ) -> Option<(SuperNodeId, Vec<SuperSnapConfig>)> {
+        self.super_nodes.get_super_snap_configs_closest_node(
+            &self.road_graph,
+            ground_pos,
+            super_type,
+        )
+    }
+}
+
 impl TreeManipulator for World {
     fn add_tree(&mut self, tree: Tree, model_id: u128) -> TreeId {
-        self.trees.add_tree(tree, model_id)
+        let id = self.trees.add_tree(tree, model_id);
+        self.record_edit(Edit::AddTree { id, tree, model_id });
+        id
     }
 
     fn remove_tree(&mut self, tree_id: TreeId) -> u128 {
-        self.trees.remove_tree(tree_id)
+        let tree = *self.trees.get_tree(&tree_id);
+        let model_id = self.trees.remove_tree(tree_id);
+        self.record_edit(Edit::RemoveTree {
+            id: tree_id,
+            tree,
+            model_id,
+        });
+        model_id
     }
 
     fn get_tree_pos(&self, id: TreeId) -> Vec3 {
         self.trees.get_tree_pos(id)
     }
+
+    fn get_trees(&self) -> Vec<(u128, Vec<(TreeId, Tree)>)> {
+        self.trees.get_trees()
+    }
+}
+
+impl HistoryManipulator for World {
+    fn undo(&mut self) -> bool {
+        let Some(edit) = self.history.undo() else {
+            return false;
+        };
+        self.publish_edit(&edit, true);
+        match edit {
+            Edit::Road { before, .. } => self.road_graph.restore(&before),
+            Edit::AddTree { id, .. } => {
+                self.trees.remove_tree(id);
+            }
+            Edit::RemoveTree { id, tree, model_id } => self.trees.insert_tree(id, tree, model_id),
+        }
+        true
+    }
+
+    fn redo(&mut self) -> bool {
+        let Some(edit) = self.history.redo() else {
+            return false;
+        };
+        self.publish_edit(&edit, false);
+        match edit {
+            Edit::Road { after, .. } => self.road_graph.restore(&after),
+            Edit::AddTree { id, tree, model_id } => self.trees.insert_tree(id, tree, model_id),
+            Edit::RemoveTree { id, .. } => {
+                self.trees.remove_tree(id);
+            }
+        }
+        true
+    }
+}
+
+impl EventStream for World {
+    fn subscribe(&mut self) -> SubscriberId {
+        self.events.subscribe()
+    }
+
+    fn unsubscribe(&mut self, subscriber: SubscriberId) {
+        self.events.unsubscribe(subscriber)
+    }
+
+    fn drain_events(&mut self, subscriber: SubscriberId) -> Vec<WorldEvent> {
+        self.events.drain(subscriber)
+    }
+}
+
+impl RouteQuery for World {
+    fn find_route(&self, from: NodeId, to: NodeId) -> Option<Route> {
+        self.road_graph.find_route(from, to)
+    }
+
+    fn find_route_between_positions(&self, from: Vec3, to: Vec3) -> Option<Route> {
+        self.road_graph.find_route_between_positions(from, to)
+    }
 }
 
 impl IdGetter for World {
@@ -1,3 +1,4 @@
+use crate::spatial_grid::SpatialGrid;
 use world_api::Tree;
 
 use utils::id::{IdManager, IdMap, SafeMap, TreeId};
@@ -17,6 +18,10 @@ pub type TreeMap = BTreeMap<u128, IdMap<TreeId, Tree, SafeMap>>;
 #[derive(Serialize, Deserialize)]
 pub struct Trees {
     tree_map: TreeMap,
+    /// Used to find the trees close to a position without checking all of them. It is rebuilt
+    /// when the trees are loaded instead of being saved.
+    #[serde(skip)]
+    tree_grid: SpatialGrid<TreeId>,
     id_manager: IdManager<TreeId>,
 }
 
@@ -24,6 +29,7 @@ impl Default for Trees {
     fn default() -> Self {
         Self {
             tree_map: BTreeMap::new(),
+            tree_grid: SpatialGrid::default(),
             id_manager: IdManager::new(),
         }
     }
@@ -31,17 +37,13 @@ impl Default for Trees {
 
 impl Trees {
     pub fn get_tree_from_pos(&self, pos: Vec3) -> Option<TreeId> {
-        for model_map in self.tree_map.values() {
-            for (id, tree) in model_map.iter() {
-                if (tree.pos() - pos).length() < TREE_RADIUS {
-                    return Some(id);
-                }
-            }
-        }
-        None
+        self.tree_grid
+            .query_point(pos)
+            .into_iter()
+            .find(|id| (self.get_tree(id).pos() - pos).length() < TREE_RADIUS)
     }
 
-    fn get_tree(&self, id: &TreeId) -> &Tree {
+    pub fn get_tree(&self, id: &TreeId) -> &Tree {
         for model_map in self.tree_map.values() {
             if let Some(tree) = model_map.get(*id) {
                 return tree;
@@ -49,12 +51,22 @@ impl Trees {
         }
         panic!("treeid should be in tree map");
     }
-}
 
-impl crate::TreeManipulator for Trees {
-    fn add_tree(&mut self, tree: Tree, model_id: u128) -> TreeId {
-        let tree_id = self.id_manager.gen();
+    /// Rebuilds the spatial grid from scratch. Used when the trees have been loaded, since the
+    /// grid is not saved.
+    pub fn rebuild_grid(&mut self) {
+        self.tree_grid = SpatialGrid::default();
+        for model_map in self.tree_map.values() {
+            for (id, tree) in model_map.iter() {
+                self.tree_grid.insert_around(id, tree.pos(), TREE_RADIUS);
+            }
+        }
+    }
 
+    /// Inserts a tree under an id that has already been generated. Used to restore removed trees.
+    pub fn insert_tree(&mut self, tree_id: TreeId, tree: Tree, model_id: u128) {
+        self.tree_grid
+            .insert_around(tree_id, tree.pos(), TREE_RADIUS);
         match self.tree_map.get_mut(&model_id) {
             Some(model_map) => {
                 model_map.insert(tree_id, tree);
@@ -66,10 +78,18 @@ impl crate::TreeManipulator for Trees {
                 self.tree_map.insert(model_id, new_model_map);
             }
         };
-        return tree_id;
+    }
+}
+
+impl crate::TreeManipulator for Trees {
+    fn add_tree(&mut self, tree: Tree, model_id: u128) -> TreeId {
+        let tree_id = self.id_manager.gen();
+        self.insert_tree(tree_id, tree, model_id);
+        tree_id
     }
 
     fn remove_tree(&mut self, tree_id: TreeId) -> u128 {
+        self.tree_grid.remove(tree_id);
         for (model_id, model_map) in self.tree_map.iter_mut() {
             if model_map.remove(tree_id).is_some() {
                 return *model_id;
@@ -81,9 +101,15 @@ impl crate::TreeManipulator for Trees {
         panic!("Tree id did not exists in tree map, when bulldozing");
     }
 
-    // fn get_trees(&self) -> &TreeMap {
-    //     &self.tree_map
-    // }
+    fn get_trees(&self) -> Vec<(u128, Vec<(TreeId, Tree)>)> {
+        self.tree_map
+            .iter()
+            .map(|(model_id, model_map)| {
+                let trees = model_map.iter().map(|(id, tree)| (id, *tree)).collect();
+                (*model_id, trees)
+            })
+            .collect()
+    }
 
     fn get_tree_pos(&self, id: TreeId) -> Vec3 {
         self.get_tree(&id).pos()
@@ -1,5 +1,13 @@
+mod collision;
 mod graph;
+mod lane_graph;
 mod node;
+mod routing;
 mod segment;
+mod stats;
+mod super_node;
+mod validation;
 
-pub use graph::RoadGraph;
+pub use graph::{GraphSnapshot, RoadGraph};
+pub use super_node::SuperNodes;
+pub use validation::{GraphViolation, ModeViolation};
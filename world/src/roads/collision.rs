@@ -0,0 +1,267 @@
+use super::RoadGraph;
+
+use world_api::{LNodeBuilderType, LRoadBuilder, SegmentType};
+
+use curves::CurveShared;
+use utils::id::{NodeId, SegmentId};
+
+use glam::*;
+
+/// Roads are allowed to overlap by this many meters. This absorbs the error of approximating the
+/// curves by their spines, and allows roads to be built right next to each other.
+const COLLISION_TOLERANCE: f32 = 0.5;
+
+/// The widest that a segment of the given type gets.
+fn max_width(segment_type: SegmentType) -> f32 {
+    segment_type
+        .from_type()
+        .compute_width()
+        .max(segment_type.to_type().compute_width())
+}
+
+/// Returns the shortest distance in the ground plane between the line segments a0-a1 and b0-b1,
+/// together with the difference in height between the two line segments where they are closest.
+fn line_segment_distance(a0: Vec3, a1: Vec3, b0: Vec3, b1: Vec3) -> (f32, f32) {
+    let height_gap = |t: f32, u: f32| (a0.lerp(a1, t).y - b0.lerp(b1, u).y).abs();
+    let (pa0, pa1, pb0, pb1) = (a0.xz(), a1.xz(), b0.xz(), b1.xz());
+    let (da, db) = (pa1 - pa0, pb1 - pb0);
+    let denom = da.perp_dot(db);
+    if denom != 0.0 {
+        let t = (pb0 - pa0).perp_dot(db) / denom;
+        let u = (pb0 - pa0).perp_dot(da) / denom;
+        if (0.0..=1.0).contains(&t) && (0.0..=1.0).contains(&u) {
+            return (0.0, height_gap(t, u));
+        }
+    }
+    // Returns the distance from p to the line segment s0-s1, and how far along s0-s1 it is closest.
+    let point_distance = |p: Vec2, s0: Vec2, s1: Vec2| {
+        let d = s1 - s0;
+        let t = if d.length_squared() == 0.0 {
+            0.0
+        } else {
+            ((p - s0).dot(d) / d.length_squared()).clamp(0.0, 1.0)
+        };
+        ((s0 + d * t - p).length(), t)
+    };
+    let (d0, u0) = point_distance(pa0, pb0, pb1);
+    let (d1, u1) = point_distance(pa1, pb0, pb1);
+    let (d2, t2) = point_distance(pb0, pa0, pa1);
+    let (d3, t3) = point_distance(pb1, pa0, pa1);
+    [(d0, 0.0, u0), (d1, 1.0, u1), (d2, t2, 0.0), (d3, t3, 1.0)]
+        .into_iter()
+        .min_by(|(a, _, _), (b, _, _)| a.total_cmp(b))
+        .map(|(distance, t, u)| (distance, height_gap(t, u)))
+        .unwrap()
+}
+
+impl RoadGraph {
+    /// Returns the existing segments that the given road would overlap if it was added, ordered by
+    /// their id. Two segments overlap if their spines come closer than the sum of half their
+    /// widths in the ground plane, unless one passes over the other with at least the minimum
+    /// clearance between them, see {`RoadGraph::get_min_clearance`}. A segment attached to a node
+    /// that the road snaps to always touches the road at that node, so around such nodes these
+    /// segments are not checked.
+    pub fn find_collisions(&self, road: &LRoadBuilder) -> Vec<SegmentId> {
+        let snapped_nodes: Vec<(NodeId, Vec3)> = road
+            .get_nodes()
+            .iter()
+            .filter_map(|node| match node {
+                LNodeBuilderType::Old(snap) => {
+                    self.get_node_loc(snap.id()).map(|loc| (snap.id(), loc.pos))
+                }
+                LNodeBuilderType::New(_) => None,
+            })
+            .collect();
+
+        let mut collisions = vec![];
+        for segment_builder in road.get_segments() {
+            let width = max_width(segment_builder.segment_type());
+            let spine = segment_builder.get_spine();
+            let (min, max) = spine.iter().fold(
+                (Vec3::splat(f32::MAX), Vec3::splat(f32::MIN)),
+                |(min, max), loc| (min.min(loc.pos), max.max(loc.pos)),
+            );
+
+            for segment_id in
+                self.query_segments(min - Vec3::splat(width), max + Vec3::splat(width))
+            {
+                if collisions.contains(&segment_id) {
+                    continue;
+                }
+                let segment = self.get_lsegment(segment_id);
+                let other_width = max_width(segment.segment_type());
+                let min_dist = (width + other_width) / 2.0 - COLLISION_TOLERANCE;
+
+                // The nodes that this segment shares with the road, and the radius around them
+                // within which the two may touch.
+                let shared: Vec<Vec3> = snapped_nodes
+                    .iter()
+                    .filter(|(id, _)| self.get_segment_nodes(segment_id).contains(id))
+                    .map(|(_, pos)| *pos)
+                    .collect();
+                let ignore_radius = width + other_width;
+                let is_ignored =
+                    |p: Vec3| shared.iter().any(|pos| (p - *pos).length() < ignore_radius);
+
+                let other_spine = segment.curve().get_spine();
+                let overlaps = spine
+                    .windows(2)
+                    .filter(|a| !is_ignored(a[0].pos) && !is_ignored(a[1].pos))
+                    .any(|a| {
+                        other_spine
+                            .windows(2)
+                            .filter(|b| !is_ignored(b[0].pos) && !is_ignored(b[1].pos))
+                            .any(|b| {
+                                let (distance, height_gap) =
+                                    line_segment_distance(a[0].pos, a[1].pos, b[0].pos, b[1].pos);
+                                distance < min_dist && height_gap < self.min_clearance
+                            })
+                    });
+                if overlaps {
+                    collisions.push(segment_id);
+                }
+            }
+        }
+        collisions.sort_unstable();
+        collisions
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::line_segment_distance;
+    use crate::test_utils::{add_straight_road, straight, test_node_type, try_add_road};
+    use crate::World;
+
+    use world_api::{LNodeBuilderType, LRoadBuilder, LSegmentBuilder, RoadManipulator, RoadQuery};
+
+    use curves::CurveShared;
+    use utils::consts::MIN_CLEARANCE;
+    use utils::id::SegmentId;
+
+    use glam::Vec3;
+
+    /// Returns a bridge that crosses the road from (-50, 0, 0) to (50, 0, 0) in the middle, at the
+    /// given height.
+    fn crossing_bridge(height: f32) -> LRoadBuilder {
+        let node_type = test_node_type();
+        let curve = straight(Vec3::new(0.0, height, -50.0), Vec3::new(0.0, height, 50.0)).remove(0);
+        let nodes = vec![
+            LNodeBuilderType::new(curve.first(), node_type),
+            LNodeBuilderType::new(curve.last(), node_type),
+        ];
+        let mut segment = LSegmentBuilder::new(node_type, curve);
+        segment.set_bridge(true);
+        LRoadBuilder::new(nodes, vec![segment], false)
+    }
+
+    /// Returns a world with a single road from (-50, 0, 0) to (50, 0, 0), and its segment.
+    fn crossed_world() -> (World, SegmentId) {
+        let mut world = World::new();
+        let segment = add_straight_road(
+            &mut world,
+            Vec3::new(-50.0, 0.0, 0.0),
+            Vec3::new(50.0, 0.0, 0.0),
+        );
+        (world, segment)
+    }
+
+    #[test]
+    fn distance_of_crossing_lines() {
+        let (distance, height_gap) = line_segment_distance(
+            Vec3::new(-1.0, 0.0, 0.0),
+            Vec3::new(1.0, 0.0, 0.0),
+            Vec3::new(0.0, 2.0, -1.0),
+            Vec3::new(0.0, 6.0, 1.0),
+        );
+        assert_eq!(distance, 0.0);
+        assert!((height_gap - 4.0).abs() < 1e-5);
+    }
+
+    #[test]
+    fn distance_of_separate_lines() {
+        let (distance, height_gap) = line_segment_distance(
+            Vec3::new(0.0, 0.0, 0.0),
+            Vec3::new(10.0, 0.0, 0.0),
+            Vec3::new(0.0, 3.0, 4.0),
+            Vec3::new(10.0, 3.0, 4.0),
+        );
+        assert!((distance - 4.0).abs() < 1e-5);
+        assert!((height_gap - 3.0).abs() < 1e-5);
+
+        // The closest points are the end of one line and the middle of the other.
+        let (distance, height_gap) = line_segment_distance(
+            Vec3::new(0.0, 0.0, 0.0),
+            Vec3::new(10.0, 0.0, 0.0),
+            Vec3::new(5.0, 1.0, 2.0),
+            Vec3::new(5.0, 1.0, 8.0),
+        );
+        assert!((distance - 2.0).abs() < 1e-5);
+        assert!((height_gap - 1.0).abs() < 1e-5);
+    }
+
+    #[test]
+    fn crossing_below_clearance_collides() {
+        let (mut world, road) = crossed_world();
+        assert_eq!(world.get_road_collisions(&crossing_bridge(2.0)), vec![road]);
+        assert!(try_add_road(
+            &mut world,
+            straight(Vec3::new(0.0, 2.0, -50.0), Vec3::new(0.0, 2.0, 50.0)),
+            true
+        )
+        .is_none());
+    }
+
+    #[test]
+    fn crossing_above_clearance_passes() {
+        let (mut world, _) = crossed_world();
+        assert!(world.get_road_collisions(&crossing_bridge(8.0)).is_empty());
+        assert!(try_add_road(
+            &mut world,
+            straight(Vec3::new(0.0, 8.0, -50.0), Vec3::new(0.0, 8.0, 50.0)),
+            true
+        )
+        .is_some());
+        assert!(world.validate().is_empty());
+    }
+
+    #[test]
+    fn crossing_at_clearance_passes() {
+        let (mut world, road) = crossed_world();
+        let bridge = crossing_bridge(4.0);
+
+        // Fitting the heights of the bridge is not exact, so the gap is measured where the spines
+        // cross rather than assumed to be 4 meters.
+        let bridge_spine = bridge.get_segments()[0].get_spine();
+        let road_spine = world.get_segment(road).unwrap().curve().get_spine().clone();
+        let gap = bridge_spine
+            .windows(2)
+            .flat_map(|a| {
+                road_spine
+                    .windows(2)
+                    .map(move |b| line_segment_distance(a[0].pos, a[1].pos, b[0].pos, b[1].pos))
+            })
+            .filter(|(distance, _)| *distance == 0.0)
+            .map(|(_, height_gap)| height_gap)
+            .next()
+            .unwrap();
+        assert!((gap - 4.0).abs() < 0.01);
+
+        assert!(world.set_min_clearance(gap));
+        assert!(world.get_road_collisions(&bridge).is_empty());
+        assert!(world.set_min_clearance(gap.next_up()));
+        assert_eq!(world.get_road_collisions(&bridge), vec![road]);
+    }
+
+    #[test]
+    fn invalid_clearances_are_rejected() {
+        let (mut world, road) = crossed_world();
+        for clearance in [f32::NAN, f32::INFINITY, f32::NEG_INFINITY, -1.0] {
+            assert!(!world.set_min_clearance(clearance));
+            assert_eq!(world.get_min_clearance(), MIN_CLEARANCE);
+            // Collisions are still found, both in plan and for a bridge below the clearance.
+            assert_eq!(world.get_road_collisions(&crossing_bridge(0.0)), vec![road]);
+            assert_eq!(world.get_road_collisions(&crossing_bridge(2.0)), vec![road]);
+        }
+    }
+}
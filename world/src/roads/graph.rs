@@ -1,8 +1,17 @@
+use super::lane_graph::LaneGraph;
 use super::node::LNode;
 use super::segment::LSegment;
+use crate::spatial_grid::SpatialGrid;
 
-use world_api::{LNodeBuilderType, LRoadBuilder, LaneMapConfig, NodeType, Side, SnapConfig};
+use world_api::{
+    LNodeBuilder, LNodeBuilderType, LRoadBuilder, LSegmentBuilder, LaneId, LaneMapConfig,
+    LaneMapEntry, NetworkStats, NodeType, NodeView, RoadGenErr, SegmentType, SegmentView, Side,
+    SnapConfig, SnapRange,
+};
 
+use curves::{CurveShared, CurveSum};
+
+use utils::consts::{MAX_NO_LANES, MIN_CLEARANCE, ROAD_MIN_LENGTH};
 use utils::id::{IdManager, IdMap, IdSet, NodeId, SegmentId, UnsafeMap};
 use utils::math::Loc;
 
@@ -11,26 +20,93 @@ use serde::{Deserialize, Serialize};
 
 type LeadingPair = (NodeId, SegmentId);
 
+/// Everything the road graph stores about a single node.
+#[derive(Clone)]
+struct NodeEntry {
+    node: LNode,
+    forward_refs: Vec<LeadingPair>,
+    backward_refs: Vec<LeadingPair>,
+}
+
+/// The state of a subset of the nodes and segments of a road graph at some point in time. `None`
+/// means that the node or segment did not exist at that time. Restoring a snapshot puts exactly
+/// these nodes and segments back into the recorded state, which includes their ids and the lane
+/// maps of the nodes.
+#[derive(Clone, Default)]
+pub struct GraphSnapshot {
+    nodes: Vec<(NodeId, Option<NodeEntry>)>,
+    segments: Vec<(SegmentId, Option<LSegment>)>,
+}
+
+impl GraphSnapshot {
+    /// Returns the segments that existed when this snapshot was taken.
+    pub fn present_segments(&self) -> Vec<SegmentId> {
+        self.segments
+            .iter()
+            .filter_map(|(id, s)| s.as_ref().map(|_| *id))
+            .collect()
+    }
+
+    /// Returns the nodes that existed when this snapshot was taken.
+    pub fn present_nodes(&self) -> Vec<NodeId> {
+        self.nodes
+            .iter()
+            .filter_map(|(id, n)| n.as_ref().map(|_| *id))
+            .collect()
+    }
+
+    /// Records every node and segment of `other` that is not part of this snapshot as not
+    /// existing. Used when `self` was taken before, and `other` after, some nodes and segments
+    /// were created.
+    pub fn cover(&mut self, other: &GraphSnapshot) {
+        for (id, _) in other.nodes.iter() {
+            if !self.nodes.iter().any(|(n, _)| n == id) {
+                self.nodes.push((*id, None));
+            }
+        }
+        for (id, _) in other.segments.iter() {
+            if !self.segments.iter().any(|(s, _)| s == id) {
+                self.segments.push((*id, None));
+            }
+        }
+    }
+}
+
 #[derive(Serialize, Deserialize)]
 pub struct RoadGraph {
-    node_map: IdMap<NodeId, LNode, UnsafeMap>,
-    segment_map: IdMap<SegmentId, LSegment, UnsafeMap>,
+    pub(super) node_map: IdMap<NodeId, LNode, UnsafeMap>,
+    pub(super) segment_map: IdMap<SegmentId, LSegment, UnsafeMap>,
     /// Defines for each node, the set of nodes that are reachable from this node, through exactly
     /// one segment in the direction of the segment.
-    forward_refs: IdMap<NodeId, Vec<LeadingPair>, UnsafeMap>,
+    pub(super) forward_refs: IdMap<NodeId, Vec<LeadingPair>, UnsafeMap>,
     /// Defines for each node, the set of nodes that are reachable from this node, through exactly
     /// one segment in the opposite direction of the segment.
-    backward_refs: IdMap<NodeId, Vec<LeadingPair>, UnsafeMap>,
+    pub(super) backward_refs: IdMap<NodeId, Vec<LeadingPair>, UnsafeMap>,
 
     /// These are basic nodes where the main segment is outgoing and open nodes where the open
     /// side is incoming
-    starting_nodes: IdSet<NodeId>,
+    pub(super) starting_nodes: IdSet<NodeId>,
     /// These are basic nodes where the main segment is incoming and open nodes where the open
     /// side is outgoing
-    ending_nodes: IdSet<NodeId>,
+    pub(super) ending_nodes: IdSet<NodeId>,
+
+    /// Defines how the lanes of the segments connect to each other. It is derived from the rest of
+    /// the graph, so it is not saved but rebuilt when the graph is loaded.
+    #[serde(skip)]
+    lane_graph: LaneGraph,
+
+    /// Used to find the nodes and segments close to a position without checking all of them. Like
+    /// the lane graph, the grids are rebuilt when the graph is loaded instead of being saved.
+    #[serde(skip)]
+    node_grid: SpatialGrid<NodeId>,
+    #[serde(skip)]
+    segment_grid: SpatialGrid<SegmentId>,
 
     node_id_manager: IdManager<NodeId>,
     segment_id_manager: IdManager<SegmentId>,
+
+    /// Segments may only cross each other if the vertical distance between them is at least this.
+    pub(super) min_clearance: f32,
 }
 
 impl Default for RoadGraph {
@@ -49,14 +125,18 @@ impl Default for RoadGraph {
             backward_refs,
             starting_nodes,
             ending_nodes,
+            lane_graph: LaneGraph::default(),
+            node_grid: SpatialGrid::default(),
+            segment_grid: SpatialGrid::default(),
             node_id_manager: IdManager::new(),
             segment_id_manager: IdManager::new(),
+            min_clearance: MIN_CLEARANCE,
         }
     }
 }
 
 impl RoadGraph {
-    fn get_lnode(&self, node: NodeId) -> &LNode {
+    pub(super) fn get_lnode(&self, node: NodeId) -> &LNode {
         self.node_map.get(node)
     }
 
@@ -72,7 +152,7 @@ impl RoadGraph {
         self.segment_map.get_mut(segment)
     }
 
-    pub fn _get_forwards_ref(&self, node: NodeId) -> &Vec<LeadingPair> {
+    pub fn get_forwards_ref(&self, node: NodeId) -> &Vec<LeadingPair> {
         self.forward_refs.get(node)
     }
 
@@ -80,28 +160,215 @@ impl RoadGraph {
         self.backward_refs.get(node)
     }
 
-    fn remove_node(&mut self, node_id: NodeId) {
+    /// Removes every trace of the node from the graph, without touching its segments.
+    fn remove_node_entry(&mut self, node_id: NodeId) {
         self.node_map.remove(node_id);
         self.forward_refs.remove(node_id);
         self.backward_refs.remove(node_id);
+        self.starting_nodes.remove(node_id);
+        self.ending_nodes.remove(node_id);
+        self.node_grid.remove(node_id);
     }
 
-    pub fn get_node_from_pos(&self, pos: Vec3) -> Option<NodeId> {
-        for (id, n) in self.node_map.iter() {
-            if n.contains_pos(pos) {
-                return Some(id);
-            }
+    /// Adds the node to the spatial grid, covering the area where the node can be picked.
+    fn index_node(&mut self, node_id: NodeId) {
+        let node = self.get_lnode(node_id);
+        let (pos, width) = (node.pos(), node.width());
+        self.node_grid.insert_around(node_id, pos, width);
+    }
+
+    /// Adds the segment to the spatial grid, covering the area where the segment can be picked.
+    fn index_segment(&mut self, segment_id: SegmentId) {
+        let segment = self.get_lsegment(segment_id);
+        let segment_type = segment.segment_type();
+        let width = segment_type
+            .from_type()
+            .compute_width()
+            .max(segment_type.to_type().compute_width());
+        let spine = segment.curve().get_spine();
+        let (min, max) = spine.iter().fold(
+            (Vec3::splat(f32::MAX), Vec3::splat(f32::MIN)),
+            |(min, max), loc| (min.min(loc.pos), max.max(loc.pos)),
+        );
+        self.segment_grid.insert(
+            segment_id,
+            min - Vec3::splat(width),
+            max + Vec3::splat(width),
+        );
+    }
+
+    /// Rebuilds the spatial grids from scratch. Used when the road graph has been loaded, since
+    /// the grids are not saved.
+    pub fn rebuild_spatial_grids(&mut self) {
+        self.node_grid = SpatialGrid::default();
+        self.segment_grid = SpatialGrid::default();
+        let nodes: Vec<NodeId> = self.node_map.keys().collect();
+        for id in nodes {
+            self.index_node(id);
+        }
+        let segments: Vec<SegmentId> = self.segment_map.keys().collect();
+        for id in segments {
+            self.index_segment(id);
         }
-        None
+    }
+
+    /// Returns the segments that might overlap the given bounding box.
+    pub fn query_segments(&self, min: Vec3, max: Vec3) -> Vec<SegmentId> {
+        self.segment_grid.query(min, max)
+    }
+
+    pub fn get_node_from_pos(&self, pos: Vec3) -> Option<NodeId> {
+        self.node_grid
+            .query_point(pos)
+            .into_iter()
+            .find(|id| self.get_lnode(*id).contains_pos(pos))
     }
 
     pub fn get_segment_from_pos(&self, pos: Vec3) -> Option<SegmentId> {
-        for (id, s) in self.segment_map.iter() {
-            if s.contains_pos(pos) {
-                return Some(id);
+        self.segment_grid
+            .query_point(pos)
+            .into_iter()
+            .find(|id| self.get_lsegment(*id).contains_pos(pos))
+    }
+
+    pub fn contains_segment(&self, segment: SegmentId) -> bool {
+        self.segment_map.contains_key(segment)
+    }
+
+    /// Returns the nodes that the given segment goes between.
+    pub fn get_segment_nodes(&self, segment: SegmentId) -> [NodeId; 2] {
+        let segment = self.get_lsegment(segment);
+        [segment.get_from_node(), segment.get_to_node()]
+    }
+
+    /// Returns the location of the given node, or {`None`} if the node does not exist.
+    pub fn get_node_loc(&self, node: NodeId) -> Option<Loc> {
+        self.node_map
+            .contains_key(node)
+            .then(|| self.get_lnode(node).loc())
+    }
+
+    /// Returns the {`SnapConfig`} that attaches a road of exactly the type of the given node to
+    /// all of its lanes, if such a config exists.
+    pub fn get_exact_snap_config(&self, node: NodeId, node_type: NodeType) -> Option<SnapConfig> {
+        if !self.node_map.contains_key(node) {
+            return None;
+        }
+        let lnode = self.get_lnode(node);
+        if lnode.node_type() != node_type {
+            return None;
+        }
+        let full_range = SnapRange::new(node_type.no_lanes());
+        lnode
+            .construct_snap_configs(node_type, node)
+            .into_iter()
+            .find(|s| *s.get_snap_range() == full_range)
+    }
+
+    /// Records the current state of the given nodes and segments.
+    pub fn snapshot(&self, nodes: &[NodeId], segments: &[SegmentId]) -> GraphSnapshot {
+        let nodes = nodes
+            .iter()
+            .map(|id| {
+                let entry = self.node_map.contains_key(*id).then(|| NodeEntry {
+                    node: self.get_lnode(*id).clone(),
+                    forward_refs: self.forward_refs.get(*id).clone(),
+                    backward_refs: self.backward_refs.get(*id).clone(),
+                });
+                (*id, entry)
+            })
+            .collect();
+        let segments = segments
+            .iter()
+            .map(|id| {
+                let segment = self
+                    .segment_map
+                    .contains_key(*id)
+                    .then(|| self.get_lsegment(*id).clone());
+                (*id, segment)
+            })
+            .collect();
+        GraphSnapshot { nodes, segments }
+    }
+
+    /// Puts the nodes and segments of the snapshot back into the state they were in when the
+    /// snapshot was taken.
+    pub fn restore(&mut self, snapshot: &GraphSnapshot) {
+        for (id, segment) in snapshot.segments.iter() {
+            if self.segment_map.contains_key(*id) {
+                self.segment_map.remove(*id);
+            }
+            if let Some(segment) = segment {
+                self.segment_map.insert(*id, segment.clone());
+            }
+        }
+
+        for (id, entry) in snapshot.nodes.iter() {
+            if self.node_map.contains_key(*id) {
+                self.remove_node_entry(*id);
+            }
+            self.starting_nodes.remove(*id);
+            self.ending_nodes.remove(*id);
+            if let Some(entry) = entry {
+                self.node_map.insert(*id, entry.node.clone());
+                self.forward_refs.insert(*id, entry.forward_refs.clone());
+                self.backward_refs.insert(*id, entry.backward_refs.clone());
+                self.update_starting_ending(&[*id]);
             }
         }
-        None
+
+        for (id, segment) in snapshot.segments.iter() {
+            if segment.is_none() {
+                self.lane_graph.remove_segment(*id);
+            }
+        }
+        let nodes: Vec<NodeId> = snapshot
+            .nodes
+            .iter()
+            .filter_map(|(id, entry)| entry.as_ref().map(|_| *id))
+            .collect();
+        for (id, segment) in snapshot.segments.iter() {
+            match segment {
+                Some(_) => self.index_segment(*id),
+                None => self.segment_grid.remove(*id),
+            }
+        }
+        for id in nodes.iter() {
+            self.index_node(*id);
+        }
+        self.update_lane_graph(&nodes);
+
+        #[cfg(feature = "validate-graph")]
+        self.assert_valid();
+    }
+
+    /// A segment can be removed if neither of its nodes would be split by removing it.
+    fn can_remove_segment(&self, segment_id: SegmentId) -> bool {
+        let segment = self.get_lsegment(segment_id);
+        self.get_lnode(segment.get_from_node())
+            .can_remove_segment(segment_id)
+            && self
+                .get_lnode(segment.get_to_node())
+                .can_remove_segment(segment_id)
+    }
+
+    /// Returns the nodes at the other end of the segments of the given node, or nothing if the node
+    /// does not exist.
+    pub fn get_neighbour_nodes(&self, node: NodeId) -> Vec<NodeId> {
+        if !self.node_map.contains_key(node) {
+            return vec![];
+        }
+        let mut neighbours: Vec<NodeId> = self
+            .forward_refs
+            .get(node)
+            .iter()
+            .chain(self.backward_refs.get(node).iter())
+            .map(|(other, _)| *other)
+            .filter(|other| *other != node)
+            .collect();
+        neighbours.sort_unstable();
+        neighbours.dedup();
+        neighbours
     }
 
     /// Returns ending segments, and the node they backward_refs to as a LeadingPair.
@@ -116,6 +383,52 @@ impl RoadGraph {
         ending_segments
     }
 
+    /// Computes the successors of each lane of the given segment.
+    fn compute_lane_successors(&self, segment_id: SegmentId) -> Vec<Vec<LaneId>> {
+        let segment = self.get_lsegment(segment_id);
+        let segment_type = segment.segment_type();
+        let to_node = self.get_lnode(segment.get_to_node());
+        (0..segment_type.from_type().no_lanes())
+            .map(|lane| {
+                segment_type
+                    .get_exit_lanes(lane)
+                    .into_iter()
+                    .filter_map(|exit_lane| to_node.get_next_segment_lane(segment_id, exit_lane))
+                    .map(|(next_segment, next_lane)| LaneId::new(next_segment, next_lane))
+                    .collect()
+            })
+            .collect()
+    }
+
+    /// Recomputes the lanes of all segments going into the given nodes, since these are the lanes
+    /// whose successors depend on the configuration of the nodes. Nodes that do not exist are
+    /// ignored.
+    fn update_lane_graph(&mut self, nodes: &[NodeId]) {
+        for node_id in nodes.iter() {
+            if !self.node_map.contains_key(*node_id) {
+                continue;
+            }
+            let incoming: Vec<SegmentId> = self
+                .backward_refs
+                .get(*node_id)
+                .iter()
+                .map(|(_, segment_id)| *segment_id)
+                .collect();
+            for segment_id in incoming {
+                let successors = self.compute_lane_successors(segment_id);
+                self.lane_graph.set_successors(segment_id, successors);
+            }
+        }
+    }
+
+    /// Rebuilds the lane graph from scratch. Used when the road graph has been loaded, since the
+    /// lane graph is not saved.
+    pub fn rebuild_lane_graph(&mut self) {
+        self.lane_graph = LaneGraph::default();
+        let nodes: Vec<NodeId> = self.node_map.keys().collect();
+        self.update_lane_graph(&nodes);
+    }
+
     fn update_starting_ending(&mut self, nodes: &[NodeId]) {
         for id in nodes.iter() {
             let node = self.get_lnode(*id);
@@ -131,14 +444,16 @@ impl RoadGraph {
                 (true, true) => {
                     panic!("A node cannot possibly be both and ending and a starting node")
                 }
-                _ => {}
+                (false, false) => {
+                    self.starting_nodes.remove(*id);
+                    self.ending_nodes.remove(*id);
+                }
             }
         }
     }
-}
 
-impl crate::RoadManipulator for RoadGraph {
-    fn add_road(
+    /// Adds the road without checking if it collides with any existing segments.
+    pub fn add_road_unchecked(
         &mut self,
         road: LRoadBuilder,
         sel_node_type: NodeType,
@@ -230,6 +545,12 @@ impl crate::RoadManipulator for RoadGraph {
         // update starting and endings nodes
         self.update_starting_ending(&node_ids);
 
+        // every new segment goes into one of the nodes of the road
+        self.update_lane_graph(&node_ids);
+
+        node_ids.iter().for_each(|id| self.index_node(*id));
+        segment_ids.iter().for_each(|id| self.index_segment(*id));
+
         // compute the new node that the tool can snap to, if any.
         let new_snap_id = node_ids[if reverse { 0 } else { node_ids.len() - 1 }];
         let new_snap = self
@@ -243,16 +564,33 @@ impl crate::RoadManipulator for RoadGraph {
             assert_eq!(self.node_map.len(), self.forward_refs.len());
             assert_eq!(self.node_map.len(), self.backward_refs.len());
         }
+        #[cfg(feature = "validate-graph")]
+        self.assert_valid();
 
         (new_snap, segment_ids)
     }
+}
+
+impl crate::RoadManipulator for RoadGraph {
+    fn add_road(
+        &mut self,
+        road: LRoadBuilder,
+        sel_node_type: NodeType,
+    ) -> Result<(Option<SnapConfig>, Vec<SegmentId>), RoadGenErr> {
+        let collisions = self.find_collisions(&road);
+        if !collisions.is_empty() {
+            return Err(RoadGenErr::Collision(collisions));
+        }
+        Ok(self.add_road_unchecked(road, sel_node_type))
+    }
+
+    fn get_road_collisions(&self, road: &LRoadBuilder) -> Vec<SegmentId> {
+        self.find_collisions(road)
+    }
 
     fn remove_segment(&mut self, segment_id: SegmentId) -> bool {
         // check if deletion is valid
-        let segment = self.get_lsegment(segment_id);
-        let from_node = self.get_lnode(segment.get_from_node());
-        let to_node = self.get_lnode(segment.get_to_node());
-        if !from_node.can_remove_segment(segment_id) || !to_node.can_remove_segment(segment_id) {
+        if !self.can_remove_segment(segment_id) {
             dbg!("Cannot bulldoze segment");
             return false;
         }
@@ -272,29 +610,248 @@ impl crate::RoadManipulator for RoadGraph {
             .get_lnode_mut(segment.get_from_node())
             .remove_segment(segment_id)
         {
-            self.remove_node(segment.get_from_node());
+            self.remove_node_entry(segment.get_from_node());
             affected_nodes.remove(1);
         }
         if self
             .get_lnode_mut(segment.get_to_node())
             .remove_segment(segment_id)
         {
-            self.remove_node(segment.get_to_node());
+            self.remove_node_entry(segment.get_to_node());
             affected_nodes.remove(0);
         }
 
         // update starting and endings nodes
         self.update_starting_ending(&affected_nodes);
 
+        self.lane_graph.remove_segment(segment_id);
+        self.update_lane_graph(&affected_nodes);
+
+        self.segment_grid.remove(segment_id);
+        affected_nodes.iter().for_each(|id| self.index_node(*id));
+
         #[cfg(debug_assertions)]
         {
             assert_eq!(self.node_map.len(), self.forward_refs.len());
             assert_eq!(self.node_map.len(), self.backward_refs.len());
         }
+        #[cfg(feature = "validate-graph")]
+        self.assert_valid();
+
+        true
+    }
+
+    fn remove_node(&mut self, node_id: NodeId) -> Option<Vec<SegmentId>> {
+        if !self.node_map.contains_key(node_id) {
+            return None;
+        }
+        let mut remaining = self.get_node_segments(node_id);
+        let mut nodes = self.get_neighbour_nodes(node_id);
+        nodes.push(node_id);
+        let before = self.snapshot(&nodes, &remaining);
+
+        // The node itself is removed in the end, so any order that keeps it from being split
+        // along the way works. Segments that would split a neighbour are postponed, in case
+        // removing the other segments first makes them removable.
+        let mut removed = Vec::with_capacity(remaining.len());
+        while !remaining.is_empty() {
+            let Some(i) = remaining.iter().position(|s| self.can_remove_segment(*s)) else {
+                self.restore(&before);
+                return None;
+            };
+            let segment_id = remaining.remove(i);
+            self.remove_segment(segment_id);
+            removed.push(segment_id);
+        }
+
+        debug_assert!(!self.node_map.contains_key(node_id));
+
+        Some(removed)
+    }
+
+    fn get_node_segments(&self, node_id: NodeId) -> Vec<SegmentId> {
+        if !self.node_map.contains_key(node_id) {
+            return vec![];
+        }
+        self.get_lnode(node_id)
+            .get_segments()
+            .into_iter()
+            .map(|(segment, _, _)| segment)
+            .collect()
+    }
 
+    fn get_min_clearance(&self) -> f32 {
+        self.min_clearance
+    }
+
+    fn set_min_clearance(&mut self, clearance: f32) -> bool {
+        if !clearance.is_finite() || clearance < 0.0 {
+            return false;
+        }
+        self.min_clearance = clearance;
         true
     }
 
+    fn is_segment_bridge(&self, id: SegmentId) -> bool {
+        self.get_lsegment(id).is_bridge()
+    }
+
+    fn get_node_grade(&self, node_id: NodeId) -> Option<f32> {
+        if !self.node_map.contains_key(node_id) {
+            return None;
+        }
+        let (segment, side, _) = *self.get_lnode(node_id).get_segments().first()?;
+        let profile = self.get_lsegment(segment).curve().get_profile();
+        Some(match side {
+            Side::In => profile.last_grade(),
+            Side::Out => profile.first_grade(),
+        })
+    }
+
+    fn split_segment(
+        &mut self,
+        segment_id: SegmentId,
+        length: f32,
+    ) -> Option<(NodeId, [SegmentId; 2])> {
+        if !self.segment_map.contains_key(segment_id) {
+            return None;
+        }
+        let segment = self.get_lsegment(segment_id);
+        let segment_type = segment.segment_type();
+        let bridge = segment.is_bridge();
+        let curve = segment.curve();
+        if segment_type.is_transition()
+            || !length.is_finite()
+            || !(ROAD_MIN_LENGTH..=curve.get_length() - ROAD_MIN_LENGTH).contains(&length)
+        {
+            return None;
+        }
+        let node_type = segment_type.from_type();
+        let [from_node, to_node] = self.get_segment_nodes(segment_id);
+        let (first_curve, last_curve) = curve.split(length);
+
+        let node_id = self.node_id_manager.gen();
+        let first_id = self.segment_id_manager.gen();
+        let last_id = self.segment_id_manager.gen();
+
+        let node_builder = LNodeBuilder::new(first_curve.last(), node_type);
+        let lane_map_config = LaneMapConfig::Sym {
+            incoming: first_id,
+            outgoing: last_id,
+        };
+        self.node_map
+            .insert(node_id, LNode::from_builder(node_builder, lane_map_config));
+        self.forward_refs.insert(node_id, vec![(to_node, last_id)]);
+        self.backward_refs
+            .insert(node_id, vec![(from_node, first_id)]);
+
+        let mut first = LSegmentBuilder::new(node_type, first_curve);
+        let mut last = LSegmentBuilder::new(node_type, last_curve);
+        first.set_bridge(bridge);
+        last.set_bridge(bridge);
+        self.segment_map
+            .insert(first_id, LSegment::from_builder(first, from_node, node_id));
+        self.segment_map
+            .insert(last_id, LSegment::from_builder(last, node_id, to_node));
+        self.segment_map.remove(segment_id);
+
+        // the end nodes keep their configuration, only the segment they refer to changes
+        self.get_lnode_mut(from_node)
+            .replace_segment(segment_id, first_id);
+        self.get_lnode_mut(to_node)
+            .replace_segment(segment_id, last_id);
+        for (other, id) in self.forward_refs.get_mut(from_node).iter_mut() {
+            if *id == segment_id {
+                (*other, *id) = (node_id, first_id);
+            }
+        }
+        for (other, id) in self.backward_refs.get_mut(to_node).iter_mut() {
+            if *id == segment_id {
+                (*other, *id) = (node_id, last_id);
+            }
+        }
+
+        let affected_nodes = [from_node, node_id, to_node];
+        self.update_starting_ending(&affected_nodes);
+
+        self.lane_graph.remove_segment(segment_id);
+        self.update_lane_graph(&affected_nodes);
+
+        self.segment_grid.remove(segment_id);
+        self.index_node(node_id);
+        self.index_segment(first_id);
+        self.index_segment(last_id);
+
+        #[cfg(feature = "validate-graph")]
+        self.assert_valid();
+
+        Some((node_id, [first_id, last_id]))
+    }
+
+    fn merge_segments(&mut self, node_id: NodeId) -> Option<SegmentId> {
+        if !self.node_map.contains_key(node_id) {
+            return None;
+        }
+        let (incoming, outgoing) = self.get_lnode(node_id).get_sym_segments()?;
+        let (incoming_segment, outgoing_segment) =
+            (self.get_lsegment(incoming), self.get_lsegment(outgoing));
+        let segment_type = incoming_segment.segment_type();
+        let from_node = incoming_segment.get_from_node();
+        let to_node = outgoing_segment.get_to_node();
+        if segment_type.is_transition()
+            || outgoing_segment.segment_type() != segment_type
+            || outgoing_segment.is_bridge() != incoming_segment.is_bridge()
+            || from_node == to_node
+        {
+            return None;
+        }
+        let bridge = incoming_segment.is_bridge();
+        let curve = incoming_segment.curve().join(outgoing_segment.curve());
+
+        let segment_id = self.segment_id_manager.gen();
+        let mut segment = LSegmentBuilder::new(segment_type.from_type(), curve);
+        segment.set_bridge(bridge);
+        self.segment_map.insert(
+            segment_id,
+            LSegment::from_builder(segment, from_node, to_node),
+        );
+        self.segment_map.remove(incoming);
+        self.segment_map.remove(outgoing);
+        self.remove_node_entry(node_id);
+
+        // the end nodes keep their configuration, only the segment they refer to changes
+        self.get_lnode_mut(from_node)
+            .replace_segment(incoming, segment_id);
+        self.get_lnode_mut(to_node)
+            .replace_segment(outgoing, segment_id);
+        for (other, id) in self.forward_refs.get_mut(from_node).iter_mut() {
+            if *id == incoming {
+                (*other, *id) = (to_node, segment_id);
+            }
+        }
+        for (other, id) in self.backward_refs.get_mut(to_node).iter_mut() {
+            if *id == outgoing {
+                (*other, *id) = (from_node, segment_id);
+            }
+        }
+
+        let affected_nodes = [from_node, to_node];
+        self.update_starting_ending(&affected_nodes);
+
+        self.lane_graph.remove_segment(incoming);
+        self.lane_graph.remove_segment(outgoing);
+        self.update_lane_graph(&affected_nodes);
+
+        self.segment_grid.remove(incoming);
+        self.segment_grid.remove(outgoing);
+        self.index_segment(segment_id);
+
+        #[cfg(feature = "validate-graph")]
+        self.assert_valid();
+
+        Some(segment_id)
+    }
+
     fn get_possible_snap_nodes(
         &self,
         side: Option<Side>,
@@ -325,11 +882,14 @@ impl crate::RoadManipulator for RoadGraph {
     ) -> Option<(NodeId, Vec<SnapConfig>)> {
         // TODO match all nodes in range and combine the snap configs generated by all of them
         let mut closest_node = None;
-        for (id, n) in self.node_map.iter() {
+        // No node can be snapped to from further away than this.
+        let max_dist = (MAX_NO_LANES + node_type.no_lanes()) as f32 * node_type.lane_width_f32();
+        for id in self.node_grid.query_around(ground_pos, max_dist) {
+            let n = self.get_lnode(id);
             if !n.can_add_some_segment() {
                 continue;
             }
-            let dist = (n.pos() - ground_pos).length();
+            let dist = (n.pos() - ground_pos).xz().length();
             if let Some((_, old_dist)) = closest_node {
                 if old_dist < dist {
                     continue;
@@ -344,14 +904,32 @@ impl crate::RoadManipulator for RoadGraph {
             let mut snap_configs = n.construct_snap_configs(node_type, id);
             snap_configs.sort_by(|a, b| {
                 (a.pos() - ground_pos)
+                    .xz()
                     .length()
-                    .partial_cmp(&(b.pos() - ground_pos).length())
+                    .partial_cmp(&(b.pos() - ground_pos).xz().length())
                     .unwrap()
             });
             (id, snap_configs)
         })
     }
 
+    fn get_segment_ids(&self) -> Vec<SegmentId> {
+        self.segment_map.keys().collect()
+    }
+
+    fn get_lanes(&self) -> Vec<LaneId> {
+        self.lane_graph.get_lanes()
+    }
+
+    fn get_lane_successors(&self, lane: LaneId) -> &[LaneId] {
+        self.lane_graph.get_successors(lane)
+    }
+
+    fn get_segment_curve(&self, id: SegmentId) -> (&CurveSum, SegmentType) {
+        let segment = self.get_lsegment(id);
+        (segment.curve(), segment.segment_type())
+    }
+
     fn debug_node(&self, id: NodeId) {
         // let mut closest_node = None;
         // for (id, n) in self.node_map.iter() {
@@ -377,3 +955,294 @@ impl crate::RoadManipulator for RoadGraph {
         dbg!(id);
     }
 }
+
+fn node_view(id: NodeId, node: &LNode) -> NodeView {
+    NodeView::new(id, node.loc(), node.node_type(), node.mode())
+}
+
+fn segment_view(id: SegmentId, segment: &LSegment) -> SegmentView<'_> {
+    SegmentView::new(
+        id,
+        segment.curve(),
+        segment.segment_type(),
+        [segment.get_from_node(), segment.get_to_node()],
+        segment.is_bridge(),
+    )
+}
+
+impl crate::RoadQuery for RoadGraph {
+    fn nodes(&self) -> impl Iterator<Item = NodeView> + '_ {
+        self.node_map.iter().map(|(id, node)| node_view(id, node))
+    }
+
+    fn segments(&self) -> impl Iterator<Item = SegmentView<'_>> {
+        self.segment_map
+            .iter()
+            .map(|(id, segment)| segment_view(id, segment))
+    }
+
+    fn get_node(&self, id: NodeId) -> Option<NodeView> {
+        self.node_map
+            .contains_key(id)
+            .then(|| node_view(id, self.get_lnode(id)))
+    }
+
+    fn get_segment(&self, id: SegmentId) -> Option<SegmentView<'_>> {
+        self.segment_map
+            .contains_key(id)
+            .then(|| segment_view(id, self.get_lsegment(id)))
+    }
+
+    fn get_node_lane_map(&self, id: NodeId) -> Option<Vec<LaneMapEntry>> {
+        self.node_map
+            .contains_key(id)
+            .then(|| self.get_lnode(id).get_lane_map())
+    }
+
+    fn compute_stats(&self) -> NetworkStats {
+        self.compute_stats()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::test_utils::{add_road, add_straight_road, arc};
+    use crate::World;
+
+    use world_api::{
+        HistoryManipulator, LNodeBuilderType, LRoadBuilder, LSegmentBuilder, LaneWidth, NodeType,
+        RoadManipulator, RoadQuery, Side,
+    };
+
+    use curves::{Curve, CurveShared, CurveSum, Straight};
+    use utils::id::{NodeId, SegmentId};
+    use utils::math::{DirXZ, Loc};
+
+    use glam::Vec3;
+
+    fn segment_ids(world: &World) -> Vec<SegmentId> {
+        let mut ids: Vec<SegmentId> = world.segments().map(|s| s.id()).collect();
+        ids.sort();
+        ids
+    }
+
+    fn node_ids(world: &World) -> Vec<NodeId> {
+        let mut ids: Vec<NodeId> = world.nodes().map(|n| n.id()).collect();
+        ids.sort();
+        ids
+    }
+
+    fn total_length(world: &World) -> f32 {
+        world.segments().map(|s| s.curve().get_length()).sum()
+    }
+
+    /// A straight road followed by a curved one, such that both kinds of curves are split.
+    fn test_world() -> World {
+        let mut world = World::new();
+        let a = Vec3::ZERO;
+        let b = Vec3::new(100.0, 0.0, 0.0);
+        add_straight_road(&mut world, a, b);
+        add_road(
+            &mut world,
+            arc(
+                Loc::new(b, DirXZ::from(Vec3::X)),
+                Vec3::new(160.0, 0.0, -60.0),
+            ),
+        );
+        world
+    }
+
+    #[test]
+    fn split_merge_round_trip() {
+        let mut world = test_world();
+        assert!(!world.redo());
+        for segment in segment_ids(&world) {
+            let (segments, nodes) = (segment_ids(&world), node_ids(&world));
+            let length = total_length(&world);
+
+            let (node, [first, last]) = world.split_segment(segment, 20.0).unwrap();
+            assert!(world.validate().is_empty());
+            assert!((total_length(&world) - length).abs() < 0.01);
+            let split_segments = segment_ids(&world);
+            assert!(!split_segments.contains(&segment));
+            assert!(split_segments.contains(&first) && split_segments.contains(&last));
+            // The split point is found on the sampled spine, so it is only accurate to centimeters.
+            let first_length = world.get_segment(first).unwrap().curve().get_length();
+            assert!((first_length - 20.0).abs() < 0.05);
+
+            let merged = world.merge_segments(node).unwrap();
+            assert!(world.validate().is_empty());
+            assert!((total_length(&world) - length).abs() < 0.01);
+            assert_eq!(node_ids(&world), nodes);
+            let merged_segments = segment_ids(&world);
+            assert_eq!(merged_segments.len(), segments.len());
+            assert!(merged_segments.contains(&merged));
+
+            // Undoing the merge and the split restores the exact ids, and redoing them does too.
+            assert!(world.undo());
+            assert_eq!(segment_ids(&world), split_segments);
+            assert!(world.undo());
+            assert_eq!(segment_ids(&world), segments);
+            assert_eq!(node_ids(&world), nodes);
+            assert!(world.validate().is_empty());
+            assert!(world.redo());
+            assert_eq!(segment_ids(&world), split_segments);
+            assert!(world.redo());
+            assert_eq!(segment_ids(&world), merged_segments);
+            assert!(world.validate().is_empty());
+            assert!((total_length(&world) - length).abs() < 0.01);
+
+            // Leave the world as it was, such that the next segment is split in the original world.
+            assert!(world.undo());
+            assert!(world.undo());
+        }
+    }
+
+    #[test]
+    fn split_rejects_short_parts() {
+        let mut world = test_world();
+        let segment = segment_ids(&world)[0];
+        assert!(world.split_segment(segment, 0.1).is_none());
+        assert!(world.split_segment(segment, 99.9).is_none());
+        assert!(world.split_segment(segment, 150.0).is_none());
+        assert!(world.split_segment(segment, f32::NAN).is_none());
+        assert!(world.split_segment(segment, f32::INFINITY).is_none());
+        assert!(world.split_segment(segment, f32::NEG_INFINITY).is_none());
+        assert_eq!(segment_ids(&world).len(), 2);
+    }
+
+    #[test]
+    fn merge_rejects_non_symmetric_nodes() {
+        let mut world = test_world();
+        let ends: Vec<NodeId> = world
+            .nodes()
+            .filter(|n| world.get_node_segments(n.id()).len() == 1)
+            .map(|n| n.id())
+            .collect();
+        for node in ends {
+            assert!(world.merge_segments(node).is_none());
+        }
+        assert!(world.validate().is_empty());
+    }
+
+    #[test]
+    fn remove_sym_node_of_chain() {
+        let mut world = World::new();
+        let points = [0.0, 100.0, 200.0, 300.0].map(|x| Vec3::new(x, 0.0, 0.0));
+        let segments: Vec<SegmentId> = points
+            .windows(2)
+            .map(|p| add_straight_road(&mut world, p[0], p[1]))
+            .collect();
+        let middle = world.get_segment(segments[0]).unwrap().to_node();
+        assert_eq!(world.get_node_segments(middle).len(), 2);
+
+        let mut removed = world.remove_node(middle).unwrap();
+        removed.sort();
+        assert_eq!(removed, segments[..2]);
+        assert!(world.validate().is_empty());
+        assert_eq!(segment_ids(&world), vec![segments[2]]);
+        // The node at the start of the chain is left without segments, so it goes too.
+        assert_eq!(world.nodes().count(), 2);
+
+        assert!(world.undo());
+        assert_eq!(segment_ids(&world), segments);
+        assert!(world.validate().is_empty());
+    }
+
+    /// Adds a three lane road through the given points, and returns its segments.
+    fn add_wide_road(world: &mut World, points: &[Vec3]) -> Vec<SegmentId> {
+        let wide = NodeType::new(LaneWidth::Standard, 3);
+        let curves: Vec<CurveSum> = points
+            .windows(2)
+            .map(|p| Curve::<Straight>::from_free(p[0], p[1]).0.into())
+            .collect();
+        let mut nodes = vec![LNodeBuilderType::new(curves[0].first(), wide)];
+        nodes.extend(
+            curves
+                .iter()
+                .map(|curve| LNodeBuilderType::new(curve.last(), wide)),
+        );
+        let segments = curves
+            .into_iter()
+            .map(|curve| LSegmentBuilder::new(wide, curve))
+            .collect();
+        let (_, segments) = world
+            .add_road(LRoadBuilder::new(nodes, segments, false), wide)
+            .unwrap();
+        segments
+    }
+
+    /// Adds a one lane road leaving each of the given lanes of the node at `pos`, and returns
+    /// their segments.
+    fn add_narrow_branches(world: &mut World, pos: Vec3, lanes: &[i8]) -> Vec<SegmentId> {
+        let narrow = NodeType::new(LaneWidth::Standard, 1);
+        let (_, configs) = world.get_snap_configs_closest_node(pos, narrow).unwrap();
+        let mut segments = vec![];
+        for lane in lanes {
+            let snap = configs
+                .iter()
+                .find(|snap| {
+                    let range = snap.get_snap_range();
+                    snap.side() == Side::Out && range.smallest() == *lane && range.len() == 1
+                })
+                .unwrap()
+                .clone();
+            let to = snap.pos() + Vec3::X * 100.0;
+            let first = Loc::new(snap.pos(), snap.dir());
+            let curve: CurveSum = Curve::<Straight>::from_first_locked(first, to).0.into();
+            let last = LNodeBuilderType::new(curve.last(), narrow);
+            let segment = LSegmentBuilder::transition(snap.node_type(), narrow, curve);
+            let nodes = vec![LNodeBuilderType::Old(snap), last];
+            let road = LRoadBuilder::new(nodes, vec![segment], false);
+            let (_, added) = world.add_road(road, narrow).unwrap();
+            segments.extend(added);
+        }
+        segments
+    }
+
+    #[test]
+    fn remove_node_that_would_split_a_neighbour() {
+        let mut world = World::new();
+        // A wide road through a symmetric node, such that removing the node first removes the
+        // segment that enters it before it finds that the other segment cannot be removed.
+        let points = [-100.0, 0.0, 100.0].map(|x| Vec3::new(x, 0.0, 0.0));
+        let wide_segments = add_wide_road(&mut world, &points);
+        let middle_node = world.get_segment(wide_segments[1]).unwrap().from_node();
+        // One lane roads leave the outermost lanes of the wide road, such that the lane between
+        // them is not attached to anything.
+        add_narrow_branches(&mut world, points[2], &[0, 2]);
+        assert!(world.validate().is_empty());
+
+        // Removing the wide road would leave a gap in the lanes of the node it ends at.
+        let (segments, nodes) = (segment_ids(&world), node_ids(&world));
+        let node_segments: Vec<Vec<SegmentId>> =
+            nodes.iter().map(|n| world.get_node_segments(*n)).collect();
+        assert!(world.remove_node(middle_node).is_none());
+        assert_eq!(segment_ids(&world), segments);
+        assert_eq!(node_ids(&world), nodes);
+        for (node, node_segments) in nodes.iter().zip(node_segments) {
+            assert_eq!(world.get_node_segments(*node), node_segments);
+        }
+        assert!(world.validate().is_empty());
+    }
+
+    #[test]
+    fn removing_main_segment_opens_node_with_remaining_lanes() {
+        let mut world = World::new();
+        let points = [Vec3::ZERO, Vec3::new(100.0, 0.0, 0.0)];
+        let wide_segment = add_wide_road(&mut world, &points)[0];
+        let branches = add_narrow_branches(&mut world, points[1], &[1, 2]);
+        let node = world.get_segment(wide_segment).unwrap().to_node();
+
+        // The node loses its leftmost lane, and is left with one lane for each branch.
+        assert!(world.remove_segment(wide_segment));
+        assert!(world.validate().is_empty());
+        let lnode = world.get_node(node).unwrap();
+        assert_eq!(lnode.node_type().no_lanes(), 2);
+        let starts: Vec<Vec3> = branches
+            .iter()
+            .map(|s| world.get_segment(*s).unwrap().curve().first().pos)
+            .collect();
+        assert!(lnode.loc().pos.distance((starts[0] + starts[1]) / 2.0) < 0.01);
+    }
+}
@@ -0,0 +1,49 @@
+use world_api::LaneId;
+
+use utils::id::{IdMap, SegmentId, UnsafeMap};
+
+/// A directed graph where the vertices are the lanes of the segments in a road graph, and the
+/// edges define which lanes a car can continue into at the end of a segment. The lane graph is
+/// derived from the road graph, and it is the responsibility of the road graph to keep it up to
+/// date.
+pub struct LaneGraph {
+    /// For each segment, the successors of each of its lanes, indexed by lane.
+    successors: IdMap<SegmentId, Vec<Vec<LaneId>>, UnsafeMap>,
+}
+
+impl Default for LaneGraph {
+    fn default() -> Self {
+        Self {
+            successors: IdMap::new(),
+        }
+    }
+}
+
+impl LaneGraph {
+    /// Sets the successors of every lane of the given segment, replacing any previous successors.
+    pub fn set_successors(&mut self, segment: SegmentId, successors: Vec<Vec<LaneId>>) {
+        if self.successors.contains_key(segment) {
+            self.successors.remove(segment);
+        }
+        self.successors.insert(segment, successors);
+    }
+
+    pub fn remove_segment(&mut self, segment: SegmentId) {
+        if self.successors.contains_key(segment) {
+            self.successors.remove(segment);
+        }
+    }
+
+    pub fn get_successors(&self, lane: LaneId) -> &[LaneId] {
+        &self.successors.get(lane.segment())[lane.lane() as usize]
+    }
+
+    pub fn get_lanes(&self) -> Vec<LaneId> {
+        self.successors
+            .iter()
+            .flat_map(|(segment, lanes)| {
+                (0..lanes.len()).map(move |lane| LaneId::new(segment, lane as u8))
+            })
+            .collect()
+    }
+}
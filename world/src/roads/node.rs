@@ -1,4 +1,8 @@
-use world_api::{LNodeBuilder, LaneMapConfig, NodeType, Side, SnapConfig, SnapRange};
+use super::validation::ModeViolation;
+
+use world_api::{
+    LNodeBuilder, LaneMapConfig, LaneMapEntry, NodeMode, NodeType, Side, SnapConfig, SnapRange,
+};
 
 use utils::id::{NodeId, SegmentId};
 use utils::math::{DirXZ, Loc};
@@ -97,6 +101,10 @@ impl LNode {
         self.loc
     }
 
+    pub fn node_type(&self) -> NodeType {
+        self.node_type
+    }
+
     /// Returns the number of lanes of this node's type. This is the number of lanes in the main
     /// segment.
     pub fn no_lanes(&self) -> u8 {
@@ -107,12 +115,12 @@ impl LNode {
         self.node_type.lane_width_f32()
     }
 
-    fn width(&self) -> f32 {
+    pub fn width(&self) -> f32 {
         self.lane_width() * self.no_lanes() as f32
     }
 
     pub fn contains_pos(&self, pos: Vec3) -> bool {
-        (self.loc.pos - pos).length() < self.width()
+        (self.loc.pos - pos).xz().length() < self.width()
     }
 
     pub fn is_starting(&self) -> bool {
@@ -133,7 +141,7 @@ impl LNode {
 
     /// Requires that `incoming_segment` is in fact an incoming_segment of this node, and that this
     /// node has an outgoing segment.
-    pub fn _get_next_segment_lane(
+    pub fn get_next_segment_lane(
         &self,
         incoming_segment: SegmentId,
         lane: u8,
@@ -157,14 +165,14 @@ impl LNode {
                 Side::In => {
                     #[cfg(debug_assertions)]
                     assert_eq!(*main_segment, incoming_segment);
-                    attached_segments._get_segment_at_index(lane)
+                    attached_segments.get_segment_at_index(lane)
                 }
                 Side::Out => {
                     #[cfg(debug_assertions)]
                     assert!(attached_segments.contains_segment(incoming_segment));
                     Some((
                         *main_segment,
-                        attached_segments._get_lane_from_segment_and_index(incoming_segment, lane),
+                        attached_segments.get_lane_from_segment_and_index(incoming_segment, lane),
                     ))
                 }
             },
@@ -177,8 +185,7 @@ impl LNode {
     }
 
     /// Returns true if the given segment_id is part of this node.
-    #[cfg(debug_assertions)]
-    fn contains_segment(&self, segment_id: SegmentId) -> bool {
+    pub fn contains_segment(&self, segment_id: SegmentId) -> bool {
         match &self.mode {
             Basic { main_segment, .. } => *main_segment == segment_id,
             Sym { incoming, outgoing } => {
@@ -203,6 +210,163 @@ impl LNode {
         }
     }
 
+    /// Returns the incoming and outgoing segment if this is a symmetric node.
+    pub fn get_sym_segments(&self) -> Option<(SegmentId, SegmentId)> {
+        match &self.mode {
+            Sym { incoming, outgoing } => Some((*incoming, *outgoing)),
+            _ => None,
+        }
+    }
+
+    /// Makes the node refer to `new_id` wherever it referred to `old_id`. The new segment takes the
+    /// place of the old one, so it must attach to the node in the same way.
+    pub fn replace_segment(&mut self, old_id: SegmentId, new_id: SegmentId) {
+        let replace = |id: &mut SegmentId| {
+            if *id == old_id {
+                *id = new_id
+            }
+        };
+        match &mut self.mode {
+            Basic { main_segment, .. } => replace(main_segment),
+            Sym { incoming, outgoing } => {
+                replace(incoming);
+                replace(outgoing);
+            }
+            Asym {
+                main_segment,
+                attached_segments,
+                ..
+            } => {
+                replace(main_segment);
+                attached_segments.replace_segment(old_id, new_id);
+            }
+            Open {
+                attached_segments, ..
+            } => attached_segments.replace_segment(old_id, new_id),
+        }
+    }
+
+    /// Returns every segment of this node, together with the side of the node that it is attached
+    /// to and the type that the segment has at this node.
+    pub fn get_segments(&self) -> Vec<(SegmentId, Side, NodeType)> {
+        match &self.mode {
+            Basic {
+                main_segment,
+                main_side,
+            } => vec![(*main_segment, *main_side, self.node_type)],
+            Sym { incoming, outgoing } => vec![
+                (*incoming, Side::In, self.node_type),
+                (*outgoing, Side::Out, self.node_type),
+            ],
+            Asym {
+                main_segment,
+                main_side,
+                attached_segments,
+            } => {
+                let mut segments = vec![(*main_segment, *main_side, self.node_type)];
+                segments.extend(
+                    attached_segments
+                        .iter()
+                        .map(|s| (s.segment_id(), main_side.switch(), s.node_type())),
+                );
+                segments
+            }
+            Open {
+                open_side,
+                attached_segments,
+            } => attached_segments
+                .iter()
+                .map(|s| (s.segment_id(), open_side.switch(), s.node_type()))
+                .collect(),
+        }
+    }
+
+    pub fn mode(&self) -> NodeMode {
+        match &self.mode {
+            Basic { .. } => NodeMode::Basic,
+            Sym { .. } => NodeMode::Sym,
+            Asym { .. } => NodeMode::Asym,
+            Open { .. } => NodeMode::Open,
+        }
+    }
+
+    /// Returns every segment of this node together with the lanes of this node that it occupies.
+    /// Main segments occupy every lane.
+    pub fn get_lane_map(&self) -> Vec<LaneMapEntry> {
+        let main = |segment_id, side| {
+            LaneMapEntry::new(
+                segment_id,
+                side,
+                self.node_type,
+                SnapRange::new(self.node_type.no_lanes()),
+            )
+        };
+        let attached = |lane_map: &LaneMap, side| {
+            lane_map
+                .iter()
+                .map(|s| {
+                    LaneMapEntry::new(s.segment_id(), side, s.node_type(), s.snap_range().clone())
+                })
+                .collect::<Vec<_>>()
+        };
+        match &self.mode {
+            Basic {
+                main_segment,
+                main_side,
+            } => vec![main(*main_segment, *main_side)],
+            Sym { incoming, outgoing } => {
+                vec![main(*incoming, Side::In), main(*outgoing, Side::Out)]
+            }
+            Asym {
+                main_segment,
+                main_side,
+                attached_segments,
+            } => {
+                let mut entries = vec![main(*main_segment, *main_side)];
+                entries.extend(attached(attached_segments, main_side.switch()));
+                entries
+            }
+            Open {
+                open_side,
+                attached_segments,
+            } => attached(attached_segments, open_side.switch()),
+        }
+    }
+
+    /// Checks that the mode of this node is consistent with itself. The segments of the node are
+    /// not checked against the graph.
+    pub fn validate_mode(&self) -> Option<ModeViolation> {
+        match &self.mode {
+            Basic { .. } => None,
+            Sym { incoming, outgoing } => {
+                (incoming == outgoing).then_some(ModeViolation::SymSameSegment)
+            }
+            Asym {
+                main_segment,
+                attached_segments,
+                ..
+            } => {
+                if attached_segments.contains_segment(*main_segment) {
+                    return Some(ModeViolation::DuplicateSegment(*main_segment));
+                }
+                attached_segments.validate(self.no_lanes())
+            }
+            Open {
+                attached_segments, ..
+            } => {
+                if attached_segments.len() < 2 {
+                    return Some(ModeViolation::TooFewOpenSegments);
+                }
+                attached_segments.validate(self.no_lanes()).or_else(|| {
+                    let covers_node = attached_segments.is_continuous()
+                        && attached_segments.smallest() == 0
+                        && attached_segments.largest() + 1 == self.no_lanes();
+                    (!covers_node).then_some(ModeViolation::OpenGap)
+                })
+            }
+        }
+    }
+
     /// Returns if there is any possibility of snapping a road to this node.
     pub fn can_add_some_segment(&self) -> bool {
         match &self.mode {
@@ -409,9 +573,8 @@ impl LNode {
                 self.loc.pos +=
                     ((left_space as i8 - right_space as i8) as f32 / 2.0) * lane_width_dir;
 
-                let new_no_lanes = attached_segments.largest() + 1;
-
                 attached_segments.shift(-(left_space as i8));
+                let new_no_lanes = attached_segments.largest() + 1;
                 attached_segments.update_no_lanes(new_no_lanes);
                 self.node_type = NodeType::new(self.node_type.lane_width(), new_no_lanes);
                 self.mode = Open {
@@ -494,12 +657,34 @@ impl LNode {
 
     /// Constructs and returns the {`SnapConfig`}'s of this node, given the type of road that is
     /// trying to snap and the id of this node.
+    ///
+    /// If the number of lanes differ, the configs where the road attaches to some of the lanes of
+    /// this node are followed by the configs of a transition segment. These have the type of this
+    /// node, as a transition segment covers all lanes of this node and changes the number of lanes
+    /// along the segment instead. If the lane widths differ, only a transition segment can snap,
+    /// which requires the number of lanes to be the same.
     pub fn construct_snap_configs(&self, node_type: NodeType, node_id: NodeId) -> Vec<SnapConfig> {
-        // TODO in the future we should generate a transition segment probably
-        if self.node_type.lane_width_f32() != node_type.lane_width_f32() {
-            return vec![];
+        if self.node_type.lane_width() != node_type.lane_width() {
+            if self.no_lanes() != node_type.no_lanes() {
+                return vec![];
+            }
+            return self.construct_attaching_snap_configs(self.node_type, node_id);
         }
 
+        let mut configs = self.construct_attaching_snap_configs(node_type, node_id);
+        if self.no_lanes() != node_type.no_lanes() {
+            configs.append(&mut self.construct_attaching_snap_configs(self.node_type, node_id));
+        }
+        configs
+    }
+
+    /// Constructs the {`SnapConfig`}'s where a road of the given type attaches directly to the
+    /// lanes of this node.
+    fn construct_attaching_snap_configs(
+        &self,
+        node_type: NodeType,
+        node_id: NodeId,
+    ) -> Vec<SnapConfig> {
         let lane_width_dir = Vec3::from(self.loc.dir.right_hand()) * self.lane_width();
         let snap_no_lanes = node_type.no_lanes();
 
@@ -665,7 +850,7 @@ mod lanes {
 
         /// Returns the lane number of this node for which the lane given by `id` and
         /// `index` is located at.
-        pub fn _get_lane_from_segment_and_index(&self, id: SegmentId, index: u8) -> u8 {
+        pub fn get_lane_from_segment_and_index(&self, id: SegmentId, index: u8) -> u8 {
             for s in self.iter() {
                 if id == s.segment_id {
                     return s._smallest() + index;
@@ -674,7 +859,7 @@ mod lanes {
             panic!("Requested segment_id does not exist in node");
         }
 
-        pub fn _get_segment_at_index(&self, index: u8) -> Option<(SegmentId, u8)> {
+        pub fn get_segment_at_index(&self, index: u8) -> Option<(SegmentId, u8)> {
             for s in self.iter() {
                 if s._contains(index) {
                     let new_index = index - s._smallest();
@@ -696,7 +881,6 @@ mod lanes {
         }
 
         /// Returns true if the given segment_id is part of this lane map.
-        #[cfg(debug_assertions)]
         pub fn contains_segment(&self, segment_id: SegmentId) -> bool {
             for s in self.iter() {
                 if s.segment_id == segment_id {
@@ -706,6 +890,15 @@ mod lanes {
             false
         }
 
+        /// Makes the attached segment `old_id`, if any, refer to `new_id` instead.
+        pub fn replace_segment(&mut self, old_id: SegmentId, new_id: SegmentId) {
+            for s in self.iter_mut() {
+                if s.segment_id == old_id {
+                    s.segment_id = new_id;
+                }
+            }
+        }
+
         /// Returns the number of lanes that are closed by a segment being attached to that lane.
         fn no_lanes_closed(&self) -> u8 {
             self.iter().fold(0, |acc, s| acc + s.no_lanes())
@@ -783,6 +976,37 @@ mod lanes {
             self.retain(|s| s.segment_id != segment_id);
         }
 
+        /// Checks that the segments fit within the lanes of the node, and that they are stored in
+        /// order from left to right without overlapping.
+        pub fn validate(&self, node_no_lanes: u8) -> Option<ModeViolation> {
+            if self.is_empty() {
+                return Some(ModeViolation::NoAttachedSegments);
+            }
+            if self.no_lanes != node_no_lanes {
+                return Some(ModeViolation::LaneCountMismatch {
+                    lane_map: self.no_lanes,
+                    node: node_no_lanes,
+                });
+            }
+            for (i, s) in self.iter().enumerate() {
+                let snap_range = s.snap_range();
+                let is_contiguous = snap_range.windows(2).all(|w| w[1] == w[0] + 1);
+                if snap_range.len() != s.no_lanes() as usize || !is_contiguous {
+                    return Some(ModeViolation::InvalidSnapRange(s.segment_id));
+                }
+                if snap_range.smallest() < 0 || snap_range.largest() >= self.no_lanes as i8 {
+                    return Some(ModeViolation::OutOfBounds(s.segment_id));
+                }
+                if self[..i].iter().any(|o| o.segment_id == s.segment_id) {
+                    return Some(ModeViolation::DuplicateSegment(s.segment_id));
+                }
+                if i > 0 && snap_range.smallest() <= self[i - 1].snap_range.largest() {
+                    return Some(ModeViolation::Unordered(s.segment_id));
+                }
+            }
+            None
+        }
+
         /// Checks if this lane map has space for the given snap_range.
         pub fn fits_snap_range(&self, snap_range: &SnapRange) -> bool {
             for s in snap_range.iter() {
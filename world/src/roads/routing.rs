@@ -0,0 +1,237 @@
+use super::RoadGraph;
+
+use world_api::{LaneId, RoadManipulator, Route, RouteStep};
+
+use curves::CurveShared;
+use utils::id::{IdMap, NodeId, SafeMap, SegmentId};
+
+use glam::*;
+
+use std::cmp::Ordering;
+use std::collections::BinaryHeap;
+
+/// The lowest cost found so far of reaching a node, and the node and segment it was reached
+/// through.
+type Reached = (f32, Option<(NodeId, SegmentId)>);
+
+/// A node that has been reached by the search, ordered such that the node with the lowest
+/// estimated total cost is popped first from a {`BinaryHeap`}.
+struct Candidate {
+    estimate: f32,
+    node: NodeId,
+}
+
+impl PartialEq for Candidate {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for Candidate {}
+
+impl PartialOrd for Candidate {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Candidate {
+    fn cmp(&self, other: &Self) -> Ordering {
+        other.estimate.total_cmp(&self.estimate)
+    }
+}
+
+impl RoadGraph {
+    /// Finds the shortest route between two nodes using A*. The cost of a segment is the length of
+    /// its curve, and the straight line distance to `to` is used as the heuristic, which never
+    /// overestimates the remaining cost.
+    pub fn find_route(&self, from: NodeId, to: NodeId) -> Option<Route> {
+        let segments = self.find_segment_path(from, to)?;
+        Some(self.gen_route(segments))
+    }
+
+    /// Finds the shortest route between whatever is located at the two positions. Nodes take
+    /// precedence over segments.
+    pub fn find_route_between_positions(&self, from: Vec3, to: Vec3) -> Option<Route> {
+        let (from_node, first_segment) = match self.get_node_from_pos(from) {
+            Some(node) => (node, None),
+            None => {
+                let segment = self.get_segment_from_pos(from)?;
+                (self.get_segment_nodes(segment)[1], Some(segment))
+            }
+        };
+        let (to_node, last_segment) = match self.get_node_from_pos(to) {
+            Some(node) => (node, None),
+            None => {
+                let segment = self.get_segment_from_pos(to)?;
+                (self.get_segment_nodes(segment)[0], Some(segment))
+            }
+        };
+
+        // If both positions are on the same segment, the route only stays on it if `to` lies ahead
+        // of `from`. Otherwise it has to leave the segment and find its way back to the start of
+        // it.
+        if let Some(segment) = first_segment.filter(|s| Some(*s) == last_segment) {
+            let lsegment = self.get_lsegment(segment);
+            if lsegment.distance_along(from) <= lsegment.distance_along(to) {
+                return Some(self.gen_route(vec![segment]));
+            }
+        }
+
+        let path = self.find_segment_path(from_node, to_node)?;
+        let segments = first_segment
+            .into_iter()
+            .chain(path)
+            .chain(last_segment)
+            .collect();
+        Some(self.gen_route(segments))
+    }
+
+    /// Returns the segments of the shortest path from `from` to `to` in order.
+    fn find_segment_path(&self, from: NodeId, to: NodeId) -> Option<Vec<SegmentId>> {
+        let to_pos = self.get_node_loc(to)?.pos;
+        let heuristic = |node: NodeId| {
+            self.get_node_loc(node)
+                .map_or(0.0, |loc| (loc.pos - to_pos).length())
+        };
+        self.get_node_loc(from)?;
+
+        let mut reached: IdMap<NodeId, Reached, SafeMap> = IdMap::new();
+        let mut open = BinaryHeap::new();
+        reached.insert(from, (0.0, None));
+        open.push(Candidate {
+            estimate: heuristic(from),
+            node: from,
+        });
+
+        while let Some(Candidate { node, .. }) = open.pop() {
+            if node == to {
+                let mut segments = vec![];
+                let mut current = to;
+                while let Some((_, Some((previous, segment)))) = reached.get(current) {
+                    segments.push(*segment);
+                    current = *previous;
+                }
+                segments.reverse();
+                return Some(segments);
+            }
+
+            let (cost, _) = reached.get(node).expect("Open nodes have been reached");
+            for (next, segment) in self.get_forwards_ref(node).iter() {
+                let next_cost = cost + self.get_lsegment(*segment).curve().get_length();
+                if reached.contains_key(*next) {
+                    let (old_cost, _) = reached.get(*next).expect("The node has been reached");
+                    if old_cost <= next_cost {
+                        continue;
+                    }
+                }
+                reached.insert(*next, (next_cost, Some((node, *segment))));
+                open.push(Candidate {
+                    estimate: next_cost + heuristic(*next),
+                    node: *next,
+                });
+            }
+        }
+        None
+    }
+
+    /// Generates the route going through the given segments, choosing for each segment the lanes
+    /// that lead into the next segment.
+    fn gen_route(&self, segments: Vec<SegmentId>) -> Route {
+        let length = segments
+            .iter()
+            .map(|s| self.get_lsegment(*s).curve().get_length())
+            .sum();
+
+        let steps = segments
+            .iter()
+            .enumerate()
+            .map(|(i, segment)| {
+                let no_lanes = self
+                    .get_lsegment(*segment)
+                    .segment_type()
+                    .from_type()
+                    .no_lanes();
+                let lanes = match segments.get(i + 1) {
+                    Some(next) => (0..no_lanes)
+                        .filter(|lane| {
+                            self.get_lane_successors(LaneId::new(*segment, *lane))
+                                .iter()
+                                .any(|l| l.segment() == *next)
+                        })
+                        .collect(),
+                    None => (0..no_lanes).collect(),
+                };
+                RouteStep::new(*segment, lanes)
+            })
+            .collect();
+
+        Route::new(steps, length)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::test_utils::{add_road, add_straight_road, arc};
+    use crate::World;
+
+    use world_api::{RoadQuery, RouteQuery};
+
+    use curves::CurveShared;
+    use utils::id::SegmentId;
+    use utils::math::{DirXZ, Loc};
+
+    use glam::Vec3;
+
+    /// Returns the position the given fraction along the spine of the segment.
+    fn pos_along(world: &World, segment: SegmentId, fraction: f32) -> Vec3 {
+        let segment = world.get_segment(segment).unwrap();
+        let spine = segment.curve().get_spine();
+        spine[((spine.len() - 1) as f32 * fraction) as usize].pos
+    }
+
+    #[test]
+    fn same_segment_forward() {
+        let mut world = World::new();
+        let segment = add_straight_road(&mut world, Vec3::ZERO, Vec3::new(100.0, 0.0, 0.0));
+        let route = world
+            .find_route_between_positions(Vec3::new(30.0, 0.0, 0.0), Vec3::new(70.0, 0.0, 0.0))
+            .unwrap();
+        assert_eq!(route.segments(), vec![segment]);
+    }
+
+    #[test]
+    fn same_segment_backward_without_way_around() {
+        let mut world = World::new();
+        add_straight_road(&mut world, Vec3::ZERO, Vec3::new(100.0, 0.0, 0.0));
+        let route = world
+            .find_route_between_positions(Vec3::new(70.0, 0.0, 0.0), Vec3::new(30.0, 0.0, 0.0));
+        assert!(route.is_none());
+    }
+
+    #[test]
+    fn same_segment_backward_goes_around() {
+        let mut world = World::new();
+        let a = Vec3::ZERO;
+        let b = Vec3::new(0.0, 0.0, -100.0);
+        let first = add_road(&mut world, arc(Loc::new(a, DirXZ::from(Vec3::X)), b));
+        let second = add_road(&mut world, arc(Loc::new(b, DirXZ::from(Vec3::NEG_X)), a));
+        let segment = first[0];
+
+        let (ahead, behind) = (
+            pos_along(&world, segment, 0.75),
+            pos_along(&world, segment, 0.25),
+        );
+        let route = world.find_route_between_positions(ahead, behind).unwrap();
+        let expected: Vec<SegmentId> = first
+            .iter()
+            .chain(second.iter())
+            .copied()
+            .chain(std::iter::once(segment))
+            .collect();
+        assert_eq!(route.segments(), expected);
+
+        let route = world.find_route_between_positions(behind, ahead).unwrap();
+        assert_eq!(route.segments(), vec![segment]);
+    }
+}
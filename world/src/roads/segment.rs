@@ -1,9 +1,9 @@
-use world_api::{LSegmentBuilder, LSegmentBuilderType};
+use world_api::{LSegmentBuilder, SegmentType};
 
 use curves::{CurveShared, CurveSum};
 use utils::id::NodeId;
 
-use glam::Vec3;
+use glam::{Vec3, Vec3Swizzles};
 
 use serde::{Deserialize, Serialize};
 
@@ -12,38 +12,53 @@ use serde::{Deserialize, Serialize};
 // #################################################################################################
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct LSegment {
-    /// This field is used for checking if a position is inside this road segment.
-    ///
-    /// TODO: use smarter format than f32, such that width changes according to transition segments
-    /// once those are implemented.
-    width: f32,
+    /// The types of the ends of this segment. Used for checking if a position is inside this road
+    /// segment, and to regenerate the graphics of this segment.
+    segment_type: SegmentType,
     curve: CurveSum,
     from_node: NodeId,
     to_node: NodeId,
+    /// The segment is carried by a bridge, and may cross other segments that are far enough below
+    /// or above it.
+    bridge: bool,
 }
 
 impl LSegment {
-    fn new(width: f32, curve: CurveSum, from_node: NodeId, to_node: NodeId) -> Self {
+    fn new(
+        segment_type: SegmentType,
+        curve: CurveSum,
+        from_node: NodeId,
+        to_node: NodeId,
+        bridge: bool,
+    ) -> Self {
         LSegment {
-            width,
+            segment_type,
             curve,
             from_node,
             to_node,
+            bridge,
         }
     }
 
     pub fn from_builder(builder: LSegmentBuilder, from_node: NodeId, to_node: NodeId) -> Self {
+        let bridge = builder.is_bridge();
         let (node_config, curve) = builder.consume();
 
-        let width = match node_config {
-            LSegmentBuilderType::Same(node_type) => node_type.compute_width(),
-        };
+        Self::new(
+            node_config.segment_type(),
+            curve,
+            from_node,
+            to_node,
+            bridge,
+        )
+    }
 
-        Self::new(width, curve, from_node, to_node)
+    pub fn segment_type(&self) -> SegmentType {
+        self.segment_type
     }
 
-    pub fn width(&self) -> f32 {
-        self.width
+    pub fn curve(&self) -> &CurveSum {
+        &self.curve
     }
 
     pub fn get_from_node(&self) -> NodeId {
@@ -54,7 +69,46 @@ impl LSegment {
         self.to_node
     }
 
+    pub fn is_bridge(&self) -> bool {
+        self.bridge
+    }
+
+    /// Checks if the given position is inside this segment. For transition segments the width is
+    /// taken where the spine is closest to the position.
     pub fn contains_pos(&self, pos: Vec3) -> bool {
-        self.curve.contains_pos(pos, self.width())
+        let spine = self.curve.get_spine();
+        let closest = spine
+            .iter()
+            .map(|loc| (loc.pos - pos).xz().length_squared())
+            .enumerate()
+            .min_by(|(_, a), (_, b)| a.total_cmp(b))
+            .map_or(0, |(i, _)| i);
+        let t = closest as f32 / (spine.len() - 1).max(1) as f32;
+
+        self.curve.contains_pos(pos, self.segment_type.width_at(t))
+    }
+
+    /// Returns how far along the spine, in meters in the ground plane, the point of the spine
+    /// closest to the given position is.
+    pub fn distance_along(&self, pos: Vec3) -> f32 {
+        let pos = pos.xz();
+        let spine = self.curve.get_spine();
+        let mut walked = 0.0;
+        let mut closest = (f32::INFINITY, 0.0);
+        for pair in spine.windows(2) {
+            let (s0, s1) = (pair[0].pos.xz(), pair[1].pos.xz());
+            let d = s1 - s0;
+            let t = if d.length_squared() == 0.0 {
+                0.0
+            } else {
+                ((pos - s0).dot(d) / d.length_squared()).clamp(0.0, 1.0)
+            };
+            let distance = (s0 + d * t - pos).length();
+            if distance < closest.0 {
+                closest = (distance, walked + d.length() * t);
+            }
+            walked += d.length();
+        }
+        closest.1
     }
 }
@@ -0,0 +1,173 @@
+use super::RoadGraph;
+
+use world_api::{LaneWidth, NetworkStats, NodeType};
+
+use curves::{CurveKind, CurveShared};
+use utils::id::{IdSet, NodeId};
+
+/// Adds one to the count of the key, or inserts it if it has not been counted before.
+fn count<K: PartialEq>(counts: &mut Vec<(K, usize)>, key: K) {
+    match counts.iter_mut().find(|(k, _)| *k == key) {
+        Some((_, count)) => *count += 1,
+        None => counts.push((key, 1)),
+    }
+}
+
+impl RoadGraph {
+    /// Counts and measures the whole road network.
+    pub fn compute_stats(&self) -> NetworkStats {
+        let mut lane_km: Vec<(LaneWidth, f32)> = Vec::new();
+        let mut add_lanes = |node_type: NodeType, length: f32| {
+            let km = node_type.no_lanes() as f32 * length / 1000.0;
+            match lane_km
+                .iter_mut()
+                .find(|(lane_width, _)| *lane_width == node_type.lane_width())
+            {
+                Some((_, total)) => *total += km,
+                None => lane_km.push((node_type.lane_width(), km)),
+            }
+        };
+
+        let mut segments_by_curve = Vec::new();
+        let mut min_curve_radius: Option<f32> = None;
+        for segment in self.segment_map.values() {
+            let curve = segment.curve();
+            let segment_type = segment.segment_type();
+            let half_length = curve.get_length() / 2.0;
+            add_lanes(segment_type.from_type(), half_length);
+            add_lanes(segment_type.to_type(), half_length);
+
+            count(&mut segments_by_curve, curve.kind());
+            if curve.kind() != CurveKind::Straight {
+                let radius = curve.get_spine().compute_min_radius();
+                if radius.is_finite() {
+                    min_curve_radius = Some(min_curve_radius.map_or(radius, |r| r.min(radius)));
+                }
+            }
+        }
+
+        let mut nodes_by_mode = Vec::new();
+        for node in self.node_map.values() {
+            count(&mut nodes_by_mode, node.mode());
+        }
+
+        NetworkStats {
+            lane_km,
+            segments_by_curve,
+            nodes_by_mode,
+            min_curve_radius,
+            starting_dead_ends: self.starting_nodes.len(),
+            ending_dead_ends: self.ending_nodes.len(),
+            connected_components: self.count_connected_components(),
+        }
+    }
+
+    /// Returns the number of groups of nodes that are connected by segments, regardless of the
+    /// direction of the segments.
+    fn count_connected_components(&self) -> usize {
+        let mut visited: IdSet<NodeId> = IdSet::new();
+        let mut components = 0;
+        for start in self.node_map.keys() {
+            if visited.contains(start) {
+                continue;
+            }
+            visited.insert(start);
+            components += 1;
+            let mut stack = vec![start];
+            while let Some(node) = stack.pop() {
+                for neighbour in self.get_neighbour_nodes(node) {
+                    if !visited.contains(neighbour) {
+                        visited.insert(neighbour);
+                        stack.push(neighbour);
+                    }
+                }
+            }
+        }
+        components
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::test_utils::{add_road, arc, test_node_type};
+    use crate::World;
+
+    use world_api::{
+        LNodeBuilderType, LRoadBuilder, LSegmentBuilder, LaneWidth, NodeMode, NodeType,
+        RoadManipulator, RoadQuery,
+    };
+
+    use curves::{Curve, CurveKind, CurveShared, CurveSum, Straight};
+    use utils::math::{DirXZ, Loc};
+
+    use glam::Vec3;
+
+    /// Adds a straight road of two standard lanes from x = 0 to x = 100, followed by a transition
+    /// to two wide lanes that ends at x = 200.
+    fn add_widening_road(world: &mut World) {
+        let standard = test_node_type();
+        let wide = NodeType::new(LaneWidth::Wide, 2);
+        let points = [0.0, 100.0, 200.0].map(|x| Vec3::new(x, 0.0, 0.0));
+        let curves: Vec<CurveSum> = points
+            .windows(2)
+            .map(|p| Curve::<Straight>::from_free(p[0], p[1]).0.into())
+            .collect();
+        let nodes = vec![
+            LNodeBuilderType::new(curves[0].first(), standard),
+            LNodeBuilderType::new(curves[1].first(), standard),
+            LNodeBuilderType::new(curves[1].last(), wide),
+        ];
+        let mut curves = curves.into_iter();
+        let segments = vec![
+            LSegmentBuilder::new(standard, curves.next().unwrap()),
+            LSegmentBuilder::transition(standard, wide, curves.next().unwrap()),
+        ];
+        world
+            .add_road(LRoadBuilder::new(nodes, segments, false), standard)
+            .unwrap();
+    }
+
+    #[test]
+    fn stats_of_two_roads() {
+        let mut world = World::new();
+        add_widening_road(&mut world);
+        // A quarter circle with a radius of 60 meters, well away from the first road.
+        let start = Vec3::new(0.0, 0.0, 500.0);
+        let arc_segments = add_road(
+            &mut world,
+            arc(
+                Loc::new(start, DirXZ::from(Vec3::X)),
+                start + Vec3::new(60.0, 0.0, -60.0),
+            ),
+        );
+        let arc_length: f32 = arc_segments
+            .iter()
+            .map(|id| world.get_segment(*id).unwrap().curve().get_length())
+            .sum();
+
+        let stats = world.compute_stats();
+        assert_eq!(stats.connected_components, 2);
+        assert_eq!(stats.starting_dead_ends, 2);
+        assert_eq!(stats.ending_dead_ends, 2);
+        assert_eq!(stats.no_segments(), 3);
+        assert_eq!(stats.no_nodes(), 5);
+        assert!(stats.segments_by_curve.contains(&(CurveKind::Straight, 2)));
+        assert!(stats.nodes_by_mode.contains(&(NodeMode::Basic, 4)));
+        assert!(stats.nodes_by_mode.contains(&(NodeMode::Sym, 1)));
+        let radius = stats.min_curve_radius.unwrap();
+        assert!((radius - 60.0).abs() < 1.0);
+
+        // The transition counts 50 meters of two lanes towards each width.
+        let km = |lane_width| {
+            stats
+                .lane_km
+                .iter()
+                .find(|(w, _)| *w == lane_width)
+                .map(|(_, km)| *km)
+        };
+        let standard = 2.0 * (150.0 + arc_length) / 1000.0;
+        assert!((km(LaneWidth::Standard).unwrap() - standard).abs() < 1e-4);
+        assert!((km(LaneWidth::Wide).unwrap() - 0.1).abs() < 1e-4);
+        assert_eq!(km(LaneWidth::Narrow), None);
+    }
+}
@@ -0,0 +1,145 @@
+use super::RoadGraph;
+
+use world_api::{Side, SuperNodeType, SuperSnapConfig};
+
+use utils::id::{IdManager, IdMap, NodeId, SuperNodeId, UnsafeMap};
+use utils::math::Loc;
+
+use glam::*;
+use serde::{Deserialize, Serialize};
+
+/// A super node groups the two nodes at the same place of a bidirectional road. The road graph is
+/// not aware of super nodes.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+struct SuperNode {
+    super_type: SuperNodeType,
+    /// The location of the center of the super node, facing the driving direction of the forward
+    /// node.
+    loc: Loc,
+    forward: NodeId,
+    backward: NodeId,
+}
+
+/// Keeps track of the super nodes on top of a {`RoadGraph`}. Super nodes are never removed when
+/// the nodes they point to are removed. Instead a super node is simply ignored as long as any of
+/// its nodes do not exist. Since node ids are never reused, this means that undoing and redoing
+/// edits of the road graph automatically brings back the super nodes as well.
+#[derive(Serialize, Deserialize)]
+pub struct SuperNodes {
+    super_node_map: IdMap<SuperNodeId, SuperNode, UnsafeMap>,
+    id_manager: IdManager<SuperNodeId>,
+}
+
+impl Default for SuperNodes {
+    fn default() -> Self {
+        Self {
+            super_node_map: IdMap::new(),
+            id_manager: IdManager::new(),
+        }
+    }
+}
+
+impl SuperNodes {
+    /// Adds a super node consisting of the given nodes, unless a super node with these nodes
+    /// already exists. Returns the id of the super node.
+    pub fn add_super_node(
+        &mut self,
+        road_graph: &RoadGraph,
+        super_type: SuperNodeType,
+        forward: NodeId,
+        backward: NodeId,
+    ) -> SuperNodeId {
+        if let Some((id, _)) = self
+            .super_node_map
+            .iter()
+            .find(|(_, n)| n.forward == forward && n.backward == backward)
+        {
+            return id;
+        }
+
+        let forward_loc = road_graph
+            .get_node_loc(forward)
+            .expect("Super nodes can only be added for existing nodes");
+        let backward_loc = road_graph
+            .get_node_loc(backward)
+            .expect("Super nodes can only be added for existing nodes");
+        let loc = Loc::new((forward_loc.pos + backward_loc.pos) / 2.0, forward_loc.dir);
+
+        let id = self.id_manager.gen();
+        self.super_node_map.insert(
+            id,
+            SuperNode {
+                super_type,
+                loc,
+                forward,
+                backward,
+            },
+        );
+        id
+    }
+
+    /// Returns the {`SuperSnapConfig`} of the given super node, if the selected super node type
+    /// matches it exactly and both of its nodes can be snapped to in their entirety.
+    pub fn get_super_snap_config(
+        &self,
+        road_graph: &RoadGraph,
+        id: SuperNodeId,
+        super_type: SuperNodeType,
+    ) -> Option<SuperSnapConfig> {
+        let super_node = self.super_node_map.get(id);
+        if super_node.super_type != super_type {
+            return None;
+        }
+        let node_type = super_type.node_type();
+        let forward = road_graph.get_exact_snap_config(super_node.forward, node_type)?;
+        let backward = road_graph.get_exact_snap_config(super_node.backward, node_type)?;
+        if forward.side() == backward.side() {
+            return None;
+        }
+        Some(SuperSnapConfig::new(
+            id,
+            super_type,
+            super_node.loc,
+            forward,
+            backward,
+        ))
+    }
+
+    pub fn get_possible_super_snap_nodes(
+        &self,
+        road_graph: &RoadGraph,
+        side: Option<Side>,
+        super_type: SuperNodeType,
+    ) -> Vec<(SuperNodeId, Loc)> {
+        self.super_node_map
+            .keys()
+            .filter_map(|id| self.get_super_snap_config(road_graph, id, super_type))
+            .filter(|s| side.is_none_or(|side| side != s.side()))
+            .map(|s| (s.id(), Loc::new(s.pos(), s.dir())))
+            .collect()
+    }
+
+    pub fn get_super_snap_configs_closest_node(
+        &self,
+        road_graph: &RoadGraph,
+        ground_pos: Vec3,
+        super_type: SuperNodeType,
+    ) -> Option<(SuperNodeId, Vec<SuperSnapConfig>)> {
+        let mut closest_node: Option<(SuperSnapConfig, f32)> = None;
+        for id in self.super_node_map.keys() {
+            let Some(snap_config) = self.get_super_snap_config(road_graph, id, super_type) else {
+                continue;
+            };
+            let dist = (snap_config.pos() - ground_pos).length();
+            if let Some((_, old_dist)) = closest_node {
+                if old_dist < dist {
+                    continue;
+                }
+            }
+            if dist < super_type.compute_width() {
+                closest_node = Some((snap_config, dist));
+            }
+        }
+        closest_node.map(|(snap_config, _)| (snap_config.id(), vec![snap_config]))
+    }
+}
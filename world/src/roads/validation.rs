@@ -0,0 +1,309 @@
+//! Checks that the different parts of a {`RoadGraph`} agree with each other. The road graph stores
+//! the same information in several places, such as the references between nodes and the modes of
+//! the nodes, and a mistake in keeping these up to date otherwise only shows up as a panic much
+//! later.
+use super::RoadGraph;
+
+use world_api::Side;
+
+use curves::CurveShared;
+use utils::id::{NodeId, SegmentId};
+
+use thiserror::Error;
+
+/// Describes how the mode of a single node is inconsistent with itself.
+#[derive(Error, Debug, Clone, PartialEq)]
+pub enum ModeViolation {
+    #[error("the incoming and outgoing segment of the symmetric node are the same")]
+    SymSameSegment,
+
+    #[error("there are no attached segments")]
+    NoAttachedSegments,
+
+    #[error("the open node has fewer than two attached segments")]
+    TooFewOpenSegments,
+
+    #[error("the lane map has {lane_map} lanes, but the node has {node} lanes")]
+    LaneCountMismatch { lane_map: u8, node: u8 },
+
+    #[error("segment {0:?} is attached outside the lanes of the node")]
+    OutOfBounds(SegmentId),
+
+    #[error("the snap range of segment {0:?} does not match its number of lanes")]
+    InvalidSnapRange(SegmentId),
+
+    #[error("segment {0:?} overlaps or is placed left of the segment before it")]
+    Unordered(SegmentId),
+
+    #[error("the attached segments of the open node do not cover all its lanes")]
+    OpenGap,
+
+    #[error("segment {0:?} is attached more than once")]
+    DuplicateSegment(SegmentId),
+}
+
+/// A broken invariant of a {`RoadGraph`}.
+#[derive(Error, Debug, Clone, PartialEq)]
+pub enum GraphViolation {
+    #[error("Node {0:?} has no forward or backward references")]
+    MissingRefs(NodeId),
+
+    #[error("Node {0:?} does not exist, but has forward or backward references")]
+    DanglingRefs(NodeId),
+
+    #[error("Segment {segment:?} goes between nodes, but node {node:?} does not exist")]
+    MissingNode { segment: SegmentId, node: NodeId },
+
+    #[error("Segment {segment:?} is not in the {side:?} references of node {node:?}")]
+    MissingRef {
+        node: NodeId,
+        segment: SegmentId,
+        side: Side,
+    },
+
+    #[error("Node {node:?} references segment {segment:?}, which does not connect to it that way")]
+    InvalidRef { node: NodeId, segment: SegmentId },
+
+    #[error("Node {node:?} contains segment {segment:?}, which does not exist")]
+    MissingSegment { node: NodeId, segment: SegmentId },
+
+    #[error("Segment {segment:?} connects to node {node:?}, which does not contain it")]
+    SegmentNotInNode { node: NodeId, segment: SegmentId },
+
+    #[error("Node {node:?} contains segment {segment:?} on the wrong side")]
+    WrongSide { node: NodeId, segment: SegmentId },
+
+    #[error("The type of segment {segment:?} does not match how node {node:?} contains it")]
+    TypeMismatch { node: NodeId, segment: SegmentId },
+
+    #[error("The starting nodes disagree with node {0:?}")]
+    StartingMismatch(NodeId),
+
+    #[error("The ending nodes disagree with node {0:?}")]
+    EndingMismatch(NodeId),
+
+    #[error("Node {node:?} is inconsistent: {violation}")]
+    InvalidMode {
+        node: NodeId,
+        violation: ModeViolation,
+    },
+
+    #[error("The position or direction of node {0:?} is not finite")]
+    NonFiniteNode(NodeId),
+
+    #[error("The spine or length of segment {0:?} is not finite")]
+    NonFiniteSegment(SegmentId),
+
+    #[error("The minimum clearance {0} is negative or not finite")]
+    InvalidClearance(f32),
+}
+
+impl RoadGraph {
+    /// Checks every invariant of the road graph, and returns all the violations found. An empty
+    /// list means that the road graph is valid.
+    pub fn validate(&self) -> Vec<GraphViolation> {
+        let mut violations = vec![];
+        self.validate_refs(&mut violations);
+        self.validate_segments(&mut violations);
+        self.validate_nodes(&mut violations);
+        self.validate_geometry(&mut violations);
+        violations
+    }
+
+    /// Panics with every violation found, if the road graph is not valid. Called after every
+    /// mutation when the `validate-graph` feature is enabled.
+    #[cfg(feature = "validate-graph")]
+    pub fn assert_valid(&self) {
+        let violations = self.validate();
+        if !violations.is_empty() {
+            let lines: Vec<String> = violations.iter().map(|v| v.to_string()).collect();
+            panic!("The road graph is invalid:\n{}", lines.join("\n"));
+        }
+    }
+
+    /// Every node has forward and backward references, and every reference points to a segment
+    /// going the right way.
+    fn validate_refs(&self, violations: &mut Vec<GraphViolation>) {
+        for node in self.node_map.keys() {
+            if !self.forward_refs.contains_key(node) || !self.backward_refs.contains_key(node) {
+                violations.push(GraphViolation::MissingRefs(node));
+            }
+        }
+
+        let refs = [
+            (&self.forward_refs, Side::Out),
+            (&self.backward_refs, Side::In),
+        ];
+        for (refs, side) in refs {
+            for (node, leading_pairs) in refs.iter() {
+                if !self.node_map.contains_key(node) {
+                    violations.push(GraphViolation::DanglingRefs(node));
+                    continue;
+                }
+                for (other, segment) in leading_pairs.iter() {
+                    let valid = self.segment_map.contains_key(*segment) && {
+                        let [from, to] = self.get_segment_nodes(*segment);
+                        match side {
+                            Side::Out => from == node && to == *other,
+                            Side::In => to == node && from == *other,
+                        }
+                    };
+                    if !valid {
+                        violations.push(GraphViolation::InvalidRef {
+                            node,
+                            segment: *segment,
+                        });
+                    }
+                }
+            }
+        }
+    }
+
+    /// Every segment goes between existing nodes, and is referenced by both of them.
+    fn validate_segments(&self, violations: &mut Vec<GraphViolation>) {
+        for (segment, lsegment) in self.segment_map.iter() {
+            let ends = [
+                (lsegment.get_from_node(), Side::Out, &self.forward_refs),
+                (lsegment.get_to_node(), Side::In, &self.backward_refs),
+            ];
+            for (node, side, refs) in ends {
+                if !self.node_map.contains_key(node) {
+                    violations.push(GraphViolation::MissingNode { segment, node });
+                    continue;
+                }
+                if !self.get_lnode(node).contains_segment(segment) {
+                    violations.push(GraphViolation::SegmentNotInNode { node, segment });
+                }
+                if refs.contains_key(node) && !refs.get(node).iter().any(|(_, s)| *s == segment) {
+                    violations.push(GraphViolation::MissingRef {
+                        node,
+                        segment,
+                        side,
+                    });
+                }
+            }
+        }
+    }
+
+    /// The mode of every node is consistent with itself and with the segments it contains, and
+    /// the starting and ending nodes are exactly those that the nodes say they are.
+    fn validate_nodes(&self, violations: &mut Vec<GraphViolation>) {
+        for (node, lnode) in self.node_map.iter() {
+            if let Some(violation) = lnode.validate_mode() {
+                violations.push(GraphViolation::InvalidMode { node, violation });
+            }
+
+            for (segment, side, node_type) in lnode.get_segments() {
+                if !self.segment_map.contains_key(segment) {
+                    violations.push(GraphViolation::MissingSegment { node, segment });
+                    continue;
+                }
+                let lsegment = self.get_lsegment(segment);
+                let segment_type = lsegment.segment_type();
+                let (end_node, end_type) = match side {
+                    Side::In => (lsegment.get_to_node(), segment_type.to_type()),
+                    Side::Out => (lsegment.get_from_node(), segment_type.from_type()),
+                };
+                if end_node != node {
+                    violations.push(GraphViolation::WrongSide { node, segment });
+                } else if end_type != node_type {
+                    violations.push(GraphViolation::TypeMismatch { node, segment });
+                }
+            }
+
+            if self.starting_nodes.contains(node) != lnode.is_starting() {
+                violations.push(GraphViolation::StartingMismatch(node));
+            }
+            if self.ending_nodes.contains(node) != lnode.is_ending() {
+                violations.push(GraphViolation::EndingMismatch(node));
+            }
+        }
+
+        for node in self.starting_nodes.iter() {
+            if !self.node_map.contains_key(node) {
+                violations.push(GraphViolation::StartingMismatch(node));
+            }
+        }
+        for node in self.ending_nodes.iter() {
+            if !self.node_map.contains_key(node) {
+                violations.push(GraphViolation::EndingMismatch(node));
+            }
+        }
+    }
+
+    /// Every position, direction and length is finite. The other checks only look at how the
+    /// parts of the graph refer to each other, so without this a node at a height of NaN is valid.
+    fn validate_geometry(&self, violations: &mut Vec<GraphViolation>) {
+        for (node, lnode) in self.node_map.iter() {
+            let loc = lnode.loc();
+            if !loc.pos.is_finite() || !loc.dir.is_finite() {
+                violations.push(GraphViolation::NonFiniteNode(node));
+            }
+        }
+        for (segment, lsegment) in self.segment_map.iter() {
+            let curve = lsegment.curve();
+            let spine_finite = curve
+                .get_spine()
+                .iter()
+                .all(|loc| loc.pos.is_finite() && loc.dir.is_finite());
+            if !spine_finite || !curve.get_length().is_finite() {
+                violations.push(GraphViolation::NonFiniteSegment(segment));
+            }
+        }
+        if !self.min_clearance.is_finite() || self.min_clearance < 0.0 {
+            violations.push(GraphViolation::InvalidClearance(self.min_clearance));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::GraphViolation;
+    use crate::roads::node::LNode;
+    use crate::roads::segment::LSegment;
+    use crate::test_utils::{add_road, straight, test_node_type};
+    use crate::World;
+
+    use world_api::{LNodeBuilder, LSegmentBuilder, LaneMapConfig, RoadQuery};
+
+    use utils::math::Loc;
+
+    use glam::Vec3;
+
+    #[test]
+    fn non_finite_geometry_is_invalid() {
+        let mut world = World::new();
+        let mut curves = straight(Vec3::ZERO, Vec3::new(100.0, 0.0, 0.0));
+        curves.extend(straight(
+            Vec3::new(100.0, 0.0, 0.0),
+            Vec3::new(200.0, 0.0, 0.0),
+        ));
+        let segments = add_road(&mut world, curves);
+        assert!(world.validate().is_empty());
+
+        // Splitting a curve at NaN gives parts whose spine and length are NaN.
+        let segment = world.get_segment(segments[0]).unwrap();
+        let (from, to) = (segment.from_node(), segment.to_node());
+        let (curve, _) = segment.curve().split(f32::NAN);
+        let builder = LSegmentBuilder::new(test_node_type(), curve);
+        let graph = &mut world.road_graph;
+        *graph.segment_map.get_mut(segments[0]) = LSegment::from_builder(builder, from, to);
+
+        let loc = Loc::new(Vec3::new(100.0, f32::NAN, 0.0), Vec3::X.into());
+        let lane_map = LaneMapConfig::Sym {
+            incoming: segments[0],
+            outgoing: segments[1],
+        };
+        let builder = LNodeBuilder::new(loc, test_node_type());
+        *graph.node_map.get_mut(to) = LNode::from_builder(builder, lane_map);
+        graph.min_clearance = f32::NAN;
+
+        let violations = world.validate();
+        assert_eq!(violations.len(), 3);
+        assert!(violations.contains(&GraphViolation::NonFiniteSegment(segments[0])));
+        assert!(violations.contains(&GraphViolation::NonFiniteNode(to)));
+        assert!(violations
+            .iter()
+            .any(|v| matches!(v, GraphViolation::InvalidClearance(c) if c.is_nan())));
+    }
+}
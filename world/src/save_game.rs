@@ -0,0 +1,298 @@
+//! Defines how a {`World`} is written to and read from save game files. Every save game starts
+//! with a single line of ascii text, the header, that identifies the file, the version of the save
+//! game format and the encoding of the rest of the file. The header is followed by the encoded
+//! world.
+use super::{GraphViolation, World};
+
+use bincode::Options;
+use serde::{de::DeserializeOwned, Serialize};
+use thiserror::Error;
+
+use std::path::Path;
+
+/// Identifies a file as a hw-architect save game.
+const MAGIC: &str = "HWAS";
+
+/// The version of the save game format. This must be incremented whenever the serialized layout
+/// of {`World`} changes, such that old save games are rejected instead of being misread.
+pub const SAVE_VERSION: u16 = 10;
+
+/// The encodings that a save game can be written in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SaveFormat {
+    /// A compact binary encoding. This should be used for actual save games.
+    Binary,
+    /// A human readable encoding using RON. This is intended for debugging.
+    Ron,
+}
+
+impl SaveFormat {
+    fn tag(&self) -> &'static str {
+        match self {
+            SaveFormat::Binary => "bin",
+            SaveFormat::Ron => "ron",
+        }
+    }
+
+    fn from_tag(tag: &str) -> Option<Self> {
+        match tag {
+            "bin" => Some(SaveFormat::Binary),
+            "ron" => Some(SaveFormat::Ron),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Error, Debug)]
+pub enum SaveError {
+    /// The save game could not be read from or written to disk.
+    #[error("Could not access the save game file")]
+    Io(#[from] std::io::Error),
+
+    /// The file does not start with a valid save game header.
+    #[error("The file is not a hw-architect save game")]
+    InvalidHeader,
+
+    /// The save game was written with a version of the format that is not supported.
+    #[error("The save game has version {0}, but only version {SAVE_VERSION} is supported")]
+    UnsupportedVersion(u16),
+
+    /// The header names an encoding that does not exist.
+    #[error("The save game uses the unknown format {0}")]
+    UnknownFormat(String),
+
+    /// The world could not be encoded or decoded using the binary encoding.
+    #[error("The binary encoding failed: {0}")]
+    Binary(#[from] bincode::Error),
+
+    /// The world could not be encoded as RON.
+    #[error("The RON encoding failed: {0}")]
+    RonEncode(#[from] ron::Error),
+
+    /// The RON encoding of the world could not be decoded.
+    #[error("The RON decoding failed: {0}")]
+    RonDecode(#[from] ron::error::SpannedError),
+
+    /// The world was decoded, but its road graph breaks some of its invariants.
+    #[error("The road graph is invalid: {}", .0[0])]
+    InvalidGraph(Vec<GraphViolation>),
+}
+
+pub type SaveResult<T> = std::result::Result<T, SaveError>;
+
+/// The options used for the binary encoding. Varint encoding keeps ids and lane counts small.
+fn binary_options() -> impl Options {
+    bincode::DefaultOptions::new()
+}
+
+fn encode<T: Serialize>(value: &T, format: SaveFormat) -> SaveResult<Vec<u8>> {
+    let mut bytes = format!("{} {} {}\n", MAGIC, SAVE_VERSION, format.tag()).into_bytes();
+    match format {
+        SaveFormat::Binary => bytes.append(&mut binary_options().serialize(value)?),
+        SaveFormat::Ron => {
+            // Id maps contain long runs of empty slots, so arrays are kept on a single line.
+            let config = ron::ser::PrettyConfig::new().compact_arrays(true);
+            bytes.append(&mut ron::ser::to_string_pretty(value, config)?.into_bytes());
+        }
+    }
+    Ok(bytes)
+}
+
+fn decode<T: DeserializeOwned>(bytes: &[u8]) -> SaveResult<T> {
+    let header_end = bytes
+        .iter()
+        .position(|b| *b == b'\n')
+        .ok_or(SaveError::InvalidHeader)?;
+    let header = std::str::from_utf8(&bytes[..header_end]).map_err(|_| SaveError::InvalidHeader)?;
+    let payload = &bytes[header_end + 1..];
+
+    let [magic, version, tag] = header.split(' ').collect::<Vec<_>>()[..] else {
+        return Err(SaveError::InvalidHeader);
+    };
+    if magic != MAGIC {
+        return Err(SaveError::InvalidHeader);
+    }
+    let version: u16 = version.parse().map_err(|_| SaveError::InvalidHeader)?;
+    if version != SAVE_VERSION {
+        return Err(SaveError::UnsupportedVersion(version));
+    }
+    let format = SaveFormat::from_tag(tag).ok_or(SaveError::UnknownFormat(tag.to_string()))?;
+
+    match format {
+        SaveFormat::Binary => Ok(binary_options().deserialize(payload)?),
+        SaveFormat::Ron => Ok(ron::de::from_bytes(payload)?),
+    }
+}
+
+impl World {
+    /// Encodes this world as a save game, including the header.
+    pub fn to_save_bytes(&self, format: SaveFormat) -> SaveResult<Vec<u8>> {
+        encode(self, format)
+    }
+
+    /// Decodes a world from a save game. The format is read from the header. The road graph of
+    /// the world is validated, such that a corrupted save game is rejected here instead of
+    /// causing a panic later on. Data that is derived from the rest of the world is not saved,
+    /// and is rebuilt once the world is known to be valid.
+    pub fn from_save_bytes(bytes: &[u8]) -> SaveResult<Self> {
+        let mut world: World = decode(bytes)?;
+        let violations = world.validate();
+        if !violations.is_empty() {
+            return Err(SaveError::InvalidGraph(violations));
+        }
+        world.rebuild_derived();
+        Ok(world)
+    }
+
+    /// Writes this world to the file at `path`, replacing the file if it exists.
+    pub fn save(&self, path: impl AsRef<Path>, format: SaveFormat) -> SaveResult<()> {
+        let bytes = self.to_save_bytes(format)?;
+        std::fs::write(path, bytes)?;
+        Ok(())
+    }
+
+    /// Reads the world stored in the save game at `path`.
+    pub fn load(path: impl AsRef<Path>) -> SaveResult<Self> {
+        let bytes = std::fs::read(path)?;
+        Self::from_save_bytes(&bytes)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::add_straight_road;
+
+    use world_api::{IdGetter, RoadManipulator, RoadQuery, Tree, TreeManipulator};
+
+    use glam::Vec3;
+
+    fn test_world() -> World {
+        let mut world = World::new();
+        add_straight_road(
+            &mut world,
+            Vec3::new(0.0, 0.0, 0.0),
+            Vec3::new(100.0, 0.0, 0.0),
+        );
+        add_straight_road(
+            &mut world,
+            Vec3::new(100.0, 0.0, 0.0),
+            Vec3::new(200.0, 0.0, 0.0),
+        );
+        world.add_tree(Tree::new(Vec3::new(50.0, 0.0, 30.0)), 0);
+        world
+    }
+
+    fn assert_same_world(a: &World, b: &World) {
+        let segments = |w: &World| {
+            let mut segments: Vec<_> = w
+                .segments()
+                .map(|s| (s.id(), s.from_node(), s.to_node()))
+                .collect();
+            segments.sort();
+            segments
+        };
+        assert_eq!(segments(a), segments(b));
+        assert_eq!(a.nodes().count(), b.nodes().count());
+        assert_eq!(a.get_trees().len(), b.get_trees().len());
+        assert!(b.validate().is_empty());
+
+        // The lane graph is not saved, so this checks that it is rebuilt.
+        let lanes = |w: &World| {
+            let mut lanes: Vec<_> = w
+                .get_lanes()
+                .into_iter()
+                .map(|lane| (lane, w.get_lane_successors(lane).to_vec()))
+                .collect();
+            lanes.sort();
+            lanes
+        };
+        assert!(!lanes(a).is_empty());
+        assert_eq!(lanes(a), lanes(b));
+
+        // Neither are the spatial grids used for picking.
+        let picks = |w: &World| {
+            (
+                w.get_node_from_pos(Vec3::new(100.0, 0.0, 0.0)),
+                w.get_segment_from_pos(Vec3::new(50.0, 0.0, 0.0)),
+                w.get_tree_from_pos(Vec3::new(50.0, 0.0, 30.0)),
+            )
+        };
+        let (node, segment, tree) = picks(a);
+        assert!(node.is_some() && segment.is_some() && tree.is_some());
+        assert_eq!(picks(a), picks(b));
+    }
+
+    #[test]
+    fn round_trip_binary() {
+        let world = test_world();
+        let bytes = world.to_save_bytes(SaveFormat::Binary).unwrap();
+        assert!(bytes.starts_with(format!("{} {} bin\n", MAGIC, SAVE_VERSION).as_bytes()));
+        let loaded = World::from_save_bytes(&bytes).unwrap();
+        assert_same_world(&world, &loaded);
+    }
+
+    #[test]
+    fn round_trip_ron() {
+        let world = test_world();
+        let bytes = world.to_save_bytes(SaveFormat::Ron).unwrap();
+        assert!(bytes.starts_with(format!("{} {} ron\n", MAGIC, SAVE_VERSION).as_bytes()));
+        let loaded = World::from_save_bytes(&bytes).unwrap();
+        assert_same_world(&world, &loaded);
+    }
+
+    #[test]
+    fn rejects_bad_header() {
+        let bytes = test_world().to_save_bytes(SaveFormat::Binary).unwrap();
+        let payload = &bytes[bytes.iter().position(|b| *b == b'\n').unwrap()..];
+
+        let with_header = |header: &str| [header.as_bytes(), payload].concat();
+        assert!(matches!(
+            World::from_save_bytes(&with_header("HWXX 8 bin")),
+            Err(SaveError::InvalidHeader)
+        ));
+        assert!(matches!(
+            World::from_save_bytes(&with_header(&format!("{} 1 bin", MAGIC))),
+            Err(SaveError::UnsupportedVersion(1))
+        ));
+        assert!(matches!(
+            World::from_save_bytes(&with_header(&format!("{} {} zip", MAGIC, SAVE_VERSION))),
+            Err(SaveError::UnknownFormat(_))
+        ));
+        assert!(matches!(
+            World::from_save_bytes(b"no header"),
+            Err(SaveError::InvalidHeader)
+        ));
+    }
+
+    #[test]
+    fn rejects_truncated_binary() {
+        let bytes = test_world().to_save_bytes(SaveFormat::Binary).unwrap();
+        let truncated = &bytes[..bytes.len() / 2];
+        assert!(matches!(
+            World::from_save_bytes(truncated),
+            Err(SaveError::Binary(_))
+        ));
+    }
+
+    /// Ids in a damaged save game that are too large for their type, or far beyond the ids in use,
+    /// are reported as errors instead of panicking.
+    #[test]
+    fn rejects_corrupted_ids() {
+        let bytes = test_world().to_save_bytes(SaveFormat::Ron).unwrap();
+        let text = String::from_utf8(bytes).unwrap();
+        let start = text.find("starting_nodes: [").unwrap() + "starting_nodes: [".len();
+        let end = start + text[start..].find(']').unwrap();
+
+        let with_starting_nodes =
+            |nodes: &str| format!("{}{}{}", &text[..start], nodes, &text[end..]).into_bytes();
+        assert!(matches!(
+            World::from_save_bytes(&with_starting_nodes("0, 70000")),
+            Err(SaveError::RonDecode(_))
+        ));
+        assert!(matches!(
+            World::from_save_bytes(&with_starting_nodes("0, 9000")),
+            Err(SaveError::InvalidGraph(_))
+        ));
+    }
+}
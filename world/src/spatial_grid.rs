@@ -0,0 +1,97 @@
+//! A uniform grid over the ground plane, used to quickly find the elements of the world that are
+//! close to some position, instead of checking every element.
+use glam::Vec3;
+
+use std::collections::{BTreeMap, HashMap};
+
+/// The side length in meters of each cell in the grid.
+const CELL_SIZE: f32 = 32.0;
+
+type Cell = (i32, i32);
+
+/// Keeps track of which cells of the ground plane each element overlaps. Elements are added with
+/// an axis aligned bounding box, where only the x and z coordinates are used. Queries return
+/// every element whose bounding box might overlap the queried area, so the caller must still do
+/// the exact check.
+pub struct SpatialGrid<K: Ord> {
+    cells: HashMap<Cell, Vec<K>>,
+    /// The cells that each element has been added to.
+    element_cells: BTreeMap<K, Vec<Cell>>,
+}
+
+impl<K: Ord> Default for SpatialGrid<K> {
+    fn default() -> Self {
+        Self {
+            cells: HashMap::new(),
+            element_cells: BTreeMap::new(),
+        }
+    }
+}
+
+fn to_cell(pos: Vec3) -> Cell {
+    (
+        (pos.x / CELL_SIZE).floor() as i32,
+        (pos.z / CELL_SIZE).floor() as i32,
+    )
+}
+
+fn cells_in(min: Vec3, max: Vec3) -> impl Iterator<Item = Cell> {
+    let (min_x, min_z) = to_cell(min);
+    let (max_x, max_z) = to_cell(max);
+    (min_x..=max_x).flat_map(move |x| (min_z..=max_z).map(move |z| (x, z)))
+}
+
+impl<K: Copy + Ord> SpatialGrid<K> {
+    /// Adds an element with the given bounding box. If the element has already been added, its
+    /// bounding box is replaced.
+    pub fn insert(&mut self, key: K, min: Vec3, max: Vec3) {
+        self.remove(key);
+        let cells: Vec<Cell> = cells_in(min, max).collect();
+        for cell in cells.iter() {
+            self.cells.entry(*cell).or_default().push(key);
+        }
+        self.element_cells.insert(key, cells);
+    }
+
+    /// Adds an element covering everything within `radius` of `pos`.
+    pub fn insert_around(&mut self, key: K, pos: Vec3, radius: f32) {
+        self.insert(key, pos - Vec3::splat(radius), pos + Vec3::splat(radius));
+    }
+
+    /// Removes the element from the grid, if it has been added.
+    pub fn remove(&mut self, key: K) {
+        let Some(cells) = self.element_cells.remove(&key) else {
+            return;
+        };
+        for cell in cells {
+            if let Some(keys) = self.cells.get_mut(&cell) {
+                keys.retain(|k| *k != key);
+                if keys.is_empty() {
+                    self.cells.remove(&cell);
+                }
+            }
+        }
+    }
+
+    /// Returns the elements that might overlap the given bounding box, ordered by their id.
+    pub fn query(&self, min: Vec3, max: Vec3) -> Vec<K> {
+        let mut keys: Vec<K> = cells_in(min, max)
+            .filter_map(|cell| self.cells.get(&cell))
+            .flatten()
+            .copied()
+            .collect();
+        keys.sort_unstable();
+        keys.dedup();
+        keys
+    }
+
+    /// Returns the elements that might contain the given position, ordered by their id.
+    pub fn query_point(&self, pos: Vec3) -> Vec<K> {
+        self.query(pos, pos)
+    }
+
+    /// Returns the elements that might be within `radius` of `pos`, ordered by their id.
+    pub fn query_around(&self, pos: Vec3, radius: f32) -> Vec<K> {
+        self.query(pos - Vec3::splat(radius), pos + Vec3::splat(radius))
+    }
+}
@@ -0,0 +1,91 @@
+//! Helpers for building small worlds in tests.
+use super::World;
+
+use world_api::{
+    LNodeBuilderType, LRoadBuilder, LSegmentBuilder, LaneWidth, NodeType, RoadManipulator, Side,
+    SnapConfig,
+};
+
+use curves::{Circular, CompositeCurveSum, Curve, CurveShared, CurveSum, Straight};
+use utils::id::SegmentId;
+use utils::math::Loc;
+
+use glam::{Vec3, Vec3Swizzles};
+
+/// The node type of every road built by these helpers.
+pub fn test_node_type() -> NodeType {
+    NodeType::new(LaneWidth::Standard, 2)
+}
+
+/// Returns the straight curve from `from` to `to`.
+pub fn straight(from: Vec3, to: Vec3) -> Vec<CurveSum> {
+    let (curve, _) = Curve::<Straight>::from_free(from, to);
+    vec![curve.into()]
+}
+
+/// Returns the circular curves that start at `first` and end at `last_pos`.
+pub fn arc(first: Loc, last_pos: Vec3) -> Vec<CurveSum> {
+    let (curve, _) = Curve::<Circular>::from_first_locked(first, last_pos);
+    match CompositeCurveSum::from(curve) {
+        CompositeCurveSum::Single(curve) => vec![curve],
+        CompositeCurveSum::Double(curve1, curve2) => vec![curve1, curve2],
+    }
+}
+
+/// Adds a straight road driving from `from` to `to`, and returns its segment. Panics if the road
+/// cannot be added.
+pub fn add_straight_road(world: &mut World, from: Vec3, to: Vec3) -> SegmentId {
+    add_road(world, straight(from, to))[0]
+}
+
+/// Adds a road going through the given curves, and returns its segments. Panics if the road cannot
+/// be added.
+pub fn add_road(world: &mut World, curves: Vec<CurveSum>) -> Vec<SegmentId> {
+    try_add_road(world, curves, false).expect("The road could not be added")
+}
+
+/// Adds a road going through the given curves, and returns its segments, or {`None`} if the road
+/// cannot be added. Each end of the road is snapped to an existing node that lies at it and points
+/// the same way. The road is carried by a bridge if `bridge` is set.
+pub fn try_add_road(
+    world: &mut World,
+    curves: Vec<CurveSum>,
+    bridge: bool,
+) -> Option<Vec<SegmentId>> {
+    let node_type = test_node_type();
+    let find_snap = |loc: Loc, side: Side| -> Option<SnapConfig> {
+        let (_, configs) = world.get_snap_configs_closest_node(loc.pos, node_type)?;
+        configs.into_iter().find(|snap| {
+            snap.side() == side
+                && snap.dir() == loc.dir
+                && (snap.pos() - loc.pos).xz().length() < 0.01
+        })
+    };
+    let first = curves[0].first();
+    let last = curves[curves.len() - 1].last();
+    let first_snap = find_snap(first, Side::Out);
+    let last_snap = find_snap(last, Side::In);
+
+    let mut nodes = vec![match first_snap {
+        Some(snap) => LNodeBuilderType::Old(snap),
+        None => LNodeBuilderType::new(first, node_type),
+    }];
+    for curve in curves[..curves.len() - 1].iter() {
+        nodes.push(LNodeBuilderType::new(curve.last(), node_type));
+    }
+    nodes.push(match last_snap {
+        Some(snap) => LNodeBuilderType::Old(snap),
+        None => LNodeBuilderType::new(last, node_type),
+    });
+    let segments = curves
+        .into_iter()
+        .map(|curve| {
+            let mut segment = LSegmentBuilder::new(node_type, curve);
+            segment.set_bridge(bridge);
+            segment
+        })
+        .collect();
+    let road = LRoadBuilder::new(nodes, segments, false);
+    let (_, segments) = world.add_road(road, node_type).ok()?;
+    Some(segments)
+}